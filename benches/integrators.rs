@@ -4,8 +4,14 @@
 //! - Performance (speed/throughput) - Lower time is better
 //! - Accuracy (error vs analytical solutions) - Lower values are better
 //! - Convergence order (error reduction with smaller timesteps) - Lower deviation is better
+//! - System convergence order (all bodies stepped together) - Lower deviation is better
+//! - Kepler convergence order (higher-order integrators on an eccentric orbit) - Lower deviation is better
+//! - Tolerance accuracy (error-controlled integrators at several tolerances) - Lower error is better
 //! - Stability (energy conservation over long simulations) - Lower drift is better
+//! - Baseline energy drift (Dormand-Prince against symplectic methods) - Lower drift is better
+//! - Planetary energy error (Wisdom-Holman against Cartesian methods) - Lower error is better
 //! - Work-precision (accuracy for different timesteps) - Lower error is better
+//! - Equal-cost accuracy (accuracy for the same force evaluations) - Lower error is better
 //! - Real N-body scenarios (performance with octree) - Lower time is better
 //!
 //! Note: Accuracy benchmarks report error values as durations (scaled by 1e9)
@@ -15,7 +21,9 @@ use criterion::{BenchmarkId, Criterion, PlotConfiguration, criterion_group, crit
 use std::hint::black_box;
 
 extern crate stardrift;
-use stardrift::physics::integrators::{AccelerationField, Integrator};
+use stardrift::physics::integrators::{
+    AccelerationField, Integrator, IntegratorParameters, SystemAccelerationField,
+};
 use stardrift::physics::math::{Scalar, Vector};
 use stardrift::physics::octree::{Octree, OctreeBody};
 
@@ -79,6 +87,79 @@ impl<'a> AccelerationField for NBodyAcceleration<'a> {
     }
 }
 
+/// Direct-summation gravity for a small system of point masses (G = 1)
+///
+/// Exercises `Integrator::step_system`, where every stage sees the other
+/// bodies at their intermediate positions, and provides exact jerks for
+/// Hermite methods.
+struct DirectSummation {
+    pub masses: Vec<Scalar>,
+    positions: Vec<Vector>,
+    velocities: Vec<Vector>,
+}
+
+impl DirectSummation {
+    pub fn new(masses: Vec<Scalar>) -> Self {
+        Self {
+            positions: vec![Vector::ZERO; masses.len()],
+            velocities: vec![Vector::ZERO; masses.len()],
+            masses,
+        }
+    }
+}
+
+impl SystemAccelerationField for DirectSummation {
+    fn update(&mut self, positions: &[Vector]) {
+        self.positions.copy_from_slice(positions);
+    }
+
+    fn update_with_velocities(&mut self, positions: &[Vector], velocities: &[Vector]) {
+        self.positions.copy_from_slice(positions);
+        self.velocities.copy_from_slice(velocities);
+    }
+
+    fn at_with_jerk(&self, index: usize, position: Vector, velocity: Vector) -> (Vector, Vector) {
+        self.positions
+            .iter()
+            .zip(&self.velocities)
+            .zip(&self.masses)
+            .enumerate()
+            .filter(|&(other, _)| other != index)
+            .fold(
+                (Vector::ZERO, Vector::ZERO),
+                |(acceleration, jerk), (_, ((&other_position, &other_velocity), &mass))| {
+                    let offset = other_position - position;
+                    let relative_velocity = other_velocity - velocity;
+                    let r = offset.length();
+                    let r3 = r * r * r;
+                    let radial = offset.dot(relative_velocity) / (r * r);
+                    (
+                        acceleration + offset * (mass / r3),
+                        jerk + (relative_velocity - offset * (3.0 * radial)) * (mass / r3),
+                    )
+                },
+            )
+    }
+
+    fn at(&self, index: usize, position: Vector) -> Vector {
+        self.positions
+            .iter()
+            .zip(&self.masses)
+            .enumerate()
+            .filter(|&(other, _)| other != index)
+            .map(|(_, (&other_position, &mass))| {
+                let offset = other_position - position;
+                let r = offset.length();
+                offset * (mass / (r * r * r))
+            })
+            .sum()
+    }
+
+    fn gravitational_parameters(&self) -> Option<&[Scalar]> {
+        Some(&self.masses)
+    }
+}
+
 // =============================================================================
 // Helper Functions and Test Scenarios
 // =============================================================================
@@ -91,7 +172,7 @@ fn get_integrators() -> Vec<(String, Box<dyn Integrator>)> {
 
     // Get all available integrators from the registry
     for name in registry.list_available() {
-        if let Ok(integrator) = registry.create(&name) {
+        if let Ok(integrator) = registry.create(&name, &IntegratorParameters::new()) {
             integrators.push((name, integrator));
         }
     }
@@ -106,7 +187,7 @@ fn get_integrators_with_order() -> Vec<(String, Box<dyn Integrator>, usize)> {
     let mut integrators = Vec::new();
 
     for name in registry.list_available() {
-        if let Ok(integrator) = registry.create(&name) {
+        if let Ok(integrator) = registry.create(&name, &IntegratorParameters::new()) {
             let order = integrator.convergence_order();
             integrators.push((name, integrator, order));
         }
@@ -264,6 +345,220 @@ fn bench_convergence_order(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_system_convergence_order(c: &mut Criterion) {
+    // Verifies that integrators keep their convergence order when all bodies are
+    // advanced together through `step_system`, using an equal-mass binary on a
+    // circular orbit whose exact solution is known
+    let mut group = c.benchmark_group("system_convergence_order");
+    group.sample_size(10);
+    group
+        .plot_config(PlotConfiguration::default().summary_scale(criterion::AxisScale::Logarithmic));
+
+    let integrators = get_integrators_with_order();
+    let timesteps = vec![0.1, 0.05, 0.025, 0.0125];
+
+    // Unit masses one unit apart orbit their barycenter at ω = sqrt(G·M/d³)
+    let omega: Scalar = 2.0_f64.sqrt();
+    let exact_position =
+        |t: Scalar| Vector::new(0.5 * (omega * t).cos(), 0.5 * (omega * t).sin(), 0.0);
+
+    for (name, integrator, expected_order) in &integrators {
+        group.bench_function(name.as_str(), |b| {
+            b.iter_custom(|iters| {
+                let mut total_order_error = 0.0;
+
+                for _ in 0..iters {
+                    let mut errors = Vec::new();
+
+                    for &dt in &timesteps {
+                        let mut field = DirectSummation::new(vec![1.0, 1.0]);
+                        let mut positions = vec![exact_position(0.0), -exact_position(0.0)];
+                        let mut velocities = vec![
+                            Vector::new(0.0, 0.5 * omega, 0.0),
+                            Vector::new(0.0, -0.5 * omega, 0.0),
+                        ];
+                        let steps = (1.0 / dt) as usize; // Simulate for 1 time unit
+
+                        for _ in 0..steps {
+                            integrator.step_system(&mut positions, &mut velocities, &mut field, dt);
+                        }
+
+                        let t = dt * steps as Scalar;
+                        errors.push((positions[0] - exact_position(t)).length());
+                    }
+
+                    let orders: Vec<Scalar> = errors
+                        .windows(2)
+                        .filter(|pair| pair[0] > 1e-10 && pair[1] > 1e-10)
+                        .map(|pair| (pair[0] / pair[1]).log2())
+                        .collect();
+
+                    if !orders.is_empty() {
+                        let avg_order = orders.iter().sum::<Scalar>() / orders.len() as Scalar;
+                        total_order_error += (avg_order - *expected_order as Scalar).abs();
+                    } else {
+                        total_order_error += 0.01;
+                    }
+                }
+
+                // Return average convergence order error as Duration
+                let avg_order_error = total_order_error / iters as f64;
+                std::time::Duration::from_nanos((avg_order_error * 1e9) as u64)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_kepler_convergence_order(c: &mut Criterion) {
+    // Verifies convergence order on an eccentric Kepler orbit for the higher-order
+    // integrators, whose harmonic oscillator errors reach round-off before their
+    // order can be measured
+    let mut group = c.benchmark_group("kepler_convergence_order");
+    group.sample_size(10);
+    group
+        .plot_config(PlotConfiguration::default().summary_scale(criterion::AxisScale::Logarithmic));
+
+    let integrators: Vec<_> = get_integrators_with_order()
+        .into_iter()
+        .filter(|(_, _, order)| *order >= 4)
+        .collect();
+    let steps_per_orbit = vec![64, 128, 256, 512];
+
+    // Orbit with a = 1 and μ = 1, so the period is 2π, starting at pericenter
+    let mu: Scalar = 1.0;
+    let eccentricity: Scalar = 0.5;
+    let kepler = CentralForce { mu };
+    let period = 2.0 * PI;
+
+    // Exact position at time t from Kepler's equation M = E - e·sin(E)
+    let exact_position = |t: Scalar| {
+        let mean_anomaly = t;
+        let mut eccentric_anomaly = mean_anomaly;
+        for _ in 0..50 {
+            eccentric_anomaly -=
+                (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+                    / (1.0 - eccentricity * eccentric_anomaly.cos());
+        }
+        Vector::new(
+            eccentric_anomaly.cos() - eccentricity,
+            (1.0 - eccentricity * eccentricity).sqrt() * eccentric_anomaly.sin(),
+            0.0,
+        )
+    };
+
+    for (name, integrator, expected_order) in &integrators {
+        group.bench_function(name.as_str(), |b| {
+            b.iter_custom(|iters| {
+                let mut total_order_error = 0.0;
+
+                for _ in 0..iters {
+                    let mut errors = Vec::new();
+
+                    for &steps in &steps_per_orbit {
+                        let dt = period / steps as Scalar;
+                        let mut position = Vector::new(1.0 - eccentricity, 0.0, 0.0);
+                        let mut velocity = Vector::new(
+                            0.0,
+                            (mu * (1.0 + eccentricity) / (1.0 - eccentricity)).sqrt(),
+                            0.0,
+                        );
+
+                        for _ in 0..steps {
+                            integrator.step(&mut position, &mut velocity, &kepler, dt);
+                        }
+
+                        errors.push((position - exact_position(period)).length());
+                    }
+
+                    let orders: Vec<Scalar> = errors
+                        .windows(2)
+                        .filter(|pair| pair[0] > 1e-12 && pair[1] > 1e-12)
+                        .map(|pair| (pair[0] / pair[1]).log2())
+                        .collect();
+
+                    if !orders.is_empty() {
+                        let avg_order = orders.iter().sum::<Scalar>() / orders.len() as Scalar;
+                        total_order_error += (avg_order - *expected_order as Scalar).abs();
+                    } else {
+                        total_order_error += 0.01;
+                    }
+                }
+
+                // Return average convergence order error as Duration
+                let avg_order_error = total_order_error / iters as f64;
+                std::time::Duration::from_nanos((avg_order_error * 1e9) as u64)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_tolerance_accuracy(c: &mut Criterion) {
+    // Measures how closely the integrators with their own error control meet
+    // their tolerance, over one period of an eccentric Kepler orbit taken in
+    // calls far longer than the steps they choose
+    let mut group = c.benchmark_group("tolerance_accuracy");
+    group.sample_size(10);
+    group
+        .plot_config(PlotConfiguration::default().summary_scale(criterion::AxisScale::Logarithmic));
+
+    use stardrift::physics::integrators::registry::IntegratorRegistry;
+    let registry = IntegratorRegistry::new().with_standard_integrators();
+
+    // Orbit with a = 1 and μ = 1, so the period is 2π, starting at pericenter
+    let mu: Scalar = 1.0;
+    let eccentricity: Scalar = 0.5;
+    let kepler = CentralForce { mu };
+    let calls = 16;
+    let dt = 2.0 * PI / calls as Scalar;
+    let tolerances = vec![1e-6, 1e-8, 1e-10, 1e-12];
+
+    for name in ["bulirsch_stoer", "dormand_prince"] {
+        for &tolerance in &tolerances {
+            let mut parameters = IntegratorParameters::new();
+            parameters.insert("tolerance".to_string(), tolerance.into());
+            if name == "dormand_prince" {
+                parameters.insert("adaptive".to_string(), true.into());
+            }
+            let integrator = registry.create(name, &parameters).expect("valid tolerance");
+
+            group.bench_function(
+                BenchmarkId::new(name, format!("tolerance_{tolerance:e}")),
+                |b| {
+                    b.iter_custom(|iters| {
+                        let mut total_error = 0.0;
+
+                        for _ in 0..iters {
+                            let start = Vector::new(1.0 - eccentricity, 0.0, 0.0);
+                            let mut position = start;
+                            let mut velocity = Vector::new(
+                                0.0,
+                                (mu * (1.0 + eccentricity) / (1.0 - eccentricity)).sqrt(),
+                                0.0,
+                            );
+
+                            for _ in 0..calls {
+                                integrator.step(&mut position, &mut velocity, &kepler, dt);
+                            }
+
+                            total_error += (position - start).length();
+                        }
+
+                        // Return average position error as Duration
+                        let avg_error = total_error / iters as f64;
+                        std::time::Duration::from_nanos((avg_error * 1e9) as u64)
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
 // =============================================================================
 // Stability Benchmarks (Conservation Properties)
 // =============================================================================
@@ -379,6 +674,153 @@ fn bench_kepler_orbit(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_baseline_energy_drift(c: &mut Criterion) {
+    // Compares the secular energy drift of the non-symplectic Dormand-Prince
+    // baseline with the bounded energy error of symplectic methods, over many
+    // periods of an eccentric Kepler orbit
+    let mut group = c.benchmark_group("baseline_energy_drift");
+    group.sample_size(10);
+    group
+        .plot_config(PlotConfiguration::default().summary_scale(criterion::AxisScale::Logarithmic));
+
+    use stardrift::physics::integrators::registry::IntegratorRegistry;
+    let registry = IntegratorRegistry::new().with_standard_integrators();
+
+    // Orbit with a = 1 and μ = 1, so the period is 2π, starting at pericenter
+    let mu: Scalar = 1.0;
+    let eccentricity: Scalar = 0.5;
+    let kepler = CentralForce { mu };
+    let orbits = 100;
+    let steps_per_orbit = 256;
+    let dt = 2.0 * PI / steps_per_orbit as Scalar;
+    let initial_energy = -mu / 2.0;
+
+    for name in ["dormand_prince", "velocity_verlet", "pefrl"] {
+        let integrator = registry
+            .create(name, &IntegratorParameters::new())
+            .expect("standard integrator");
+
+        group.bench_function(name, |b| {
+            b.iter_custom(|iters| {
+                let mut total_energy_error = 0.0;
+
+                for _ in 0..iters {
+                    let mut position = Vector::new(1.0 - eccentricity, 0.0, 0.0);
+                    let mut velocity = Vector::new(
+                        0.0,
+                        (mu * (1.0 + eccentricity) / (1.0 - eccentricity)).sqrt(),
+                        0.0,
+                    );
+
+                    for _ in 0..orbits * steps_per_orbit {
+                        integrator.step(&mut position, &mut velocity, &kepler, dt);
+                    }
+
+                    let final_energy = 0.5 * velocity.length_squared() - mu / position.length();
+                    total_energy_error += ((final_energy - initial_energy) / initial_energy).abs();
+                }
+
+                // Return average relative energy error as Duration
+                let avg_error = total_energy_error / iters as f64;
+                std::time::Duration::from_nanos((avg_error * 1e9) as u64)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_planetary_energy_error(c: &mut Criterion) {
+    // Compares the energy error of the Wisdom-Holman splitting with Cartesian
+    // integrators at the same timestep, for two giant planets around a star
+    let mut group = c.benchmark_group("planetary_energy_error");
+    group.sample_size(10);
+    group
+        .plot_config(PlotConfiguration::default().summary_scale(criterion::AxisScale::Logarithmic));
+
+    use stardrift::physics::integrators::registry::IntegratorRegistry;
+    let registry = IntegratorRegistry::new().with_standard_integrators();
+
+    // Jupiter and Saturn analogues on circular orbits around a unit mass
+    // (G = 1), with 40 steps per inner orbit for 100 inner orbits
+    let masses = vec![1.0, 1e-3, 3e-4];
+    let radii: [Scalar; 2] = [5.2, 9.5];
+    let inner_period = 2.0 * PI * radii[0].powf(1.5);
+    let steps_per_orbit = 40;
+    let orbits = 100;
+    let dt = inner_period / steps_per_orbit as Scalar;
+
+    let energy = |positions: &[Vector], velocities: &[Vector]| -> Scalar {
+        let mut energy = 0.0;
+        for (i, (&mass, (&position, &velocity))) in masses
+            .iter()
+            .zip(positions.iter().zip(velocities))
+            .enumerate()
+        {
+            energy += 0.5 * mass * velocity.length_squared();
+            for (&other_mass, &other_position) in masses[..i].iter().zip(positions) {
+                energy -= mass * other_mass / (position - other_position).length();
+            }
+        }
+        energy
+    };
+
+    let mut democratic = IntegratorParameters::new();
+    democratic.insert("coordinates".to_string(), "democratic_heliocentric".into());
+    let cases = [
+        (
+            "wisdom_holman",
+            "wisdom_holman",
+            IntegratorParameters::new(),
+        ),
+        ("wisdom_holman_democratic", "wisdom_holman", democratic),
+        (
+            "velocity_verlet",
+            "velocity_verlet",
+            IntegratorParameters::new(),
+        ),
+        ("pefrl", "pefrl", IntegratorParameters::new()),
+    ];
+
+    for (label, name, parameters) in cases {
+        let integrator = registry
+            .create(name, &parameters)
+            .expect("standard integrator");
+
+        group.bench_function(label, |b| {
+            b.iter_custom(|iters| {
+                let mut total_energy_error = 0.0;
+
+                for _ in 0..iters {
+                    let mut field = DirectSummation::new(masses.clone());
+                    let mut positions = vec![Vector::ZERO];
+                    let mut velocities = vec![Vector::ZERO];
+                    for (i, &radius) in radii.iter().enumerate() {
+                        let phase = 2.0 * i as Scalar;
+                        let speed = (1.0 / radius).sqrt();
+                        positions.push(Vector::new(phase.cos(), phase.sin(), 0.0) * radius);
+                        velocities.push(Vector::new(-phase.sin(), phase.cos(), 0.0) * speed);
+                    }
+                    let initial_energy = energy(&positions, &velocities);
+
+                    for _ in 0..orbits * steps_per_orbit {
+                        integrator.step_system(&mut positions, &mut velocities, &mut field, dt);
+                    }
+
+                    let final_energy = energy(&positions, &velocities);
+                    total_energy_error += ((final_energy - initial_energy) / initial_energy).abs();
+                }
+
+                // Return average relative energy error as Duration
+                let avg_error = total_energy_error / iters as f64;
+                std::time::Duration::from_nanos((avg_error * 1e9) as u64)
+            });
+        });
+    }
+
+    group.finish();
+}
+
 // =============================================================================
 // Work-Precision Benchmarks (Cost vs Accuracy)
 // =============================================================================
@@ -435,6 +877,73 @@ fn bench_work_precision(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_equal_cost_accuracy(c: &mut Criterion) {
+    // Compares accuracy at equal cost rather than equal timestep: each
+    // integrator gets the same budget of force evaluations per orbit, so its
+    // timestep grows with its force evaluations per step
+    let mut group = c.benchmark_group("equal_cost_accuracy");
+    group.sample_size(10);
+    group
+        .plot_config(PlotConfiguration::default().summary_scale(criterion::AxisScale::Logarithmic));
+
+    // Evaluation budgets per orbit, divisible by every fixed per-step cost
+    // of the standard integrators
+    let budgets = [756, 1512, 3024];
+
+    // Orbit with a = 1 and μ = 1, so the period is 2π, starting at pericenter
+    let mu: Scalar = 1.0;
+    let eccentricity: Scalar = 0.5;
+    let kepler = CentralForce { mu };
+    let initial_position = Vector::new(1.0 - eccentricity, 0.0, 0.0);
+    let initial_velocity = Vector::new(
+        0.0,
+        (mu * (1.0 + eccentricity) / (1.0 - eccentricity)).sqrt(),
+        0.0,
+    );
+
+    // Integrators whose cost varies with the step are left out, since their
+    // timestep does not fix their cost
+    let integrators = get_integrators()
+        .into_iter()
+        .filter(|(_, integrator)| integrator.force_evaluations().is_fixed());
+
+    for (name, integrator) in integrators {
+        let evaluations = integrator.force_evaluations().minimum();
+
+        for budget in budgets {
+            let steps = budget / evaluations;
+            let dt = 2.0 * PI / steps as Scalar;
+
+            group.bench_function(
+                BenchmarkId::new(name.as_str(), format!("evals_{budget}")),
+                |b| {
+                    b.iter_custom(|iters| {
+                        let mut total_error = 0.0;
+
+                        for _ in 0..iters {
+                            let mut position = initial_position;
+                            let mut velocity = initial_velocity;
+
+                            for _ in 0..steps {
+                                integrator.step(&mut position, &mut velocity, &kepler, dt);
+                            }
+
+                            // After one period the orbit returns to pericenter
+                            total_error += (position - initial_position).length();
+                        }
+
+                        // Return average position error as Duration
+                        let avg_error = total_error / iters as f64;
+                        std::time::Duration::from_nanos((avg_error * 1e9) as u64)
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
 // =============================================================================
 // Real N-Body Benchmarks
 // =============================================================================
@@ -456,6 +965,7 @@ fn bench_nbody_realistic(c: &mut Criterion) {
 
         bodies.push(OctreeBody {
             position: Vector::new(radius * angle.cos(), radius * angle.sin(), z),
+            velocity: Vector::ZERO,
             mass: 1.0 + (i as Scalar) * 0.015, // Mass from 0.5 to 2.0
             entity: bevy::ecs::entity::Entity::from_raw_u32(i as u32).unwrap(),
         });
@@ -502,11 +1012,24 @@ fn bench_nbody_realistic(c: &mut Criterion) {
 
 criterion_group!(performance, bench_integrator_performance);
 
-criterion_group!(accuracy, bench_integrator_accuracy, bench_convergence_order);
-
-criterion_group!(stability, bench_integrator_stability, bench_kepler_orbit);
-
-criterion_group!(precision, bench_work_precision);
+criterion_group!(
+    accuracy,
+    bench_integrator_accuracy,
+    bench_convergence_order,
+    bench_system_convergence_order,
+    bench_kepler_convergence_order,
+    bench_tolerance_accuracy
+);
+
+criterion_group!(
+    stability,
+    bench_integrator_stability,
+    bench_kepler_orbit,
+    bench_baseline_energy_drift,
+    bench_planetary_energy_error
+);
+
+criterion_group!(precision, bench_work_precision, bench_equal_cost_accuracy);
 
 criterion_group!(realistic, bench_nbody_realistic);
 
@@ -3,8 +3,10 @@ use rand::Rng;
 use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 use stardrift::config::SimulationConfig;
+use stardrift::physics::gravity::{DirectSummation, FastMultipole, GravitySolver, ParticleMesh};
+use stardrift::physics::linear_octree::LinearOctree;
 use stardrift::physics::math::{Scalar, Vector};
-use stardrift::physics::octree::{Octree, OctreeBody};
+use stardrift::physics::octree::{MultipoleOrder, Octree, OctreeBody, OpeningCriterion};
 use std::f64::consts;
 use std::hint::black_box;
 
@@ -28,6 +30,7 @@ fn generate_test_bodies_spherical(count: usize, seed: u64, radius: Scalar) -> Ve
         let mass = rng.random_range(1.0..100.0);
         bodies.push(OctreeBody {
             position,
+            velocity: Vector::ZERO,
             mass,
             entity: bevy::ecs::entity::Entity::from_raw_u32(i as u32).unwrap(),
         });
@@ -63,6 +66,20 @@ fn bench_construction_scaling(c: &mut Criterion) {
                 black_box(octree);
             });
         });
+
+        // The linear octree keeps its buffers between builds, as it does
+        // from one tick to the next in the simulation
+        let mut linear = LinearOctree::new(
+            physics.octree_theta,
+            physics.force_calculation_min_distance,
+            physics.force_calculation_max_force,
+        );
+        group.bench_with_input(BenchmarkId::new("linear", count), &count, |b, _| {
+            b.iter(|| {
+                linear.build(black_box(&bodies));
+                black_box(&linear);
+            });
+        });
     }
 
     group.finish();
@@ -94,6 +111,54 @@ fn bench_construction_memory(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_refit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("octree_refit");
+
+    for &count in &[10_000, 100_000] {
+        // Each iteration alternates between two states a small step apart, as
+        // consecutive ticks of the simulation are
+        let before = generate_test_bodies_spherical(count, 42, 500.0);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let after: Vec<OctreeBody> = before
+            .iter()
+            .map(|body| OctreeBody {
+                position: body.position
+                    + Vector::new(
+                        rng.random_range(-0.5..0.5),
+                        rng.random_range(-0.5..0.5),
+                        rng.random_range(-0.5..0.5),
+                    ),
+                ..*body
+            })
+            .collect();
+        let states = [&before, &after];
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut octree = Octree::new(0.5, 10.0, 1e4);
+        let mut tick = 0;
+        group.bench_with_input(BenchmarkId::new("rebuild", count), &count, |b, _| {
+            b.iter(|| {
+                tick += 1;
+                octree.build(black_box(states[tick % 2].iter().copied()));
+                black_box(&octree);
+            });
+        });
+
+        let mut octree = Octree::new(0.5, 10.0, 1e4).with_refit(0.25);
+        octree.build(before.iter().copied());
+        let mut tick = 0;
+        group.bench_with_input(BenchmarkId::new("refit", count), &count, |b, _| {
+            b.iter(|| {
+                tick += 1;
+                black_box(octree.refit(black_box(states[tick % 2])));
+            });
+        });
+    }
+
+    group.finish();
+}
+
 // =============================================================================
 // Force Calculation Performance Benchmarks
 // =============================================================================
@@ -171,6 +236,281 @@ fn bench_theta_accuracy_tradeoff(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_solver_comparison(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solver_comparison");
+    group.sample_size(10);
+
+    // Forces on every body from each solver, including the build
+    let body_counts = [50, 200, 1_000, 2_000];
+    let config = SimulationConfig::default();
+    let physics = &config.physics;
+
+    for &count in &body_counts {
+        let bodies = generate_test_bodies_spherical(count, 42, 500.0);
+        let solvers: [Box<dyn GravitySolver>; 2] = [
+            Box::new(
+                Octree::new(
+                    physics.octree_theta,
+                    physics.force_calculation_min_distance,
+                    physics.force_calculation_max_force,
+                )
+                .with_leaf_threshold(physics.octree_leaf_threshold),
+            ),
+            Box::new(DirectSummation::new(
+                physics.force_calculation_min_distance,
+                physics.force_calculation_max_force,
+            )),
+        ];
+
+        group.throughput(Throughput::Elements(count as u64));
+        for mut solver in solvers {
+            let mut forces = vec![Vector::ZERO; count];
+            group.bench_function(BenchmarkId::new(solver.name(), count), |b| {
+                b.iter(|| {
+                    solver.build(black_box(&bodies));
+                    solver.forces(&bodies, physics.gravitational_constant, &mut forces);
+                    black_box(&forces);
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_theta_force_error(c: &mut Criterion) {
+    let mut group = c.benchmark_group("theta_force_error");
+    group.sample_size(10);
+
+    // Mean relative error of the Barnes-Hut force on each body against exact
+    // direct summation, for each multipole order
+    let multipole_orders = [
+        ("monopole", MultipoleOrder::Monopole),
+        ("quadrupole", MultipoleOrder::Quadrupole),
+    ];
+    let theta_values = [0.1, 0.3, 0.5, 0.8, 1.0, 1.5, 2.0];
+    let body_count = 2_000;
+    let bodies = generate_test_bodies_spherical(body_count, 42, 500.0);
+    let g = 10.0;
+
+    let mut direct = DirectSummation::new(10.0, 1e4);
+    direct.build(&bodies);
+    let mut exact_forces = vec![Vector::ZERO; body_count];
+    direct.forces(&bodies, g, &mut exact_forces);
+
+    for (name, multipole_order) in multipole_orders {
+        for &theta in &theta_values {
+            let mut octree = Octree::new(theta, 10.0, 1e4).with_multipole_order(multipole_order);
+            octree.build(bodies.iter().copied());
+            let mut forces = vec![Vector::ZERO; body_count];
+
+            group.bench_function(
+                BenchmarkId::new(name, format!("theta_{}", (theta * 100.0) as u32)),
+                |b| {
+                    b.iter_custom(|iters| {
+                        let mut total_error = 0.0;
+
+                        for _ in 0..iters {
+                            octree.forces(&bodies, g, &mut forces);
+                            total_error += forces
+                                .iter()
+                                .zip(&exact_forces)
+                                .map(|(force, exact)| (*force - *exact).length() / exact.length())
+                                .sum::<Scalar>()
+                                / body_count as Scalar;
+                        }
+
+                        // Return average relative force error as Duration
+                        let avg_error = total_error / iters as f64;
+                        std::time::Duration::from_nanos((avg_error * 1e9) as u64)
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_opening_criteria(c: &mut Criterion) {
+    let mut group = c.benchmark_group("opening_criteria");
+    group.sample_size(10);
+
+    // Forces on every body under each opening criterion, at settings of
+    // similar force error. The relative criterion reads each body's
+    // acceleration, recorded here from direct summation.
+    let criteria = [
+        ("geometric", OpeningCriterion::Geometric, 0.5),
+        ("bmax", OpeningCriterion::Bmax, 0.3),
+        (
+            "relative_acceleration",
+            OpeningCriterion::RelativeAcceleration,
+            0.0005,
+        ),
+    ];
+    let body_count = 10_000;
+    let bodies = generate_test_bodies_spherical(body_count, 42, 500.0);
+    let g = 10.0;
+
+    let mut direct = DirectSummation::new(10.0, 1e4);
+    direct.build(&bodies);
+    let mut accelerations = vec![Vector::ZERO; body_count];
+    direct.forces(&bodies, g, &mut accelerations);
+    for (acceleration, body) in accelerations.iter_mut().zip(&bodies) {
+        *acceleration /= body.mass;
+    }
+
+    group.throughput(Throughput::Elements(body_count as u64));
+    for (name, criterion, parameter) in criteria {
+        let mut octree = Octree::new(parameter, 10.0, 1e4)
+            .with_opening_criterion(criterion)
+            .with_relative_accuracy(parameter);
+        octree.build(bodies.iter().copied());
+        octree.record_accelerations(&bodies, &accelerations);
+        let mut forces = vec![Vector::ZERO; body_count];
+
+        group.bench_function(BenchmarkId::new(name, body_count), |b| {
+            b.iter(|| {
+                octree.forces(black_box(&bodies), g, &mut forces);
+                black_box(&forces);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_large_n_solvers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_n_solvers");
+    group.sample_size(10);
+
+    // Build plus forces on every body at the body counts where the
+    // O(N log N) tree walk dominates, against the O(N) fast multipole method
+    // and the particle mesh
+    let body_counts = [10_000, 100_000];
+    let config = SimulationConfig::default();
+    let physics = &config.physics;
+
+    for &count in &body_counts {
+        let bodies = generate_test_bodies_spherical(count, 42, 500.0);
+        let octree = |multipole_order| -> Box<dyn GravitySolver> {
+            Box::new(
+                Octree::new(
+                    physics.octree_theta,
+                    physics.force_calculation_min_distance,
+                    physics.force_calculation_max_force,
+                )
+                .with_leaf_threshold(physics.octree_leaf_threshold)
+                .with_multipole_order(multipole_order),
+            )
+        };
+        let fmm = |order| -> Box<dyn GravitySolver> {
+            Box::new(
+                FastMultipole::new(
+                    order,
+                    physics.fmm.theta,
+                    physics.fmm.leaf_threshold,
+                    physics.force_calculation_min_distance,
+                    physics.force_calculation_max_force,
+                )
+                .unwrap(),
+            )
+        };
+        let particle_mesh = |p3m_cutoff| -> Box<dyn GravitySolver> {
+            Box::new(
+                ParticleMesh::new(
+                    physics.particle_mesh.grid_size,
+                    physics.particle_mesh.split_scale,
+                    p3m_cutoff,
+                    physics.force_calculation_min_distance,
+                    physics.force_calculation_max_force,
+                )
+                .unwrap(),
+            )
+        };
+        let solvers = [
+            ("barnes_hut_monopole", octree(MultipoleOrder::Monopole)),
+            ("barnes_hut_quadrupole", octree(MultipoleOrder::Quadrupole)),
+            ("fmm_order_2", fmm(2)),
+            ("fmm_order_3", fmm(3)),
+            ("fmm_order_4", fmm(4)),
+            ("particle_mesh", particle_mesh(None)),
+            (
+                "particle_mesh_p3m",
+                particle_mesh(Some(physics.particle_mesh.p3m_cutoff)),
+            ),
+        ];
+
+        group.throughput(Throughput::Elements(count as u64));
+        for (name, mut solver) in solvers {
+            let mut forces = vec![Vector::ZERO; count];
+            group.bench_function(BenchmarkId::new(name, count), |b| {
+                b.iter(|| {
+                    solver.build(black_box(&bodies));
+                    solver.forces(&bodies, physics.gravitational_constant, &mut forces);
+                    black_box(&forces);
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_fmm_force_error(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fmm_force_error");
+    group.sample_size(10);
+
+    // Mean relative error of the fast multipole force on each body against
+    // exact direct summation, for each expansion order
+    let orders = [2, 3, 4];
+    let theta_values = [0.3, 0.5, 0.7];
+    let body_count = 2_000;
+    let bodies = generate_test_bodies_spherical(body_count, 42, 500.0);
+    let g = 10.0;
+
+    let mut direct = DirectSummation::new(10.0, 1e4);
+    direct.build(&bodies);
+    let mut exact_forces = vec![Vector::ZERO; body_count];
+    direct.forces(&bodies, g, &mut exact_forces);
+
+    for &order in &orders {
+        for &theta in &theta_values {
+            let mut fmm = FastMultipole::new(order, theta, 16, 10.0, 1e4).unwrap();
+            fmm.build(&bodies);
+            let mut forces = vec![Vector::ZERO; body_count];
+
+            group.bench_function(
+                BenchmarkId::new(
+                    format!("order_{order}"),
+                    format!("theta_{}", (theta * 100.0) as u32),
+                ),
+                |b| {
+                    b.iter_custom(|iters| {
+                        let mut total_error = 0.0;
+
+                        for _ in 0..iters {
+                            fmm.forces(&bodies, g, &mut forces);
+                            total_error += forces
+                                .iter()
+                                .zip(&exact_forces)
+                                .map(|(force, exact)| (*force - *exact).length() / exact.length())
+                                .sum::<Scalar>()
+                                / body_count as Scalar;
+                        }
+
+                        // Return average relative force error as Duration
+                        let avg_error = total_error / iters as f64;
+                        std::time::Duration::from_nanos((avg_error * 1e9) as u64)
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
 // =============================================================================
 // Real-World Scenario Benchmarks
 // =============================================================================
@@ -223,13 +563,19 @@ fn bench_realworld_60fps_target(c: &mut Criterion) {
 criterion_group!(
     construction,
     bench_construction_scaling,
-    bench_construction_memory
+    bench_construction_memory,
+    bench_refit
 );
 
 criterion_group!(
     physics,
     bench_force_calculation_scaling,
-    bench_theta_accuracy_tradeoff
+    bench_theta_accuracy_tradeoff,
+    bench_solver_comparison,
+    bench_theta_force_error,
+    bench_opening_criteria,
+    bench_large_n_solvers,
+    bench_fmm_force_error
 );
 
 criterion_group!(realworld, bench_realworld_60fps_target);
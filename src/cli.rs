@@ -1,9 +1,10 @@
 //! Command line interface for Stardrift
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::fmt;
 
 use crate::config::{ColorScheme, IntegratorConfig, SimulationConfig};
+use crate::physics::integrators::IntegratorParameters;
 use crate::physics::integrators::registry::IntegratorRegistry;
 use crate::plugins::screenshot::{AutomatedScreenshotNaming, AutomatedScreenshotSchedule};
 
@@ -27,6 +28,17 @@ impl fmt::Display for CliError {
 
 impl std::error::Error for CliError {}
 
+/// Output format for `--list-integrators`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum ListFormat {
+    /// Aligned table for reading in a terminal
+    #[default]
+    Table,
+    /// JSON array for scripts
+    Json,
+}
+
 /// Stardrift - N-body gravity simulation
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -67,6 +79,15 @@ pub struct Args {
     #[arg(long)]
     pub list_integrators: bool,
 
+    /// Output format for --list-integrators
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "table",
+        requires = "list_integrators"
+    )]
+    pub format: ListFormat,
+
     /// Take screenshot after N seconds (can be fractional)
     #[arg(long, value_name = "SECONDS")]
     pub screenshot_after: Option<f32>,
@@ -113,11 +134,45 @@ pub struct Args {
 }
 
 /// Handles the --list-integrators flag by printing available integrators and exiting
-pub fn handle_list_integrators() {
+///
+/// The table lists each integrator's order, whether it is symplectic and
+/// time-reversible, and its force evaluations per step, followed by the
+/// aliases. JSON output carries the same fields for each integrator.
+pub fn handle_list_integrators(format: ListFormat) {
     let registry = IntegratorRegistry::new().with_standard_integrators();
+    let descriptions = registry.describe_available();
+
+    if format == ListFormat::Json {
+        match serde_json::to_string_pretty(&descriptions) {
+            Ok(json) => println!("{json}"),
+            Err(err) => eprintln!("Error: {err}"),
+        }
+        return;
+    }
+
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    let name_width = descriptions
+        .iter()
+        .map(|description| description.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Name".len());
+
     println!("Available integrators:");
-    for name in registry.list_available() {
-        println!("  - {name}");
+    println!(
+        "  {:<name_width$}  {:>5}  {:<10}  {:<10}  {:>10}  Description",
+        "Name", "Order", "Symplectic", "Reversible", "Evals/Step"
+    );
+    for description in &descriptions {
+        println!(
+            "  {:<name_width$}  {:>5}  {:<10}  {:<10}  {:>10}  {}",
+            description.name,
+            description.order,
+            yes_no(description.symplectic),
+            yes_no(description.time_reversible),
+            description.force_evaluations,
+            description.description
+        );
     }
 
     let aliases = registry.list_aliases();
@@ -151,15 +206,23 @@ pub fn load_and_apply_config(args: &Args) -> Result<SimulationConfig, CliError>
     }
 
     if let Some(integrator_type) = &args.integrator {
-        // Validate integrator name against registry
+        // Parameters from the config file only apply to the integrator it names
+        let parameters = if config.physics.integrator.integrator_type == *integrator_type {
+            std::mem::take(&mut config.physics.integrator.parameters)
+        } else {
+            IntegratorParameters::new()
+        };
+
+        // Validate integrator name and parameters against registry
         let registry = IntegratorRegistry::new().with_standard_integrators();
         registry
-            .create(integrator_type)
-            .map_err(|err| CliError::InvalidIntegrator(err))?;
+            .create(integrator_type, &parameters)
+            .map_err(CliError::InvalidIntegrator)?;
 
         println!("Using integrator: {integrator_type}");
         config.physics.integrator = IntegratorConfig {
             integrator_type: integrator_type.clone(),
+            parameters,
         };
     }
 
@@ -1,3 +1,8 @@
+use crate::physics::block_timestep::MAX_BLOCK_LEVEL;
+use crate::physics::gravity::{Softening, ThetaAutoTune};
+use crate::physics::integrators::IntegratorParameters;
+use crate::physics::octree::{MultipoleOrder, OctreeLayout, OpeningCriterion};
+use crate::physics::timestep::TimestepCriterion;
 use crate::prelude::*;
 use clap::ValueEnum;
 use config::{Config, ConfigError, File};
@@ -82,18 +87,31 @@ pub struct SimulationConfig {
 pub struct PhysicsConfig {
     pub gravitational_constant: Scalar,
     pub body_count: usize,
+    pub gravity_solver: GravitySolverKind,
     pub octree_theta: Scalar,
     pub octree_leaf_threshold: usize,
+    pub octree_multipole_order: MultipoleOrder,
+    pub octree_opening_criterion: OpeningCriterion,
+    pub octree_relative_accuracy: Scalar,
+    pub octree_layout: OctreeLayout,
+    pub octree_update: OctreeUpdate,
+    pub octree_refit_max_reinserted: Scalar,
     pub body_distribution_sphere_radius_multiplier: f32,
     pub body_distribution_min_distance: f32,
     pub min_body_radius: f32,
     pub max_body_radius: f32,
     pub force_calculation_min_distance: Scalar,
     pub force_calculation_max_force: Scalar,
+    pub force_calculation_softening: Softening,
     pub initial_seed: Option<u64>,
     pub initial_velocity: InitialVelocityConfig,
     #[serde(default)]
     pub integrator: IntegratorConfig,
+    pub collisions: CollisionConfig,
+    pub fmm: FmmConfig,
+    pub particle_mesh: ParticleMeshConfig,
+    pub timestep: TimestepConfig,
+    pub force_error: ForceErrorConfig,
     pub barycentric_drift_correction: bool,
 }
 
@@ -102,22 +120,50 @@ impl Default for PhysicsConfig {
         Self {
             gravitational_constant: 100.0,
             body_count: 25,
+            gravity_solver: GravitySolverKind::BarnesHut,
             octree_theta: 0.5,
             octree_leaf_threshold: 1,
+            octree_multipole_order: MultipoleOrder::Monopole,
+            octree_opening_criterion: OpeningCriterion::Geometric,
+            octree_relative_accuracy: 0.0005,
+            octree_layout: OctreeLayout::Boxed,
+            octree_update: OctreeUpdate::Rebuild,
+            octree_refit_max_reinserted: 0.25,
             body_distribution_sphere_radius_multiplier: 500.0,
             body_distribution_min_distance: 0.001,
             min_body_radius: 2.0,
             max_body_radius: 4.0,
             force_calculation_min_distance: 1.0,
             force_calculation_max_force: 1e5,
+            force_calculation_softening: Softening::Clamp,
             initial_seed: None,
             initial_velocity: InitialVelocityConfig::default(),
             integrator: IntegratorConfig::default(),
+            collisions: CollisionConfig::default(),
+            fmm: FmmConfig::default(),
+            particle_mesh: ParticleMeshConfig::default(),
+            timestep: TimestepConfig::default(),
+            force_error: ForceErrorConfig::default(),
             barycentric_drift_correction: true,
         }
     }
 }
 
+/// Algorithm used to calculate gravitational forces
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GravitySolverKind {
+    /// Barnes-Hut octree, O(N log N) with accuracy set by `octree_theta`
+    #[default]
+    BarnesHut,
+    /// Exact pairwise summation, O(N²); suited to systems of a few hundred bodies
+    Direct,
+    /// Cartesian fast multipole method, O(N) with accuracy set by `fmm`
+    Fmm,
+    /// FFT particle-mesh solver for very large systems, set up by `particle_mesh`
+    ParticleMesh,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct InitialVelocityConfig {
@@ -140,21 +186,16 @@ impl Default for InitialVelocityConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum VelocityMode {
     Random,
+    #[default]
     Orbital,
     Tangential,
     Radial,
 }
 
-impl Default for VelocityMode {
-    fn default() -> Self {
-        VelocityMode::Orbital
-    }
-}
-
 /// Flexible integrator configuration
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -162,16 +203,238 @@ pub struct IntegratorConfig {
     /// Type of integrator (e.g., "velocity_verlet")
     #[serde(rename = "type")]
     pub integrator_type: String,
+    /// Integrator-specific parameters (e.g., composition coefficients)
+    #[serde(flatten)]
+    pub parameters: IntegratorParameters,
 }
 
 impl Default for IntegratorConfig {
     fn default() -> Self {
         Self {
             integrator_type: "velocity_verlet".to_string(),
+            parameters: IntegratorParameters::new(),
+        }
+    }
+}
+
+/// How bodies respond when their spheres overlap
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionMode {
+    /// Bodies pass through each other
+    #[default]
+    None,
+    /// Bodies merge inelastically into one
+    Merge,
+    /// Bodies bounce off each other
+    Bounce,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CollisionConfig {
+    pub mode: CollisionMode,
+    /// Coefficient of restitution used in bounce mode, from 0.0 (perfectly
+    /// inelastic) to 1.0 (perfectly elastic)
+    pub restitution: Scalar,
+}
+
+impl Default for CollisionConfig {
+    fn default() -> Self {
+        Self {
+            mode: CollisionMode::None,
+            restitution: 0.5,
         }
     }
 }
 
+/// Settings of the fast multipole method solver
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FmmConfig {
+    /// Highest multipole degree kept in the expansions, from 2 to 4
+    pub order: usize,
+    /// Opening angle; cells interact through their expansions once the sum
+    /// of their radii is below theta times their separation
+    pub theta: Scalar,
+    /// Maximum bodies per leaf cell
+    pub leaf_threshold: usize,
+}
+
+impl Default for FmmConfig {
+    fn default() -> Self {
+        Self {
+            order: 3,
+            theta: 0.5,
+            leaf_threshold: 16,
+        }
+    }
+}
+
+/// Settings of the particle-mesh solver
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ParticleMeshConfig {
+    /// Cells per side of the mesh, a power of two from 8 to 128
+    pub grid_size: usize,
+    /// Scale below which mesh forces fade, in grid cells
+    pub split_scale: Scalar,
+    /// Whether to add the exact short-range force between nearby bodies
+    pub p3m: bool,
+    /// Radius of the short-range sum, in units of the split scale
+    pub p3m_cutoff: Scalar,
+}
+
+impl Default for ParticleMeshConfig {
+    fn default() -> Self {
+        Self {
+            grid_size: 64,
+            split_scale: 1.25,
+            p3m: false,
+            p3m_cutoff: 4.5,
+        }
+    }
+}
+
+/// Sampling of the gravity solver's force error, and tuning of the octree
+/// opening angle from it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ForceErrorConfig {
+    /// Whether to sample the force error; auto-tuning also requires it
+    pub enabled: bool,
+    /// Bodies whose forces are compared against direct summation each time
+    pub sample_size: usize,
+    /// Fixed ticks between samples
+    pub interval: usize,
+    pub auto_tune: ThetaAutoTune,
+    /// RMS relative force error aimed for by `auto_tune = "force_error"`
+    pub target_error: Scalar,
+    /// Frame time in seconds aimed for by `auto_tune = "frame_time"`
+    pub target_frame_time: Scalar,
+    pub min_theta: Scalar,
+    pub max_theta: Scalar,
+}
+
+impl Default for ForceErrorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_size: 32,
+            interval: 60,
+            auto_tune: ThetaAutoTune::Off,
+            target_error: 1e-3,
+            target_frame_time: 1.0 / 60.0,
+            min_theta: 0.2,
+            max_theta: 1.2,
+        }
+    }
+}
+
+/// How the Barnes-Hut octree follows the bodies from one step to the next
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OctreeUpdate {
+    /// Rebuild the whole tree every time
+    #[default]
+    Rebuild,
+    /// Refit the existing tree to the moved bodies, rebuilding once more than
+    /// `octree_refit_max_reinserted` of them have changed leaf
+    Refit,
+}
+
+/// How the length of each physics step is chosen
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestepMode {
+    /// One step per fixed tick, spanning the whole tick
+    #[default]
+    Fixed,
+    /// As many sub-steps per fixed tick as the timestep criterion requires
+    Adaptive,
+    /// Individual power-of-two block steps per body within each fixed tick
+    Block,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TimestepConfig {
+    pub mode: TimestepMode,
+    pub criterion: TimestepCriterion,
+    /// Dimensionless accuracy parameter η; smaller values give shorter steps
+    pub accuracy: Scalar,
+    /// Length ε of the acceleration criterion, which lets a body move a
+    /// fraction of it per step. `None` uses `force_calculation_min_distance`,
+    /// the scale below which the pair force is softened and stops growing.
+    pub softening: Option<Scalar>,
+    pub min_dt: Scalar,
+    pub max_dt: Scalar,
+    /// Upper bound on sub-steps per fixed tick; once reached, the remaining
+    /// sub-steps are lengthened so the tick still covers its full duration
+    pub max_substeps: usize,
+    /// Deepest block level in block mode; the shortest block step is the
+    /// fixed tick divided by 2^max_block_level
+    pub max_block_level: u32,
+}
+
+impl Default for TimestepConfig {
+    fn default() -> Self {
+        Self {
+            mode: TimestepMode::Fixed,
+            criterion: TimestepCriterion::Acceleration,
+            accuracy: 0.025,
+            softening: None,
+            min_dt: 1e-5,
+            max_dt: 1.0 / 60.0,
+            max_substeps: 64,
+            max_block_level: 6,
+        }
+    }
+}
+
+impl ForceErrorConfig {
+    /// Reject settings the sampler or the theta tuner cannot run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let checks = [
+            (
+                self.sample_size > 0,
+                "force_error.sample_size must be positive",
+            ),
+            (self.interval > 0, "force_error.interval must be positive"),
+            (
+                self.target_error > 0.0,
+                "force_error.target_error must be positive",
+            ),
+            (
+                self.target_frame_time > 0.0,
+                "force_error.target_frame_time must be positive",
+            ),
+            (
+                0.0 < self.min_theta && self.min_theta <= self.max_theta,
+                "force_error requires 0 < min_theta <= max_theta",
+            ),
+        ];
+
+        match checks.into_iter().find(|(valid, _)| !valid) {
+            Some((_, message)) => Err(ConfigError::Message(message.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl TimestepConfig {
+    /// Reject settings the timestep modes cannot run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_block_level > MAX_BLOCK_LEVEL {
+            return Err(ConfigError::Message(format!(
+                "timestep.max_block_level must be at most {MAX_BLOCK_LEVEL}, got {}",
+                self.max_block_level
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct RenderingConfig {
@@ -256,35 +519,25 @@ impl Default for TrailConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum FadeCurve {
     Linear,
+    #[default]
     Exponential,
     SmoothStep,
     EaseInOut,
 }
 
-impl Default for FadeCurve {
-    fn default() -> Self {
-        FadeCurve::Exponential
-    }
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum TaperCurve {
+    #[default]
     Linear,
     Exponential,
     SmoothStep,
 }
 
-impl Default for TaperCurve {
-    fn default() -> Self {
-        TaperCurve::Linear
-    }
-}
-
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct ScreenshotConfig {
@@ -346,6 +599,12 @@ impl SimulationConfig {
         }
     }
 
+    /// Reject settings that deserialize but that the simulation cannot run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.physics.timestep.validate()?;
+        self.physics.force_error.validate()
+    }
+
     fn load_config_with_source(source: File<config::FileSourceFile, config::FileFormat>) -> Self {
         let config_result = Config::builder()
             .add_source(config::File::from_str(
@@ -357,10 +616,16 @@ impl SimulationConfig {
 
         match config_result {
             Ok(config) => match config.try_deserialize::<Self>() {
-                Ok(sim_config) => {
-                    info!("Configuration loaded successfully");
-                    sim_config
-                }
+                Ok(sim_config) => match sim_config.validate() {
+                    Ok(()) => {
+                        info!("Configuration loaded successfully");
+                        sim_config
+                    }
+                    Err(e) => {
+                        warn!("Invalid config: {}. Using defaults.", e);
+                        Self::default()
+                    }
+                },
                 Err(e) => {
                     warn!("Failed to deserialize config: {}. Using defaults.", e);
                     Self::default()
@@ -28,7 +28,7 @@ fn main() {
 
     // Handle list-integrators flag
     if args.list_integrators {
-        cli::handle_list_integrators();
+        cli::handle_list_integrators(args.format);
         return;
     }
 
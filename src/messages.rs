@@ -18,3 +18,13 @@ pub enum SimulationCommand {
     #[cfg(not(target_arch = "wasm32"))]
     Quit,
 }
+
+/// Emitted when a collision merges one body into another
+///
+/// The `absorbed` entity has been despawned by the time this message is read;
+/// the `survivor` carries the combined mass, momentum, and volume.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyMerged {
+    pub survivor: Entity,
+    pub absorbed: Entity,
+}
@@ -1,4 +1,4 @@
-use crate::physics::math::Vector;
+use crate::physics::math::{Scalar, Vector};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Aabb3d {
@@ -16,6 +16,19 @@ impl Aabb3d {
         (self.min + self.max) * 0.5
     }
 
+    /// Whether `point` lies inside the box or on its boundary
+    #[inline]
+    pub fn contains(&self, point: Vector) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Squared distance from `point` to the nearest point of the box, zero
+    /// for points inside it
+    #[inline]
+    pub fn distance_squared_to(&self, point: Vector) -> Scalar {
+        (point.clamp(self.min, self.max) - point).length_squared()
+    }
+
     pub fn octants(self) -> [Aabb3d; 8] {
         let center = self.center();
 
@@ -0,0 +1,214 @@
+//! Hierarchical block (individual) timesteps
+//!
+//! In clustered systems a handful of tight binaries would force a global
+//! timestep down for every body. Block timesteps instead give each body its
+//! own step of `tick / 2^k` for some level `k`, so bodies in quiet regions
+//! take long steps while only the bodies in close encounters take short ones.
+//! Because every step is a power-of-two fraction of the tick, blocks nest and
+//! all bodies are synchronized again at the end of each tick.
+//!
+//! At every block boundary the bodies whose blocks start there are advanced
+//! with the per-body `Integrator::step`, one level at a time. While a body
+//! takes its step, every other body enters the field at its position at the
+//! middle of that step, predicted with a second-order Taylor expansion from
+//! the start of its own block. Sampling the others at the midpoint rather
+//! than at the start keeps the error from their motion second order.
+
+use crate::physics::integrators::{
+    FrozenBodyField, Integrator, SystemAccelerationField, for_each_chunk,
+};
+use crate::physics::math::{Scalar, Vector};
+
+/// Deepest supported block level, which keeps block times in a `u64`
+pub const MAX_BLOCK_LEVEL: u32 = 32;
+
+/// Summary of one tick of block timesteps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockStepReport {
+    /// Shortest block step taken by any body
+    pub shortest_step: Scalar,
+    /// Number of block boundaries at which some bodies were advanced
+    pub block_times: usize,
+    /// Total number of individual body steps
+    pub body_steps: usize,
+}
+
+/// Advance a system through one tick using hierarchical block timesteps.
+///
+/// Each body's step is chosen at the start of each of its blocks by calling
+/// `step_size` with its current acceleration and, after its first block, a
+/// jerk estimated from the change in acceleration across the previous block.
+/// The step is rounded down to `tick / 2^k` with `0 <= k <= max_level`, and
+/// `k` is raised further if needed so the block starts on a boundary of its
+/// own size.
+///
+/// # Arguments
+///
+/// * `integrator` - Integrator used to advance each body through its block
+/// * `positions` - Position of each body, advanced in place
+/// * `velocities` - Velocity of each body, advanced in place
+/// * `field` - System-wide field, updated with predicted positions at each block boundary
+/// * `tick` - Length of the tick; the longest possible block
+/// * `max_level` - Deepest level, giving the shortest block `tick / 2^max_level`;
+///   capped at `MAX_BLOCK_LEVEL`
+/// * `step_size` - Desired step for a body given its acceleration and estimated jerk
+pub fn step_blocks(
+    integrator: &dyn Integrator,
+    positions: &mut [Vector],
+    velocities: &mut [Vector],
+    field: &mut dyn SystemAccelerationField,
+    tick: Scalar,
+    max_level: u32,
+    step_size: impl Fn(Vector, Option<Vector>) -> Scalar,
+) -> BlockStepReport {
+    debug_assert_eq!(positions.len(), velocities.len());
+
+    let max_level = max_level.min(MAX_BLOCK_LEVEL);
+    let body_count = positions.len();
+    // Time is counted in units of the shortest block
+    let units_per_tick: u64 = 1 << max_level;
+    let unit = tick / units_per_tick as Scalar;
+
+    // State at the start of each body's current block, used for prediction
+    let mut start_position = positions.to_vec();
+    let mut start_velocity = velocities.to_vec();
+    let mut start_acceleration = vec![Vector::ZERO; body_count];
+    let mut start_time = vec![0u64; body_count];
+    let mut end_time = vec![0u64; body_count];
+    // Acceleration at the start of the previous block and that block's length
+    let mut previous_block: Vec<Option<(Vector, Scalar)>> = vec![None; body_count];
+
+    let mut predicted = vec![Vector::ZERO; body_count];
+    let mut active: Vec<(usize, Vector, Vector)> = Vec::new();
+
+    let mut report = BlockStepReport {
+        shortest_step: tick,
+        block_times: 0,
+        body_steps: 0,
+    };
+
+    let mut now = 0u64;
+    while now < units_per_tick {
+        // Bodies whose blocks end now hold their integrated state and start a
+        // new block; all others are predicted forward from the start of theirs
+        for i in 0..body_count {
+            if end_time[i] == now {
+                start_position[i] = positions[i];
+                start_velocity[i] = velocities[i];
+                start_time[i] = now;
+            }
+        }
+        predict_all(
+            &mut predicted,
+            (&start_position, &start_velocity, &start_acceleration),
+            &start_time,
+            now as Scalar,
+            unit,
+        );
+        field.update(&predicted);
+
+        let mut levels_started = 0u64;
+        for i in 0..body_count {
+            if end_time[i] != now {
+                continue;
+            }
+
+            let acceleration = field.at(i, predicted[i]);
+            let jerk = previous_block[i].map(|(previous_acceleration, length)| {
+                (acceleration - previous_acceleration) / length
+            });
+
+            let level = block_level(step_size(acceleration, jerk), tick, max_level, now);
+            let step = (units_per_tick >> level) as Scalar * unit;
+
+            start_acceleration[i] = acceleration;
+            previous_block[i] = Some((acceleration, step));
+            end_time[i] = now + (units_per_tick >> level);
+            levels_started |= 1 << level;
+            report.shortest_step = report.shortest_step.min(step);
+        }
+
+        // Bodies starting blocks of the same length are stepped together
+        // against the others as predicted to the middle of that block, which
+        // keeps the coupling between bodies on different levels second order
+        for level in (0..=max_level).filter(|level| levels_started & (1 << level) != 0) {
+            let length = units_per_tick >> level;
+            let step = length as Scalar * unit;
+
+            predict_all(
+                &mut predicted,
+                (&start_position, &start_velocity, &start_acceleration),
+                &start_time,
+                now as Scalar + 0.5 * length as Scalar,
+                unit,
+            );
+            field.update(&predicted);
+            let snapshot: &dyn SystemAccelerationField = field;
+
+            active.clear();
+            active.extend(
+                (0..body_count)
+                    .filter(|&i| start_time[i] == now && end_time[i] == now + length)
+                    .map(|i| (i, positions[i], velocities[i])),
+            );
+
+            for_each_chunk(&mut active, |_, chunk| {
+                for (i, position, velocity) in chunk.iter_mut() {
+                    integrator.step(
+                        position,
+                        velocity,
+                        &FrozenBodyField::new(snapshot, *i),
+                        step,
+                    );
+                }
+            });
+
+            for &(i, position, velocity) in &active {
+                positions[i] = position;
+                velocities[i] = velocity;
+            }
+
+            report.body_steps += active.len();
+        }
+
+        report.block_times += 1;
+
+        now = end_time.iter().copied().min().unwrap_or(units_per_tick);
+    }
+
+    report
+}
+
+/// Predict every body to `time` with a second-order Taylor expansion from the
+/// start of its block, with times measured in units of the shortest block
+fn predict_all(
+    predicted: &mut [Vector],
+    (positions, velocities, accelerations): (&[Vector], &[Vector], &[Vector]),
+    start_times: &[u64],
+    time: Scalar,
+    unit: Scalar,
+) {
+    for (i, prediction) in predicted.iter_mut().enumerate() {
+        let elapsed = (time - start_times[i] as Scalar) * unit;
+        *prediction =
+            positions[i] + velocities[i] * elapsed + accelerations[i] * (0.5 * elapsed * elapsed);
+    }
+}
+
+/// Level of the longest block no longer than `step` that starts at `now`
+fn block_level(step: Scalar, tick: Scalar, max_level: u32, now: u64) -> u32 {
+    let mut level = if step >= tick {
+        0
+    } else if step > 0.0 {
+        ((tick / step).log2().ceil() as u32).min(max_level)
+    } else {
+        max_level
+    };
+
+    // A block must start on a multiple of its own length to stay nested
+    while level < max_level && !now.is_multiple_of(1u64 << (max_level - level)) {
+        level += 1;
+    }
+
+    level
+}
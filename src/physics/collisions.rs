@@ -0,0 +1,181 @@
+//! Collision detection and response for spherical bodies
+//!
+//! Bodies are treated as spheres of their `Radius`. Overlapping pairs are found
+//! with a sort-and-sweep broad phase along the x axis, which is O(N log N) for
+//! the sparse distributions typical of the simulation, followed by an exact
+//! sphere-sphere test.
+//!
+//! Contacts are resolved either by merging bodies inelastically
+//! ([`merge_bodies`]) or by bouncing them apart with an impulse ([`bounce`]).
+
+use crate::physics::math::{Scalar, Vector};
+use bevy::prelude::Entity;
+
+/// Snapshot of the state of a body taking part in collision detection
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionBody {
+    pub entity: Entity,
+    pub position: Vector,
+    pub velocity: Vector,
+    pub mass: Scalar,
+    pub radius: Scalar,
+}
+
+impl CollisionBody {
+    /// Returns true if the spheres of the two bodies intersect
+    #[inline]
+    pub fn overlaps(&self, other: &CollisionBody) -> bool {
+        let contact_distance = self.radius + other.radius;
+        self.position.distance_squared(other.position) < contact_distance * contact_distance
+    }
+}
+
+/// State of the single body that results from merging several bodies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MergedBody {
+    pub position: Vector,
+    pub velocity: Vector,
+    pub mass: Scalar,
+    pub radius: Scalar,
+}
+
+/// Finds all pairs of overlapping bodies.
+///
+/// Bodies are sorted by the lower x extent of their spheres and swept once;
+/// only pairs whose x extents overlap are tested exactly.
+///
+/// # Returns
+///
+/// Index pairs `(i, j)` into `bodies` with `i < j`.
+pub fn find_overlapping_pairs(bodies: &[CollisionBody]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..bodies.len()).collect();
+    order.sort_unstable_by(|&a, &b| {
+        let min_a = bodies[a].position.x - bodies[a].radius;
+        let min_b = bodies[b].position.x - bodies[b].radius;
+        min_a.total_cmp(&min_b)
+    });
+
+    let mut pairs = Vec::new();
+
+    for (sorted_index, &i) in order.iter().enumerate() {
+        let max_x = bodies[i].position.x + bodies[i].radius;
+
+        for &j in &order[sorted_index + 1..] {
+            // Every remaining body starts beyond this one's extent
+            if bodies[j].position.x - bodies[j].radius > max_x {
+                break;
+            }
+
+            if bodies[i].overlaps(&bodies[j]) {
+                pairs.push((i.min(j), i.max(j)));
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Groups bodies connected by overlapping pairs into clusters.
+///
+/// A chain of contacts (A touches B, B touches C) forms one cluster, so every
+/// body takes part in at most one merge per step. Only clusters with two or
+/// more members are returned.
+pub fn group_overlapping(body_count: usize, pairs: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    fn find(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]]; // Path halving
+            index = parents[index];
+        }
+        index
+    }
+
+    let mut parents: Vec<usize> = (0..body_count).collect();
+
+    for &(a, b) in pairs {
+        let root_a = find(&mut parents, a);
+        let root_b = find(&mut parents, b);
+        if root_a != root_b {
+            parents[root_b] = root_a;
+        }
+    }
+
+    let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); body_count];
+    for index in 0..body_count {
+        let root = find(&mut parents, index);
+        clusters[root].push(index);
+    }
+
+    clusters.retain(|cluster| cluster.len() > 1);
+    clusters
+}
+
+/// Merges bodies in a perfectly inelastic collision.
+///
+/// Mass and linear momentum are conserved, the merged body sits at the
+/// combined centre of mass, and its radius is chosen so that the merged
+/// volume equals the sum of the individual volumes.
+pub fn merge_bodies<'a>(bodies: impl IntoIterator<Item = &'a CollisionBody>) -> MergedBody {
+    let (mass, weighted_position, momentum, volume) = bodies.into_iter().fold(
+        (0.0, Vector::ZERO, Vector::ZERO, 0.0),
+        |(mass, weighted_position, momentum, volume), body| {
+            (
+                mass + body.mass,
+                weighted_position + body.position * body.mass,
+                momentum + body.velocity * body.mass,
+                volume + body.radius.powi(3), // Common 4π/3 factor cancels
+            )
+        },
+    );
+
+    debug_assert!(mass > 0.0, "Merged mass must be positive, got {}", mass);
+
+    MergedBody {
+        position: weighted_position / mass,
+        velocity: momentum / mass,
+        mass,
+        radius: volume.cbrt(),
+    }
+}
+
+/// Resolves a contact between two overlapping bodies with an impulse.
+///
+/// The impulse acts along the line of centres and reverses the approaching
+/// component of the relative velocity, scaled by `restitution` (0.0 for a
+/// perfectly inelastic contact, 1.0 for a perfectly elastic one). Equal and
+/// opposite impulses conserve linear momentum. Bodies that are already
+/// separating receive no impulse.
+///
+/// The overlap is then removed by pushing the bodies apart along the same
+/// line, each in inverse proportion to its mass, so the centre of mass does
+/// not move.
+///
+/// Bodies that do not overlap are left untouched.
+pub fn bounce(a: &mut CollisionBody, b: &mut CollisionBody, restitution: Scalar) {
+    if !a.overlaps(b) {
+        return;
+    }
+
+    let offset = b.position - a.position;
+    let distance = offset.length();
+    // Coincident centres have no line of contact; any direction separates them
+    let normal = if distance > 0.0 {
+        offset / distance
+    } else {
+        Vector::X
+    };
+
+    let inverse_mass_a = a.mass.recip();
+    let inverse_mass_b = b.mass.recip();
+    let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+
+    let approach_speed = (b.velocity - a.velocity).dot(normal);
+    if approach_speed < 0.0 {
+        let impulse = -(1.0 + restitution) * approach_speed / inverse_mass_sum;
+        a.velocity -= normal * (impulse * inverse_mass_a);
+        b.velocity += normal * (impulse * inverse_mass_b);
+    }
+
+    let penetration = a.radius + b.radius - distance;
+    a.position -= normal * (penetration * inverse_mass_a / inverse_mass_sum);
+    b.position += normal * (penetration * inverse_mass_b / inverse_mass_sum);
+}
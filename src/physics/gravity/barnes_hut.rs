@@ -0,0 +1,153 @@
+//! Barnes-Hut gravity solvers backed by the boxed and linear octrees
+
+use super::GravitySolver;
+use crate::physics::aabb3d::Aabb3d;
+use crate::physics::linear_octree::LinearOctree;
+use crate::physics::math::{Scalar, Vector};
+use crate::physics::octree::{Octree, OctreeBody};
+use bevy::prelude::Entity;
+
+impl GravitySolver for Octree {
+    fn name(&self) -> &'static str {
+        "barnes_hut"
+    }
+
+    /// Refits the existing tree when refitting is enabled, and rebuilds it
+    /// otherwise
+    fn build(&mut self, bodies: &[OctreeBody]) {
+        if self.refit_max_reinserted.is_some() {
+            self.refit(bodies);
+        } else {
+            Octree::build(self, bodies.iter().copied());
+        }
+    }
+
+    fn force_at(
+        &self,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        self.calculate_force_at_position(position, mass, exclude_entity, g)
+    }
+
+    fn force_and_jerk_at(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        self.calculate_force_and_jerk_at_position(position, velocity, mass, exclude_entity, g)
+    }
+
+    fn potential_at(&self, position: Vector, exclude_entity: Entity, g: Scalar) -> Option<Scalar> {
+        Some(self.calculate_potential_at_position(position, exclude_entity, g))
+    }
+
+    fn potential_energy(&self, bodies: &[OctreeBody], g: Scalar) -> Option<Scalar> {
+        Some(Octree::potential_energy(self, bodies, g))
+    }
+
+    fn record_accelerations(&mut self, bodies: &[OctreeBody], accelerations: &[Vector]) {
+        Octree::record_accelerations(self, bodies, accelerations);
+    }
+
+    fn theta(&self) -> Option<Scalar> {
+        Some(self.theta)
+    }
+
+    fn set_theta(&mut self, theta: Scalar) {
+        self.theta = theta;
+    }
+
+    fn bounds(&self) -> Vec<Aabb3d> {
+        Octree::bounds(self)
+    }
+}
+
+impl GravitySolver for LinearOctree {
+    fn name(&self) -> &'static str {
+        "barnes_hut"
+    }
+
+    fn build(&mut self, bodies: &[OctreeBody]) {
+        LinearOctree::build(self, bodies);
+    }
+
+    fn force_at(
+        &self,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        self.calculate_force_at_position(position, mass, exclude_entity, g)
+    }
+
+    fn force_and_jerk_at(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        self.calculate_force_and_jerk_at_position(position, velocity, mass, exclude_entity, g)
+    }
+
+    /// Evaluates the bodies in Morton order, so that neighbouring bodies
+    /// walk the tree one after another
+    fn forces(&self, bodies: &[OctreeBody], g: Scalar, forces: &mut [Vector]) {
+        debug_assert_eq!(bodies.len(), forces.len());
+
+        self.for_each_body_in_key_order(forces, |body| {
+            self.calculate_force_at_position(body.position, body.mass, body.entity, g)
+        });
+    }
+
+    fn forces_and_jerks(
+        &self,
+        bodies: &[OctreeBody],
+        g: Scalar,
+        derivatives: &mut [(Vector, Vector)],
+    ) {
+        debug_assert_eq!(bodies.len(), derivatives.len());
+
+        self.for_each_body_in_key_order(derivatives, |body| {
+            self.calculate_force_and_jerk_at_position(
+                body.position,
+                body.velocity,
+                body.mass,
+                body.entity,
+                g,
+            )
+        });
+    }
+
+    fn potential_at(&self, position: Vector, exclude_entity: Entity, g: Scalar) -> Option<Scalar> {
+        Some(self.calculate_potential_at_position(position, exclude_entity, g))
+    }
+
+    fn potential_energy(&self, bodies: &[OctreeBody], g: Scalar) -> Option<Scalar> {
+        Some(LinearOctree::potential_energy(self, bodies, g))
+    }
+
+    fn record_accelerations(&mut self, bodies: &[OctreeBody], accelerations: &[Vector]) {
+        LinearOctree::record_accelerations(self, bodies, accelerations);
+    }
+
+    fn theta(&self) -> Option<Scalar> {
+        Some(self.theta)
+    }
+
+    fn set_theta(&mut self, theta: Scalar) {
+        self.theta = theta;
+    }
+
+    fn bounds(&self) -> Vec<Aabb3d> {
+        LinearOctree::bounds(self)
+    }
+}
@@ -0,0 +1,233 @@
+//! Exact direct-summation gravity solver
+//!
+//! Sums the pair force between every two bodies with no approximation, at
+//! O(N²) cost. This is the reference the approximate solvers are measured
+//! against, and the better choice outright for small systems, where a tree
+//! saves little.
+//!
+//! Whole-system evaluations visit each pair once and apply the force to both
+//! bodies with opposite signs, which halves the work and conserves momentum
+//! to round-off.
+
+use super::{GravitySolver, PairForce, Softening};
+use crate::physics::math::{Scalar, Vector};
+use crate::physics::octree::OctreeBody;
+use bevy::prelude::Entity;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+
+/// Fewest rows of the pair triangle worth a task of their own
+const MIN_ROWS_PER_TASK: usize = 16;
+
+/// Exact O(N²) gravity solver
+#[derive(Debug, Clone)]
+pub struct DirectSummation {
+    pair_force: PairForce,
+    bodies: Vec<OctreeBody>,
+}
+
+impl DirectSummation {
+    /// Creates a solver with the same force clamping as the octree
+    ///
+    /// # Arguments
+    ///
+    /// * `min_distance` - Minimum distance between bodies to prevent force singularities
+    /// * `max_force` - Maximum allowed force magnitude
+    pub fn new(min_distance: Scalar, max_force: Scalar) -> Self {
+        Self {
+            pair_force: PairForce::new(min_distance, max_force),
+            bodies: Vec::new(),
+        }
+    }
+
+    /// Sets the kernel that softens the force between close bodies, with
+    /// `min_distance` as its softening length. Default is `Softening::Clamp`.
+    pub fn with_softening(mut self, softening: Softening) -> Self {
+        self.pair_force.softening = softening;
+        self
+    }
+}
+
+impl GravitySolver for DirectSummation {
+    fn name(&self) -> &'static str {
+        "direct"
+    }
+
+    fn build(&mut self, bodies: &[OctreeBody]) {
+        self.bodies.clear();
+        self.bodies.extend_from_slice(bodies);
+    }
+
+    fn force_at(
+        &self,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        self.bodies
+            .iter()
+            .filter(|other| other.entity != exclude_entity)
+            .map(|other| {
+                self.pair_force
+                    .force(other.position - position, g * mass * other.mass)
+            })
+            .sum()
+    }
+
+    fn force_and_jerk_at(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        self.bodies
+            .iter()
+            .filter(|other| other.entity != exclude_entity)
+            .fold((Vector::ZERO, Vector::ZERO), |(force, jerk), other| {
+                let (pair_force, pair_jerk) = self.pair_force.force_and_jerk(
+                    other.position - position,
+                    other.velocity - velocity,
+                    g * mass * other.mass,
+                );
+                (force + pair_force, jerk + pair_jerk)
+            })
+    }
+
+    fn potential_at(&self, position: Vector, exclude_entity: Entity, g: Scalar) -> Option<Scalar> {
+        Some(
+            self.bodies
+                .iter()
+                .filter(|other| other.entity != exclude_entity)
+                .map(|other| {
+                    self.pair_force
+                        .potential(position.distance(other.position), g * other.mass)
+                })
+                .sum(),
+        )
+    }
+
+    fn forces(&self, bodies: &[OctreeBody], g: Scalar, forces: &mut [Vector]) {
+        debug_assert_eq!(bodies.len(), forces.len());
+
+        sum_pairs(
+            bodies.len(),
+            |i, j| {
+                let (body, other) = (&bodies[i], &bodies[j]);
+                self.pair_force
+                    .force(other.position - body.position, g * body.mass * other.mass)
+            },
+            forces,
+        );
+    }
+
+    fn forces_and_jerks(
+        &self,
+        bodies: &[OctreeBody],
+        g: Scalar,
+        derivatives: &mut [(Vector, Vector)],
+    ) {
+        debug_assert_eq!(bodies.len(), derivatives.len());
+
+        sum_pairs(
+            bodies.len(),
+            |i, j| {
+                let (body, other) = (&bodies[i], &bodies[j]);
+                self.pair_force.force_and_jerk(
+                    other.position - body.position,
+                    other.velocity - body.velocity,
+                    g * body.mass * other.mass,
+                )
+            },
+            derivatives,
+        );
+    }
+}
+
+/// Quantity that two bodies exert on each other with opposite signs
+trait Antisymmetric: Copy + Send + Sync + 'static {
+    const ZERO: Self;
+
+    fn add(&mut self, other: Self);
+
+    fn subtract(&mut self, other: Self);
+}
+
+impl Antisymmetric for Vector {
+    const ZERO: Self = Vector::ZERO;
+
+    fn add(&mut self, other: Self) {
+        *self += other;
+    }
+
+    fn subtract(&mut self, other: Self) {
+        *self -= other;
+    }
+}
+
+impl Antisymmetric for (Vector, Vector) {
+    const ZERO: Self = (Vector::ZERO, Vector::ZERO);
+
+    fn add(&mut self, other: Self) {
+        self.0 += other.0;
+        self.1 += other.1;
+    }
+
+    fn subtract(&mut self, other: Self) {
+        self.0 -= other.0;
+        self.1 -= other.1;
+    }
+}
+
+/// Sum an antisymmetric interaction over every pair of `count` bodies
+///
+/// `interaction(i, j)` is what body `j` exerts on body `i`, for `i < j`;
+/// body `j` receives its negation. Row `i` of the pair triangle holds the
+/// `count - 1 - i` pairs that start at body `i`, so rows are dealt out to
+/// the compute task pool cyclically to even out the work. Each task sums
+/// into its own buffer, and the buffers are added together at the end.
+fn sum_pairs<T: Antisymmetric>(
+    count: usize,
+    interaction: impl Fn(usize, usize) -> T + Sync,
+    output: &mut [T],
+) {
+    let sum_rows = |first_row: usize, row_step: usize, sums: &mut [T]| {
+        for i in (first_row..count).step_by(row_step) {
+            let mut row_sum = T::ZERO;
+            for (j, sum) in sums.iter_mut().enumerate().skip(i + 1) {
+                let value = interaction(i, j);
+                row_sum.add(value);
+                sum.subtract(value);
+            }
+            sums[i].add(row_sum);
+        }
+    };
+
+    output.fill(T::ZERO);
+
+    let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let tasks = task_pool.thread_num().min(count / MIN_ROWS_PER_TASK).max(1);
+
+    if tasks == 1 {
+        sum_rows(0, 1, output);
+        return;
+    }
+
+    let partial_sums = task_pool.scope(|scope| {
+        let sum_rows = &sum_rows;
+        for task in 0..tasks {
+            scope.spawn(async move {
+                let mut sums = vec![T::ZERO; count];
+                sum_rows(task, tasks, &mut sums);
+                sums
+            });
+        }
+    });
+
+    for sums in partial_sums {
+        for (total, value) in output.iter_mut().zip(sums) {
+            total.add(value);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Radix-2 fast Fourier transforms on cubic grids
+//!
+//! A small pure-Rust complex FFT for the particle-mesh solver. One-dimensional
+//! transforms use the iterative Cooley-Tukey algorithm with precomputed
+//! twiddle factors, so line lengths must be powers of two. Three-dimensional
+//! transforms apply it along each axis in turn: every pass transforms the
+//! contiguous rows of the grid in parallel and then rotates the axes, so that
+//! after three passes each axis has been transformed once and the grid is
+//! back in its original layout.
+
+use crate::physics::math::Scalar;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use std::f64::consts::TAU;
+use std::ops::{Add, Mul, Sub};
+
+/// Complex number in Cartesian form
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+    pub re: Scalar,
+    pub im: Scalar,
+}
+
+impl Complex {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+
+    pub const fn new(re: Scalar, im: Scalar) -> Self {
+        Self { re, im }
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Mul<Scalar> for Complex {
+    type Output = Self;
+
+    fn mul(self, factor: Scalar) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+}
+
+/// Direction of a transform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// X(k) = Σ x(j)·exp(-2πi·jk/N)
+    Forward,
+    /// x(j) = Σ X(k)·exp(+2πi·jk/N), without the 1/N normalization
+    Inverse,
+}
+
+/// Precomputed radix-2 FFT of one line length
+#[derive(Debug, Clone)]
+pub struct Fft {
+    size: usize,
+    /// exp(-2πi·k/N) for k < N/2
+    twiddles: Vec<Complex>,
+    /// Bit-reversed index of each position
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    /// Creates a transform of `size` points
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `size` is not a power of two.
+    pub fn new(size: usize) -> Result<Self, String> {
+        if !size.is_power_of_two() {
+            return Err(format!("FFT size must be a power of two, got {size}"));
+        }
+
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -TAU * k as Scalar / size as Scalar;
+                Complex::new(angle.cos(), angle.sin())
+            })
+            .collect();
+
+        let bits = size.trailing_zeros();
+        let reversed = (0..size)
+            .map(|index| {
+                if bits == 0 {
+                    0
+                } else {
+                    index.reverse_bits() >> (usize::BITS - bits)
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            size,
+            twiddles,
+            reversed,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Transforms one line of `size` points in place
+    pub fn transform(&self, line: &mut [Complex], direction: Direction) {
+        debug_assert_eq!(line.len(), self.size);
+
+        for (index, &reversed) in self.reversed.iter().enumerate() {
+            if index < reversed {
+                line.swap(index, reversed);
+            }
+        }
+
+        let mut half = 1;
+        while half < self.size {
+            let stride = self.size / (2 * half);
+            for block in line.chunks_exact_mut(2 * half) {
+                let (low, high) = block.split_at_mut(half);
+                for (k, (a, b)) in low.iter_mut().zip(high.iter_mut()).enumerate() {
+                    let twiddle = match direction {
+                        Direction::Forward => self.twiddles[k * stride],
+                        Direction::Inverse => self.twiddles[k * stride].conjugate(),
+                    };
+                    let product = *b * twiddle;
+                    *b = *a - product;
+                    *a = *a + product;
+                }
+            }
+            half *= 2;
+        }
+    }
+
+    /// Transforms a cubic grid of `size`³ points in place, with the last
+    /// index contiguous
+    ///
+    /// `scratch` is resized to the grid and used for the axis rotations.
+    pub fn transform_3d(
+        &self,
+        grid: &mut Vec<Complex>,
+        scratch: &mut Vec<Complex>,
+        direction: Direction,
+    ) {
+        let n = self.size;
+        debug_assert_eq!(grid.len(), n * n * n);
+        scratch.resize(grid.len(), Complex::ZERO);
+
+        for _ in 0..3 {
+            for_each_rows(grid, n, |_, rows| {
+                for line in rows.chunks_exact_mut(n) {
+                    self.transform(line, direction);
+                }
+            });
+
+            // Rotate the axes so the middle one becomes contiguous:
+            // (a, b, c) with c contiguous becomes (c, a, b)
+            let source = &*grid;
+            for_each_rows(scratch, n, |first_row, rows| {
+                for (row, line) in rows.chunks_exact_mut(n).enumerate() {
+                    let (c, a) = ((first_row + row) / n, (first_row + row) % n);
+                    for (b, value) in line.iter_mut().enumerate() {
+                        *value = source[(a * n + b) * n + c];
+                    }
+                }
+            });
+            std::mem::swap(grid, scratch);
+        }
+    }
+}
+
+/// Fewest rows of a grid worth a task of their own
+const MIN_ROWS_PER_TASK: usize = 64;
+
+/// Runs `f` on whole rows of `row_length` elements in parallel on the compute
+/// task pool, passing the index of the first row of each chunk
+fn for_each_rows(
+    data: &mut [Complex],
+    row_length: usize,
+    f: impl Fn(usize, &mut [Complex]) + Sync,
+) {
+    let rows = data.len() / row_length;
+    let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let tasks = task_pool.thread_num().min(rows / MIN_ROWS_PER_TASK).max(1);
+
+    if tasks == 1 {
+        f(0, data);
+        return;
+    }
+
+    let rows_per_task = rows.div_ceil(tasks);
+    task_pool.scope(|scope| {
+        let f = &f;
+        for (task, chunk) in data.chunks_mut(rows_per_task * row_length).enumerate() {
+            scope.spawn(async move { f(task * rows_per_task, chunk) });
+        }
+    });
+}
@@ -0,0 +1,826 @@
+//! Cartesian fast multipole method gravity solver
+//!
+//! Bodies are sorted into an adaptive octree with the same octant subdivision
+//! as `Octree`. Each cell carries the Cartesian multipole moments of its
+//! bodies about its center of mass,
+//!
+//! ```text
+//! Mₙ = Σⱼ mⱼ·(c - xⱼ)ⁿ     for |n| ≤ p
+//! ```
+//!
+//! where `n` is a multi-index and `p` the expansion order. A dual tree walk
+//! then pairs up cells: two cells whose radii sum to less than θ times their
+//! separation R exchange their moments into local expansions of each other,
+//!
+//! ```text
+//! Lₖ += Σₙ C(n+k, n)·aₙ₊ₖ(R)·Mₙ     for |n| + |k| ≤ p + 1
+//! ```
+//!
+//! with `aₘ` the Taylor coefficients of 1/|R|, while cells too close for that
+//! are split, down to direct pairwise forces between leaves. A final
+//! downward pass shifts each local expansion to the cell's children and
+//! evaluates it at every body. Each cell interacts with a bounded number of
+//! others, so a whole-system evaluation costs O(N).
+//!
+//! Forces include the multipole moments up to degree `p`, so order 2 matches
+//! a quadrupole Barnes-Hut tree and each further order gains a power of θ.
+//!
+//! # Reference
+//!
+//! Dehnen (2002) "A Hierarchical O(N) Force Calculation Algorithm",
+//! Journal of Computational Physics 179, 27-42.
+
+use super::{GravitySolver, PairForce, Softening};
+use crate::physics::aabb3d::Aabb3d;
+use crate::physics::math::{Scalar, Vector, VectorExt};
+use crate::physics::octree::{MAX_OCTREE_DEPTH, Octant, OctreeBody};
+use bevy::prelude::Entity;
+use std::ops::Range;
+
+/// Lowest supported expansion order
+pub const MIN_ORDER: usize = 2;
+
+/// Highest supported expansion order
+pub const MAX_ORDER: usize = 4;
+
+/// Number of multi-indices of total degree up to `degree`
+const fn term_count(degree: usize) -> usize {
+    (degree + 1) * (degree + 2) * (degree + 3) / 6
+}
+
+/// Size of the coefficient buffers, enough for local expansions of the
+/// highest order
+const MAX_TERMS: usize = term_count(MAX_ORDER + 1);
+
+/// Binomial coefficient C(n, k) of a multi-index pair, Π C(nᵢ, kᵢ)
+fn binomial(n: [usize; 3], k: [usize; 3]) -> Scalar {
+    (0..3)
+        .map(|axis| {
+            (0..k[axis])
+                .map(|i| (n[axis] - i) as Scalar / (i + 1) as Scalar)
+                .product::<Scalar>()
+        })
+        .product()
+}
+
+/// Multi-indices up to a fixed total degree, ordered by degree
+#[derive(Debug, Clone)]
+struct MultiIndices {
+    exponents: Vec<[usize; 3]>,
+    lookup: Vec<usize>,
+    side: usize,
+}
+
+impl MultiIndices {
+    fn new(degree: usize) -> Self {
+        let mut exponents = Vec::with_capacity(term_count(degree));
+        for total in 0..=degree {
+            for x in (0..=total).rev() {
+                for y in (0..=total - x).rev() {
+                    exponents.push([x, y, total - x - y]);
+                }
+            }
+        }
+
+        let side = degree + 1;
+        let mut lookup = vec![usize::MAX; side * side * side];
+        for (index, n) in exponents.iter().enumerate() {
+            lookup[(n[0] * side + n[1]) * side + n[2]] = index;
+        }
+
+        Self {
+            exponents,
+            lookup,
+            side,
+        }
+    }
+
+    fn index(&self, n: [usize; 3]) -> usize {
+        self.lookup[(n[0] * self.side + n[1]) * self.side + n[2]]
+    }
+
+    /// Index of `n` lowered by `by` in `axis`, which must hold at least that much
+    fn lowered(&self, mut n: [usize; 3], axis: usize, by: usize) -> usize {
+        n[axis] -= by;
+        self.index(n)
+    }
+
+    /// Powers xⁿ of `x` for each of the first `terms` multi-indices
+    fn powers(&self, x: Vector, terms: usize, out: &mut [Scalar]) {
+        out[0] = 1.0;
+        for index in 1..terms {
+            let n = self.exponents[index];
+            let axis = (0..3).find(|&axis| n[axis] > 0).unwrap_or(0);
+            out[index] = out[self.lowered(n, axis, 1)] * x[axis];
+        }
+    }
+
+    /// Taylor coefficients aₙ = ∂ⁿ(1/|r|)/n! of the inverse distance at `r`
+    /// for each of the first `terms` multi-indices
+    ///
+    /// Uses the recurrence
+    /// k·r²·aₙ = -(2k - 1)·Σᵢ rᵢ·aₙ₋ₑᵢ - (k - 1)·Σᵢ aₙ₋₂ₑᵢ for k = |n|.
+    fn inverse_distance_taylor(&self, r: Vector, terms: usize, out: &mut [Scalar]) {
+        let distance_squared = r.length_squared();
+        out[0] = distance_squared.sqrt().recip();
+
+        for index in 1..terms {
+            let n = self.exponents[index];
+            let degree = (n[0] + n[1] + n[2]) as Scalar;
+
+            let mut first = 0.0;
+            let mut second = 0.0;
+            for axis in 0..3 {
+                if n[axis] >= 1 {
+                    first += r[axis] * out[self.lowered(n, axis, 1)];
+                }
+                if n[axis] >= 2 {
+                    second += out[self.lowered(n, axis, 2)];
+                }
+            }
+
+            out[index] = (-(2.0 * degree - 1.0) * first - (degree - 1.0) * second)
+                / (degree * distance_squared);
+        }
+    }
+}
+
+/// One term of a precomputed expansion operator
+#[derive(Debug, Clone, Copy)]
+struct Term {
+    target: usize,
+    source: usize,
+    coefficient_index: usize,
+    coefficient: Scalar,
+}
+
+/// Precomputed operators for expansions of one order
+#[derive(Debug, Clone)]
+struct Operators {
+    indices: MultiIndices,
+    /// Number of multipole terms, of degree up to the order
+    multipole_terms: usize,
+    /// Number of local terms, of degree up to the order plus one
+    local_terms: usize,
+    /// Multipole to local: target k, source n, coefficient n+k
+    multipole_to_local: Vec<Term>,
+    /// Whether each multipole-to-local term changes sign when the
+    /// separation is reversed
+    multipole_to_local_odd: Vec<bool>,
+    /// Local to local: target j, source k, coefficient k-j
+    local_to_local: Vec<Term>,
+    /// Gradient of a local expansion: target axis, source k, coefficient k-eᵢ
+    local_gradient: Vec<Term>,
+    /// Gradient of a multipole expansion: target axis, source n, coefficient n+eᵢ
+    multipole_gradient: Vec<Term>,
+}
+
+impl Operators {
+    fn new(order: usize) -> Self {
+        let indices = MultiIndices::new(order + 1);
+        let multipole_terms = term_count(order);
+        let local_terms = term_count(order + 1);
+        let degree = |n: [usize; 3]| n[0] + n[1] + n[2];
+        let add = |a: [usize; 3], b: [usize; 3]| [a[0] + b[0], a[1] + b[1], a[2] + b[2]];
+        let unit = |axis: usize| {
+            let mut e = [0; 3];
+            e[axis] = 1;
+            e
+        };
+
+        let mut multipole_to_local = Vec::new();
+        let mut multipole_to_local_odd = Vec::new();
+        for (target, &k) in indices.exponents.iter().enumerate() {
+            for (source, &n) in indices.exponents[..multipole_terms].iter().enumerate() {
+                if degree(n) + degree(k) > order + 1 {
+                    continue;
+                }
+                let sum = add(n, k);
+                multipole_to_local.push(Term {
+                    target,
+                    source,
+                    coefficient_index: indices.index(sum),
+                    coefficient: binomial(sum, n),
+                });
+                multipole_to_local_odd.push(degree(sum) % 2 == 1);
+            }
+        }
+
+        let mut local_to_local = Vec::new();
+        for (target, &j) in indices.exponents.iter().enumerate() {
+            for (source, &k) in indices.exponents.iter().enumerate() {
+                if (0..3).any(|axis| k[axis] < j[axis]) {
+                    continue;
+                }
+                local_to_local.push(Term {
+                    target,
+                    source,
+                    coefficient_index: indices.index([k[0] - j[0], k[1] - j[1], k[2] - j[2]]),
+                    coefficient: binomial(k, j),
+                });
+            }
+        }
+
+        let mut local_gradient = Vec::new();
+        for (source, &k) in indices.exponents.iter().enumerate() {
+            for axis in 0..3 {
+                if k[axis] > 0 {
+                    local_gradient.push(Term {
+                        target: axis,
+                        source,
+                        coefficient_index: indices.lowered(k, axis, 1),
+                        coefficient: k[axis] as Scalar,
+                    });
+                }
+            }
+        }
+
+        let mut multipole_gradient = Vec::new();
+        for (source, &n) in indices.exponents[..multipole_terms].iter().enumerate() {
+            for axis in 0..3 {
+                multipole_gradient.push(Term {
+                    target: axis,
+                    source,
+                    coefficient_index: indices.index(add(n, unit(axis))),
+                    coefficient: (n[axis] + 1) as Scalar,
+                });
+            }
+        }
+
+        Self {
+            indices,
+            multipole_terms,
+            local_terms,
+            multipole_to_local,
+            multipole_to_local_odd,
+            local_to_local,
+            local_gradient,
+            multipole_gradient,
+        }
+    }
+
+    /// Gradient of the potential of a multipole expansion at offset `r`
+    /// from its center
+    fn multipole_field(&self, moments: &[Scalar], r: Vector) -> Vector {
+        let mut coefficients = [0.0; MAX_TERMS];
+        self.indices
+            .inverse_distance_taylor(r, self.local_terms, &mut coefficients);
+
+        let mut field = Vector::ZERO;
+        for term in &self.multipole_gradient {
+            field[term.target] +=
+                term.coefficient * moments[term.source] * coefficients[term.coefficient_index];
+        }
+        field
+    }
+
+    /// Gradient of the potential of a local expansion at offset `h` from
+    /// its center
+    fn local_field(&self, local: &[Scalar], h: Vector) -> Vector {
+        let mut powers = [0.0; MAX_TERMS];
+        self.indices.powers(h, self.local_terms, &mut powers);
+
+        let mut field = Vector::ZERO;
+        for term in &self.local_gradient {
+            field[term.target] +=
+                term.coefficient * local[term.source] * powers[term.coefficient_index];
+        }
+        field
+    }
+}
+
+/// A cell of the FMM tree
+#[derive(Debug, Clone)]
+struct Cell {
+    bounds: Aabb3d,
+    center_of_mass: Vector,
+    center_of_mass_velocity: Vector,
+    total_mass: Scalar,
+    /// Largest distance from the center of mass to any of the cell's bodies
+    radius: Scalar,
+    /// Indices of the cell's bodies in tree order
+    bodies: Range<usize>,
+    /// Indices of the cell's children, empty for leaves
+    children: Range<usize>,
+}
+
+impl Cell {
+    fn new(bounds: Aabb3d, bodies: Range<usize>) -> Self {
+        Self {
+            bounds,
+            center_of_mass: bounds.center(),
+            center_of_mass_velocity: Vector::ZERO,
+            total_mass: 0.0,
+            radius: 0.0,
+            bodies,
+            children: 0..0,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+/// Cartesian fast multipole method solver with O(N) whole-system evaluation
+#[derive(Debug, Clone)]
+pub struct FastMultipole {
+    order: usize,
+    theta: Scalar,
+    leaf_threshold: usize,
+    pair_force: PairForce,
+    operators: Operators,
+    /// Bodies in tree order
+    bodies: Vec<OctreeBody>,
+    /// Index in the order given to `build` of each body in tree order
+    build_order: Vec<usize>,
+    cells: Vec<Cell>,
+    /// Multipole moments of each cell, `multipole_terms` per cell
+    multipoles: Vec<Scalar>,
+}
+
+impl FastMultipole {
+    /// Creates a solver whose direct force between nearby bodies is softened
+    /// by the kernel set with `with_softening`
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Highest multipole degree, from `MIN_ORDER` to `MAX_ORDER`
+    /// * `theta` - Opening angle; cells interact through their expansions once
+    ///   the sum of their radii is below theta times their separation
+    /// * `leaf_threshold` - Maximum bodies per leaf cell
+    /// * `min_distance` - Minimum distance between bodies to prevent force singularities
+    /// * `max_force` - Maximum allowed force magnitude between two bodies
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the order is out of range, theta is not between 0
+    /// and 1, or the leaf threshold is zero.
+    pub fn new(
+        order: usize,
+        theta: Scalar,
+        leaf_threshold: usize,
+        min_distance: Scalar,
+        max_force: Scalar,
+    ) -> Result<Self, String> {
+        if !(MIN_ORDER..=MAX_ORDER).contains(&order) {
+            return Err(format!(
+                "FMM order must be between {MIN_ORDER} and {MAX_ORDER}, got {order}"
+            ));
+        }
+        if !(theta > 0.0 && theta < 1.0) {
+            return Err(format!(
+                "FMM theta must be between 0 and 1 exclusive, got {theta}"
+            ));
+        }
+        if leaf_threshold == 0 {
+            return Err("FMM leaf threshold must be at least 1".to_string());
+        }
+
+        Ok(Self {
+            order,
+            theta,
+            leaf_threshold,
+            pair_force: PairForce::new(min_distance, max_force),
+            operators: Operators::new(order),
+            bodies: Vec::new(),
+            build_order: Vec::new(),
+            cells: Vec::new(),
+            multipoles: Vec::new(),
+        })
+    }
+
+    /// Sets the kernel that softens the direct force between nearby bodies,
+    /// with `min_distance` as its softening length. Default is
+    /// `Softening::Clamp`.
+    pub fn with_softening(mut self, softening: Softening) -> Self {
+        self.pair_force.softening = softening;
+        self
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    fn multipole(&self, cell: usize) -> &[Scalar] {
+        let terms = self.operators.multipole_terms;
+        &self.multipoles[cell * terms..(cell + 1) * terms]
+    }
+
+    /// Split a cell's bodies among its octants, depth first
+    fn subdivide(&mut self, cell: usize, depth: usize, scratch: &mut Vec<(OctreeBody, usize)>) {
+        let range = self.cells[cell].bodies.clone();
+        if range.len() <= self.leaf_threshold || depth >= MAX_OCTREE_DEPTH {
+            return;
+        }
+
+        let bounds = self.cells[cell].bounds;
+        let center = bounds.center();
+        let octants = bounds.octants();
+
+        // Counting sort of the cell's bodies by octant
+        let mut counts = [0usize; 8];
+        for body in &self.bodies[range.clone()] {
+            counts[Octant::from_position(body.position, center).index()] += 1;
+        }
+        let mut offsets = [0usize; 8];
+        for octant in 1..8 {
+            offsets[octant] = offsets[octant - 1] + counts[octant - 1];
+        }
+
+        scratch.clear();
+        scratch.extend(
+            self.bodies[range.clone()]
+                .iter()
+                .copied()
+                .zip(self.build_order[range.clone()].iter().copied()),
+        );
+        for &(body, index) in scratch.iter() {
+            let octant = Octant::from_position(body.position, center).index();
+            let slot = range.start + offsets[octant];
+            self.bodies[slot] = body;
+            self.build_order[slot] = index;
+            offsets[octant] += 1;
+        }
+
+        let first_child = self.cells.len();
+        let mut start = range.start;
+        for (octant, &count) in counts.iter().enumerate() {
+            if count > 0 {
+                self.cells
+                    .push(Cell::new(octants[octant], start..start + count));
+                start += count;
+            }
+        }
+        self.cells[cell].children = first_child..self.cells.len();
+
+        for child in first_child..self.cells.len() {
+            self.subdivide(child, depth + 1, scratch);
+        }
+    }
+
+    /// Compute the mass, center of mass, radius, and multipole moments of
+    /// every cell from its bodies
+    fn compute_moments(&mut self) {
+        let terms = self.operators.multipole_terms;
+        self.multipoles.clear();
+        self.multipoles.resize(self.cells.len() * terms, 0.0);
+        let mut powers = [0.0; MAX_TERMS];
+
+        for (cell, moments) in self
+            .cells
+            .iter_mut()
+            .zip(self.multipoles.chunks_exact_mut(terms))
+        {
+            let bodies = &self.bodies[cell.bodies.clone()];
+            let (total_mass, weighted_sum, momentum) = bodies.iter().fold(
+                (0.0, Vector::ZERO, Vector::ZERO),
+                |(mass, position, momentum), body| {
+                    (
+                        mass + body.mass,
+                        position + body.position * body.mass,
+                        momentum + body.velocity * body.mass,
+                    )
+                },
+            );
+
+            if total_mass > 0.0 {
+                cell.center_of_mass = weighted_sum / total_mass;
+                cell.center_of_mass_velocity = momentum / total_mass;
+            }
+            cell.total_mass = total_mass;
+            cell.radius = bodies
+                .iter()
+                .map(|body| body.position.distance(cell.center_of_mass))
+                .fold(0.0, Scalar::max);
+
+            for body in bodies {
+                self.operators.indices.powers(
+                    cell.center_of_mass - body.position,
+                    terms,
+                    &mut powers,
+                );
+                for (moment, power) in moments.iter_mut().zip(&powers[..terms]) {
+                    *moment += body.mass * power;
+                }
+            }
+        }
+    }
+
+    /// Whether two cells are far enough apart to interact through their
+    /// expansions
+    fn well_separated(&self, a: &Cell, b: &Cell) -> bool {
+        let radii = a.radius + b.radius;
+        radii * radii
+            < self.theta * self.theta * a.center_of_mass.distance_squared(b.center_of_mass)
+    }
+
+    /// Interactions of a cell's bodies among themselves
+    fn interact_within(&self, cell: usize, evaluation: &mut Evaluation, g: Scalar) {
+        let children = self.cells[cell].children.clone();
+        if children.is_empty() {
+            let range = self.cells[cell].bodies.clone();
+            for i in range.clone() {
+                for j in i + 1..range.end {
+                    self.pair(i, j, evaluation, g);
+                }
+            }
+            return;
+        }
+
+        for a in children.clone() {
+            self.interact_within(a, evaluation, g);
+            for b in a + 1..children.end {
+                self.interact_between(a, b, evaluation, g);
+            }
+        }
+    }
+
+    /// Interactions between the bodies of two disjoint cells
+    fn interact_between(&self, a: usize, b: usize, evaluation: &mut Evaluation, g: Scalar) {
+        let (cell_a, cell_b) = (&self.cells[a], &self.cells[b]);
+
+        if self.well_separated(cell_a, cell_b) {
+            self.multipole_to_local(a, b, evaluation);
+        } else if cell_a.is_leaf() && cell_b.is_leaf() {
+            for i in cell_a.bodies.clone() {
+                for j in cell_b.bodies.clone() {
+                    self.pair(i, j, evaluation, g);
+                }
+            }
+        } else if cell_b.is_leaf() || (!cell_a.is_leaf() && cell_a.radius >= cell_b.radius) {
+            for child in cell_a.children.clone() {
+                self.interact_between(child, b, evaluation, g);
+            }
+        } else {
+            for child in cell_b.children.clone() {
+                self.interact_between(a, child, evaluation, g);
+            }
+        }
+    }
+
+    /// Direct force between two bodies, applied to both
+    fn pair(&self, i: usize, j: usize, evaluation: &mut Evaluation, g: Scalar) {
+        let (body, other) = (&self.bodies[i], &self.bodies[j]);
+        let force = self
+            .pair_force
+            .force(other.position - body.position, g * body.mass * other.mass);
+        evaluation.forces[i] += force;
+        evaluation.forces[j] -= force;
+    }
+
+    /// Add the multipole expansion of each cell to the local expansion of
+    /// the other
+    fn multipole_to_local(&self, a: usize, b: usize, evaluation: &mut Evaluation) {
+        let operators = &self.operators;
+        let local_terms = operators.local_terms;
+        let separation = self.cells[a].center_of_mass - self.cells[b].center_of_mass;
+
+        let mut coefficients = [0.0; MAX_TERMS];
+        operators
+            .indices
+            .inverse_distance_taylor(separation, local_terms, &mut coefficients);
+
+        let (moments_a, moments_b) = (self.multipole(a), self.multipole(b));
+        let locals = &mut evaluation.locals;
+        for (term, &odd) in operators
+            .multipole_to_local
+            .iter()
+            .zip(&operators.multipole_to_local_odd)
+        {
+            let factor = term.coefficient * coefficients[term.coefficient_index];
+            let reversed = if odd { -factor } else { factor };
+            locals[a * local_terms + term.target] += factor * moments_b[term.source];
+            locals[b * local_terms + term.target] += reversed * moments_a[term.source];
+        }
+    }
+
+    /// Shift each cell's local expansion to its children and evaluate the
+    /// leaves' expansions at their bodies
+    fn evaluate_locals(&self, evaluation: &mut Evaluation, g: Scalar) {
+        let operators = &self.operators;
+        let local_terms = operators.local_terms;
+        let mut parent = [0.0; MAX_TERMS];
+        let mut powers = [0.0; MAX_TERMS];
+
+        // Children always follow their parent, so each local expansion is
+        // complete by the time it is visited
+        for (index, cell) in self.cells.iter().enumerate() {
+            let local = index * local_terms..(index + 1) * local_terms;
+            parent[..local_terms].copy_from_slice(&evaluation.locals[local.clone()]);
+
+            if cell.is_leaf() {
+                for i in cell.bodies.clone() {
+                    let body = &self.bodies[i];
+                    let field = operators
+                        .local_field(&parent[..local_terms], body.position - cell.center_of_mass);
+                    evaluation.forces[i] += field * (g * body.mass);
+                }
+                continue;
+            }
+
+            for child in cell.children.clone() {
+                operators.indices.powers(
+                    self.cells[child].center_of_mass - cell.center_of_mass,
+                    local_terms,
+                    &mut powers,
+                );
+                let child_local = &mut evaluation.locals[child * local_terms..][..local_terms];
+                for term in &operators.local_to_local {
+                    child_local[term.target] +=
+                        term.coefficient * parent[term.source] * powers[term.coefficient_index];
+                }
+            }
+        }
+    }
+
+    /// Force at a position from one cell and its descendants, walking the
+    /// tree for a single target
+    fn force_from_cell(
+        &self,
+        cell: usize,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        let node = &self.cells[cell];
+        let offset = position - node.center_of_mass;
+
+        if node.radius * node.radius < self.theta * self.theta * offset.length_squared() {
+            return self.operators.multipole_field(self.multipole(cell), offset) * (g * mass);
+        }
+
+        if node.is_leaf() {
+            return self.bodies[node.bodies.clone()]
+                .iter()
+                .filter(|other| other.entity != exclude_entity)
+                .map(|other| {
+                    self.pair_force
+                        .force(other.position - position, g * mass * other.mass)
+                })
+                .sum();
+        }
+
+        node.children
+            .clone()
+            .map(|child| self.force_from_cell(child, position, mass, exclude_entity, g))
+            .sum()
+    }
+
+    /// Force and its time derivative at a position from one cell and its
+    /// descendants
+    ///
+    /// Accepted cells contribute their full multipole force but only the
+    /// derivative of their monopole force, as in the octree.
+    fn force_and_jerk_from_cell(
+        &self,
+        cell: usize,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        let node = &self.cells[cell];
+        let offset = position - node.center_of_mass;
+
+        if node.radius * node.radius < self.theta * self.theta * offset.length_squared() {
+            let force = self.operators.multipole_field(self.multipole(cell), offset) * (g * mass);
+            let (_, jerk) = self.pair_force.force_and_jerk(
+                -offset,
+                node.center_of_mass_velocity - velocity,
+                g * mass * node.total_mass,
+            );
+            return (force, jerk);
+        }
+
+        if node.is_leaf() {
+            return self.bodies[node.bodies.clone()]
+                .iter()
+                .filter(|other| other.entity != exclude_entity)
+                .fold((Vector::ZERO, Vector::ZERO), |(force, jerk), other| {
+                    let (pair_force, pair_jerk) = self.pair_force.force_and_jerk(
+                        other.position - position,
+                        other.velocity - velocity,
+                        g * mass * other.mass,
+                    );
+                    (force + pair_force, jerk + pair_jerk)
+                });
+        }
+
+        node.children
+            .clone()
+            .fold((Vector::ZERO, Vector::ZERO), |(force, jerk), child| {
+                let (child_force, child_jerk) = self.force_and_jerk_from_cell(
+                    child,
+                    position,
+                    velocity,
+                    mass,
+                    exclude_entity,
+                    g,
+                );
+                (force + child_force, jerk + child_jerk)
+            })
+    }
+}
+
+/// Accumulators of one whole-system evaluation
+struct Evaluation {
+    /// Local expansion of each cell, `local_terms` per cell
+    locals: Vec<Scalar>,
+    /// Force on each body in tree order
+    forces: Vec<Vector>,
+}
+
+impl GravitySolver for FastMultipole {
+    fn name(&self) -> &'static str {
+        "fmm"
+    }
+
+    fn build(&mut self, bodies: &[OctreeBody]) {
+        self.bodies.clear();
+        self.build_order.clear();
+        self.cells.clear();
+
+        let Some(first) = bodies.first() else {
+            self.multipoles.clear();
+            return;
+        };
+
+        self.bodies.extend_from_slice(bodies);
+        self.build_order.extend(0..bodies.len());
+
+        // Same padded bounds as the octree
+        let (min, max) =
+            bodies
+                .iter()
+                .fold((first.position, first.position), |(min, max), body| {
+                    (
+                        min.component_min(body.position),
+                        max.component_max(body.position),
+                    )
+                });
+        let padding = (max - min) * 0.1;
+        self.cells.push(Cell::new(
+            Aabb3d::new(min - padding, max + padding),
+            0..bodies.len(),
+        ));
+
+        let mut scratch = Vec::with_capacity(bodies.len());
+        self.subdivide(0, 0, &mut scratch);
+        self.compute_moments();
+    }
+
+    fn force_at(
+        &self,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        if self.cells.is_empty() {
+            return Vector::ZERO;
+        }
+        self.force_from_cell(0, position, mass, exclude_entity, g)
+    }
+
+    fn force_and_jerk_at(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        if self.cells.is_empty() {
+            return (Vector::ZERO, Vector::ZERO);
+        }
+        self.force_and_jerk_from_cell(0, position, velocity, mass, exclude_entity, g)
+    }
+
+    fn forces(&self, bodies: &[OctreeBody], g: Scalar, forces: &mut [Vector]) {
+        debug_assert_eq!(bodies.len(), forces.len());
+        debug_assert_eq!(bodies.len(), self.bodies.len());
+
+        if self.cells.is_empty() {
+            return;
+        }
+
+        let mut evaluation = Evaluation {
+            locals: vec![0.0; self.cells.len() * self.operators.local_terms],
+            forces: vec![Vector::ZERO; self.bodies.len()],
+        };
+
+        self.interact_within(0, &mut evaluation, g);
+        self.evaluate_locals(&mut evaluation, g);
+
+        for (&index, &force) in self.build_order.iter().zip(&evaluation.forces) {
+            forces[index] = force;
+        }
+    }
+
+    fn bounds(&self) -> Vec<Aabb3d> {
+        self.cells.iter().map(|cell| cell.bounds).collect()
+    }
+}
@@ -0,0 +1,137 @@
+//! Sampled force error of an approximate gravity solver, and tuning of its
+//! opening angle toward a target
+//!
+//! Exact forces for every body cost O(N²), but the error of an approximate
+//! solver is well estimated from a few randomly chosen bodies, each summed
+//! exactly against all others in O(N). `ThetaTuner` uses such estimates, or
+//! the frame time, to steer theta between bounds.
+
+use super::GravitySolver;
+use crate::physics::math::Scalar;
+use crate::physics::octree::OctreeBody;
+use serde::{Deserialize, Serialize};
+
+/// Assumed growth of the force error with theta, error ∝ θ^3, from the
+/// monopole Barnes-Hut error on a Plummer sphere
+const ERROR_EXPONENT: Scalar = 3.0;
+
+/// Assumed fall of the force cost with theta, cost ∝ θ^-1.5
+const COST_EXPONENT: Scalar = 1.5;
+
+/// Largest factor by which a single tuning step changes theta
+const MAX_STEP: Scalar = 1.25;
+
+/// Relative force errors of a solver over a sample of bodies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceErrorEstimate {
+    /// Root mean square of |F - F_exact| / |F_exact| over the sample
+    pub rms_relative_error: Scalar,
+    /// Largest |F - F_exact| / |F_exact| in the sample
+    pub max_relative_error: Scalar,
+    /// Bodies in the sample with a nonzero exact force
+    pub sample_count: usize,
+}
+
+/// Compare the forces of `solver` with those of `reference` on the bodies at
+/// `samples`, indices into `bodies`
+///
+/// Both solvers must have been built from `bodies`; the reference is
+/// normally `DirectSummation`. Bodies with no exact force are skipped, and
+/// `None` is returned if no body is left.
+pub fn estimate_force_error(
+    solver: &dyn GravitySolver,
+    reference: &dyn GravitySolver,
+    bodies: &[OctreeBody],
+    samples: &[usize],
+    g: Scalar,
+) -> Option<ForceErrorEstimate> {
+    let mut sum_of_squares = 0.0;
+    let mut max_relative_error: Scalar = 0.0;
+    let mut sample_count = 0;
+
+    for body in samples.iter().map(|&index| &bodies[index]) {
+        let exact = reference.force_at(body.position, body.mass, body.entity, g);
+        if exact.length_squared() == 0.0 {
+            continue;
+        }
+
+        let force = solver.force_at(body.position, body.mass, body.entity, g);
+        let relative_error = (force - exact).length() / exact.length();
+        sum_of_squares += relative_error * relative_error;
+        max_relative_error = max_relative_error.max(relative_error);
+        sample_count += 1;
+    }
+
+    (sample_count > 0).then(|| ForceErrorEstimate {
+        rms_relative_error: (sum_of_squares / sample_count as Scalar).sqrt(),
+        max_relative_error,
+        sample_count,
+    })
+}
+
+/// What theta auto-tuning steers toward
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThetaAutoTune {
+    /// Keep theta as configured
+    #[default]
+    Off,
+    /// The RMS relative force error of the sampled bodies
+    ForceError,
+    /// The frame time
+    FrameTime,
+}
+
+/// Steers theta between bounds toward a target force error or frame time
+///
+/// Each step scales theta by the factor that would meet the target if error
+/// grew as θ^3 and cost fell as θ^-1.5, limited to a factor of 1.25 either
+/// way so that noisy measurements do not make theta oscillate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThetaTuner {
+    pub target: ThetaAutoTune,
+    /// RMS relative force error to aim for with `ThetaAutoTune::ForceError`
+    pub target_error: Scalar,
+    /// Frame time in seconds to aim for with `ThetaAutoTune::FrameTime`
+    pub target_frame_time: Scalar,
+    pub min_theta: Scalar,
+    pub max_theta: Scalar,
+}
+
+impl ThetaTuner {
+    /// Theta for the next measurement, from the current `theta` and the
+    /// latest force error estimate or frame time in seconds
+    ///
+    /// Returns `theta` unchanged when tuning is off or the measurement the
+    /// target needs is missing or gives no direction. Should `min_theta`
+    /// exceed `max_theta`, `min_theta` wins.
+    pub fn next_theta(
+        &self,
+        theta: Scalar,
+        estimate: Option<&ForceErrorEstimate>,
+        frame_time: Option<Scalar>,
+    ) -> Scalar {
+        let factor = match self.target {
+            ThetaAutoTune::Off => return theta,
+            ThetaAutoTune::ForceError => match estimate {
+                Some(estimate) => {
+                    (self.target_error / estimate.rms_relative_error).powf(1.0 / ERROR_EXPONENT)
+                }
+                None => return theta,
+            },
+            ThetaAutoTune::FrameTime => match frame_time {
+                Some(frame_time) => (frame_time / self.target_frame_time).powf(1.0 / COST_EXPONENT),
+                None => return theta,
+            },
+        };
+
+        // 0 / 0 when both the measurement and its target are zero
+        if factor.is_nan() {
+            return theta;
+        }
+
+        (theta * factor.clamp(1.0 / MAX_STEP, MAX_STEP))
+            .min(self.max_theta)
+            .max(self.min_theta)
+    }
+}
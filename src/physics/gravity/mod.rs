@@ -0,0 +1,404 @@
+//! Gravity solvers for N-body force calculation
+//!
+//! A `GravitySolver` is built from a snapshot of every body and then answers
+//! force queries against it. The simulation drives whichever solver is
+//! selected in `PhysicsConfig::gravity_solver`:
+//!
+//! - `barnes_hut`: the `Octree`, O(N log N) with an approximation controlled
+//!   by `octree_theta`
+//! - `direct`: exact O(N²) pairwise summation, for small systems and for
+//!   measuring the error of the approximate solvers
+//! - `fmm`: the Cartesian fast multipole method, O(N) with accuracy set by
+//!   the expansion order and opening angle in `PhysicsConfig::fmm`
+//! - `particle_mesh`: FFT convolution of the mass on a grid, for very large
+//!   systems, with an optional P3M short-range correction, configured in
+//!   `PhysicsConfig::particle_mesh`
+//!
+//! All solvers share the same softened Newtonian pair force, `PairForce`, so
+//! they agree exactly wherever no approximation is made. Its `Softening`
+//! kernel is Plummer or cubic-spline softening, or the legacy clamp on
+//! distance and force. The particle mesh replaces it with a smooth mesh force
+//! below a few grid cells unless the P3M correction is enabled.
+
+pub mod barnes_hut;
+pub mod direct;
+pub mod fft;
+pub mod fmm;
+pub mod force_error;
+pub mod particle_mesh;
+
+pub use direct::DirectSummation;
+pub use fmm::FastMultipole;
+pub use force_error::{ForceErrorEstimate, ThetaAutoTune, ThetaTuner, estimate_force_error};
+pub use particle_mesh::ParticleMesh;
+
+use crate::physics::aabb3d::Aabb3d;
+use crate::physics::integrators::for_each_chunk;
+use crate::physics::math::{Scalar, Vector};
+use crate::physics::octree::OctreeBody;
+use bevy::prelude::Entity;
+use serde::{Deserialize, Serialize};
+
+/// Solver for the gravitational forces between a set of bodies
+///
+/// `build` takes a snapshot of the bodies; the force methods then evaluate
+/// the field of that snapshot at arbitrary positions, leaving out the body
+/// with the excluded entity so that a body never attracts itself.
+pub trait GravitySolver: Send + Sync {
+    /// Name of the solver as used in configuration
+    fn name(&self) -> &'static str;
+
+    /// Rebuild the solver from the current state of every body
+    fn build(&mut self, bodies: &[OctreeBody]);
+
+    /// Force on a body of `mass` at `position` from every body except
+    /// `exclude_entity`
+    fn force_at(&self, position: Vector, mass: Scalar, exclude_entity: Entity, g: Scalar)
+    -> Vector;
+
+    /// Force and its time derivative on a body of `mass` at `position` moving
+    /// at `velocity`, from every body except `exclude_entity`
+    fn force_and_jerk_at(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector);
+
+    /// Force on each of `bodies`, which must be the bodies of the latest
+    /// `build` in the same order
+    ///
+    /// The default evaluates `force_at` for each body in parallel on the
+    /// compute task pool. Solvers that can share work between bodies
+    /// override it.
+    fn forces(&self, bodies: &[OctreeBody], g: Scalar, forces: &mut [Vector]) {
+        debug_assert_eq!(bodies.len(), forces.len());
+
+        for_each_chunk(forces, |offset, chunk| {
+            for (force, body) in chunk.iter_mut().zip(&bodies[offset..]) {
+                *force = self.force_at(body.position, body.mass, body.entity, g);
+            }
+        });
+    }
+
+    /// Force and its time derivative on each of `bodies`, which must be the
+    /// bodies of the latest `build` in the same order
+    ///
+    /// The default evaluates `force_and_jerk_at` for each body in parallel
+    /// on the compute task pool.
+    fn forces_and_jerks(
+        &self,
+        bodies: &[OctreeBody],
+        g: Scalar,
+        derivatives: &mut [(Vector, Vector)],
+    ) {
+        debug_assert_eq!(bodies.len(), derivatives.len());
+
+        for_each_chunk(derivatives, |offset, chunk| {
+            for (derivative, body) in chunk.iter_mut().zip(&bodies[offset..]) {
+                *derivative =
+                    self.force_and_jerk_at(body.position, body.velocity, body.mass, body.entity, g);
+            }
+        });
+    }
+
+    /// Gravitational potential at `position` from every body except
+    /// `exclude_entity`: the potential energy per unit mass of a body there
+    ///
+    /// Like `PairForce::potential`, this is softened but leaves out the force
+    /// cap of the clamp. Solvers that only compute forces return `None`.
+    fn potential_at(&self, position: Vector, exclude_entity: Entity, g: Scalar) -> Option<Scalar> {
+        let _ = (position, exclude_entity, g);
+        None
+    }
+
+    /// Total gravitational potential energy of `bodies`, which must be the
+    /// bodies of the latest `build`, or `None` if the solver has no
+    /// potential
+    ///
+    /// The default sums ½·m·Φ over the bodies with `potential_at`, in
+    /// parallel on the compute task pool, so that each pair is counted once.
+    fn potential_energy(&self, bodies: &[OctreeBody], g: Scalar) -> Option<Scalar> {
+        let mut energies = vec![None; bodies.len()];
+        for_each_chunk(&mut energies, |offset, chunk| {
+            for (energy, body) in chunk.iter_mut().zip(&bodies[offset..]) {
+                *energy = self
+                    .potential_at(body.position, body.entity, g)
+                    .map(|potential| 0.5 * body.mass * potential);
+            }
+        });
+        energies.into_iter().sum()
+    }
+
+    /// Remember the acceleration of each of `bodies` from a whole-system
+    /// evaluation, for solvers whose accuracy adapts to it
+    ///
+    /// The default ignores them.
+    fn record_accelerations(&mut self, bodies: &[OctreeBody], accelerations: &[Vector]) {
+        let _ = (bodies, accelerations);
+    }
+
+    /// Opening angle of a solver whose accuracy it sets and that can change
+    /// between evaluations, or `None`
+    fn theta(&self) -> Option<Scalar> {
+        None
+    }
+
+    /// Change the opening angle reported by `theta`; solvers without one
+    /// ignore it
+    fn set_theta(&mut self, theta: Scalar) {
+        let _ = theta;
+    }
+
+    /// Bounding boxes of the solver's spatial subdivision, for visualization
+    ///
+    /// Solvers without one return none.
+    fn bounds(&self) -> Vec<Aabb3d> {
+        Vec::new()
+    }
+}
+
+/// How the pair force is kept finite when two bodies come close
+///
+/// The kernels replace the point mass with a smooth mass distribution of
+/// scale ε, the `min_distance` of the `PairForce`, and derive force and
+/// potential from the same density, so energy is conserved and every pair
+/// pulls equally on both bodies.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Softening {
+    /// Legacy clamp: separations below `min_distance` are treated as
+    /// `min_distance`, and force magnitudes are capped at `max_force`. The
+    /// force is not the gradient of any potential near the clamp.
+    #[default]
+    Clamp,
+    /// Plummer softening, φ = -G·M/√(r² + ε²), which softens the force at
+    /// every separation, by under 1% beyond 10ε
+    Plummer,
+    /// Cubic-spline kernel of Monaghan & Lattanzio (1985) as used by
+    /// GADGET, with compact support h = 2.8ε: exactly Newtonian beyond h,
+    /// and with the same central potential -G·M/ε as Plummer softening
+    CubicSpline,
+}
+
+/// Support radius of the cubic-spline kernel in units of its
+/// Plummer-equivalent softening length
+const SPLINE_SUPPORT: Scalar = 2.8;
+
+/// Newtonian gravity between two point masses, softened for stability
+///
+/// The softening kernel decides the force below `min_distance`, or within
+/// a few `min_distance` for the smooth kernels. Only the legacy clamp caps
+/// force magnitudes at `max_force`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairForce {
+    pub min_distance: Scalar,
+    pub max_force: Scalar,
+    pub softening: Softening,
+}
+
+impl PairForce {
+    /// Creates the legacy clamped pair force
+    pub fn new(min_distance: Scalar, max_force: Scalar) -> Self {
+        Self {
+            min_distance,
+            max_force,
+            softening: Softening::Clamp,
+        }
+    }
+
+    /// Sets the softening kernel, with `min_distance` as its softening length
+    pub fn with_softening(mut self, softening: Softening) -> Self {
+        self.softening = softening;
+        self
+    }
+
+    /// Force per unit strength and separation, f(r) such that the force is
+    /// S·f(r)·r, and (1/r)·df/dr, at squared separation `distance_squared`,
+    /// or `None` for the legacy clamp
+    #[inline]
+    fn kernel(&self, distance_squared: Scalar) -> Option<(Scalar, Scalar)> {
+        let softening_squared = self.min_distance * self.min_distance;
+
+        match self.softening {
+            Softening::Clamp => None,
+            Softening::Plummer => {
+                let softened_squared = distance_squared + softening_squared;
+                let inverse_cube = 1.0 / (softened_squared * softened_squared.sqrt());
+                Some((inverse_cube, -3.0 * inverse_cube / softened_squared))
+            }
+            Softening::CubicSpline => {
+                let support = SPLINE_SUPPORT * self.min_distance;
+                let distance = distance_squared.sqrt();
+
+                if distance >= support {
+                    let inverse_cube = 1.0 / (distance_squared * distance);
+                    return Some((inverse_cube, -3.0 * inverse_cube / distance_squared));
+                }
+
+                // With u = r/h, f = P(u)/h³ and (1/r)·df/dr = (P'(u)/u)/h⁵
+                let u = distance / support;
+                let (polynomial, derivative_over_u) = if u < 0.5 {
+                    (32.0 / 3.0 + u * u * (32.0 * u - 38.4), 96.0 * u - 76.8)
+                } else {
+                    let u_cubed = u * u * u;
+                    (
+                        64.0 / 3.0 - 48.0 * u + 38.4 * u * u
+                            - 32.0 / 3.0 * u_cubed
+                            - 1.0 / (15.0 * u_cubed),
+                        (-48.0 + 76.8 * u - 32.0 * u * u + 0.2 / (u_cubed * u)) / u,
+                    )
+                };
+                let support_cubed = support * support * support;
+                Some((
+                    polynomial / support_cubed,
+                    derivative_over_u / (support_cubed * support * support),
+                ))
+            }
+        }
+    }
+
+    /// Potential energy of a body and a point mass, -S·φ(r) for φ the
+    /// kernel's potential per unit mass and G
+    ///
+    /// The legacy clamp has no potential that matches its force, so this
+    /// returns -S/max(r, `min_distance`), the potential of the distance clamp
+    /// without the force cap.
+    ///
+    /// # Arguments
+    /// * `distance` - Separation of the two bodies
+    /// * `strength` - Product G·m·M of the gravitational constant and both masses
+    #[inline]
+    pub fn potential(&self, distance: Scalar, strength: Scalar) -> Scalar {
+        match self.softening {
+            Softening::Clamp => -strength / distance.max(self.min_distance),
+            Softening::Plummer => {
+                -strength / (distance * distance + self.min_distance * self.min_distance).sqrt()
+            }
+            Softening::CubicSpline => {
+                let support = SPLINE_SUPPORT * self.min_distance;
+                if distance >= support {
+                    return -strength / distance;
+                }
+
+                let u = distance / support;
+                let kernel = if u < 0.5 {
+                    -2.8 + u * u * (16.0 / 3.0 + u * u * (6.4 * u - 9.6))
+                } else {
+                    -3.2 + 1.0 / (15.0 * u)
+                        + u * u * (32.0 / 3.0 + u * (-16.0 + u * (9.6 - 32.0 / 15.0 * u)))
+                };
+                strength * kernel / support
+            }
+        }
+    }
+
+    /// Whether `distance_squared` lies outside the softening core: beyond ε
+    /// for the clamp and Plummer kernels, beyond the spline support for the
+    /// cubic spline, and for the clamp also below `max_force`
+    ///
+    /// Only the clamp and the cubic spline are Newtonian there. The Plummer
+    /// force stays below Newtonian at every distance, by under 1% beyond 10ε.
+    #[inline]
+    pub(crate) fn is_outside_softening(&self, distance_squared: Scalar, strength: Scalar) -> bool {
+        let softening_squared = self.min_distance * self.min_distance;
+
+        match self.softening {
+            Softening::Clamp => {
+                distance_squared >= softening_squared
+                    && strength <= self.max_force * distance_squared
+            }
+            Softening::Plummer => distance_squared >= softening_squared,
+            Softening::CubicSpline => {
+                distance_squared >= SPLINE_SUPPORT * SPLINE_SUPPORT * softening_squared
+            }
+        }
+    }
+
+    /// Force on a body towards a point mass
+    ///
+    /// # Arguments
+    /// * `separation` - Position of the point mass relative to the body
+    /// * `strength` - Product G·m·M of the gravitational constant and both masses
+    #[inline]
+    pub fn force(&self, separation: Vector, strength: Scalar) -> Vector {
+        if let Some((per_separation, _)) = self.kernel(separation.length_squared()) {
+            return separation * (strength * per_separation);
+        }
+
+        let distance_squared = separation
+            .length_squared()
+            .max(self.min_distance * self.min_distance);
+
+        let distance = distance_squared.sqrt();
+        let force_magnitude = (strength / distance_squared).min(self.max_force);
+
+        separation / distance * force_magnitude
+    }
+
+    /// Force on a body towards a point mass and the time derivative of that
+    /// force
+    ///
+    /// A smooth kernel gives F = S·f(r)·r for S the strength and r the
+    /// separation, whose derivative is dF/dt = S·(f·v + (1/r)(df/dr)(r·v)·r).
+    ///
+    /// With the legacy clamp, beyond `min_distance` the force is F = f·r̂
+    /// with f = G·m·M/r², whose derivative is dF/dt = (f/r)·(v - 3(r̂·v)·r̂)
+    /// for v the velocity of the point relative to the body. Where `max_force` caps f its magnitude is
+    /// constant and only its direction turns, giving
+    /// dF/dt = (f/r)·(v - (r̂·v)·r̂). Within `min_distance` the force grows
+    /// linearly with separation, so dF/dt = v·f/r_min.
+    ///
+    /// # Arguments
+    /// * `separation` - Position of the point mass relative to the body
+    /// * `relative_velocity` - Velocity of the point mass relative to the body
+    /// * `strength` - Product G·m·M of the gravitational constant and both masses
+    #[inline]
+    pub fn force_and_jerk(
+        &self,
+        separation: Vector,
+        relative_velocity: Vector,
+        strength: Scalar,
+    ) -> (Vector, Vector) {
+        if let Some((per_separation, derivative_over_distance)) =
+            self.kernel(separation.length_squared())
+        {
+            return (
+                separation * (strength * per_separation),
+                (relative_velocity * per_separation
+                    + separation * (derivative_over_distance * separation.dot(relative_velocity)))
+                    * strength,
+            );
+        }
+
+        let min_distance_squared = self.min_distance * self.min_distance;
+        let distance_squared = separation.length_squared();
+        let clamped_distance_squared = distance_squared.max(min_distance_squared);
+
+        if clamped_distance_squared == 0.0 {
+            return (Vector::ZERO, Vector::ZERO);
+        }
+
+        let distance = clamped_distance_squared.sqrt();
+        let direction = separation / distance;
+        let unclamped_magnitude = strength / clamped_distance_squared;
+        let force_magnitude = unclamped_magnitude.min(self.max_force);
+
+        let jerk = if distance_squared < min_distance_squared {
+            relative_velocity * (force_magnitude / distance)
+        } else {
+            let radial_velocity = direction.dot(relative_velocity);
+            let radial_factor = if unclamped_magnitude > self.max_force {
+                1.0
+            } else {
+                3.0
+            };
+            (relative_velocity - direction * (radial_factor * radial_velocity))
+                * (force_magnitude / distance)
+        };
+
+        (direction * force_magnitude, jerk)
+    }
+}
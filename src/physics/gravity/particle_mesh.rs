@@ -0,0 +1,511 @@
+//! Particle-mesh gravity solver with an optional P3M short-range correction
+//!
+//! Masses are spread onto a cubic grid around the bodies with cloud-in-cell
+//! weights, the potential of the grid is found by convolving it with a Green's
+//! function through FFTs, and the gradient of the potential is interpolated
+//! back to each body with the same cloud-in-cell weights. The grid is padded
+//! to twice its size in each dimension before the convolution, so the
+//! boundaries are isolated rather than periodic.
+//!
+//! The Green's function is the long-range part of a split Newtonian kernel,
+//!
+//! ```text
+//! φ_long(r) = erf(r / 2rₛ) / r
+//! ```
+//!
+//! with rₛ the split scale, so mesh forces match Newtonian gravity beyond a
+//! few rₛ and fade smoothly below it. The kernel is divided by the square of
+//! the cloud-in-cell window so that assignment and interpolation together do
+//! not smooth it a second time.
+//!
+//! With the P3M correction enabled, the missing short-range part
+//!
+//! ```text
+//! F_short(r) = F(r)·[erfc(r / 2rₛ) + (r / rₛ√π)·exp(-r² / 4rₛ²)]
+//! ```
+//!
+//! is summed directly over the bodies within the cutoff radius, which are
+//! found with an `Octree`. The two parts add up to the softened pair force of
+//! the other solvers, within the truncation of the short-range sum.
+//!
+//! A mesh evaluation costs O(N + M log M) for M grid cells, independent of
+//! how the bodies are distributed; the short-range sum adds O(N·n) for n
+//! bodies within the cutoff of each.
+//!
+//! # Reference
+//!
+//! Hockney & Eastwood (1988) "Computer Simulation Using Particles", and
+//! Springel (2005) "The cosmological simulation code GADGET-2", MNRAS 364,
+//! 1105-1134, for the force split.
+
+use super::fft::{Complex, Direction, Fft};
+use super::{GravitySolver, PairForce, Softening};
+use crate::physics::aabb3d::Aabb3d;
+use crate::physics::integrators::for_each_chunk;
+use crate::physics::math::{Scalar, Vector};
+use crate::physics::octree::{Octree, OctreeBody};
+use bevy::prelude::Entity;
+use std::f64::consts::{FRAC_2_SQRT_PI, PI};
+
+/// Smallest supported grid, in cells per side
+pub const MIN_GRID_SIZE: usize = 8;
+
+/// Largest supported grid, in cells per side
+///
+/// The padded grid of a 128³ mesh already holds 256³ complex values.
+pub const MAX_GRID_SIZE: usize = 128;
+
+/// Cells kept free on each side of the grid so that the potential stencil of
+/// every cell holding mass lies on the grid
+const MARGIN: usize = 2;
+
+/// Fraction of the Newtonian pair force left to the short-range sum at
+/// separation `r` for split scale `split`
+fn short_range_fraction(r: Scalar, split: Scalar) -> Scalar {
+    let x = r / (2.0 * split);
+    libm::erfc(x) + FRAC_2_SQRT_PI * x * (-x * x).exp()
+}
+
+/// Derivative of `short_range_fraction` with respect to `r`
+fn short_range_fraction_derivative(r: Scalar, split: Scalar) -> Scalar {
+    let x = r / (2.0 * split);
+    -FRAC_2_SQRT_PI * x * x / split * (-x * x).exp()
+}
+
+/// Short-range correction of the mesh force
+#[derive(Debug)]
+struct ShortRange {
+    /// Cutoff radius in units of the split scale
+    cutoff: Scalar,
+    octree: Octree,
+}
+
+/// Particle-mesh gravity solver for very large body counts
+#[derive(Debug)]
+pub struct ParticleMesh {
+    grid_size: usize,
+    /// Split scale rₛ in grid cells
+    split_scale: Scalar,
+    pair_force: PairForce,
+    short_range: Option<ShortRange>,
+    /// Transform of the padded grid
+    fft: Fft,
+    /// Transformed Green's function in units of one cell, deconvolved and
+    /// normalized for the inverse transform
+    kernel: Vec<Scalar>,
+    /// Padded grid holding the mass and then the potential
+    density: Vec<Complex>,
+    scratch: Vec<Complex>,
+    /// Gradient of the potential at each grid node, without G
+    field: Vec<Vector>,
+    /// Position of grid node (0, 0, 0)
+    origin: Vector,
+    cell_size: Scalar,
+    total_mass: Scalar,
+    center_of_mass: Vector,
+}
+
+impl ParticleMesh {
+    /// Creates a solver whose short-range force between nearby bodies is
+    /// softened by the kernel set with `with_softening`
+    ///
+    /// # Arguments
+    ///
+    /// * `grid_size` - Cells per side of the mesh, a power of two from
+    ///   `MIN_GRID_SIZE` to `MAX_GRID_SIZE`
+    /// * `split_scale` - Scale rₛ below which mesh forces fade, in grid cells
+    /// * `p3m_cutoff` - Radius of the short-range sum in units of rₛ, or
+    ///   `None` for mesh forces only
+    /// * `min_distance` - Minimum distance between bodies to prevent force singularities
+    /// * `max_force` - Maximum allowed force magnitude between two bodies
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the grid size is not a power of two in range, or
+    /// the split scale or cutoff is not positive and finite.
+    pub fn new(
+        grid_size: usize,
+        split_scale: Scalar,
+        p3m_cutoff: Option<Scalar>,
+        min_distance: Scalar,
+        max_force: Scalar,
+    ) -> Result<Self, String> {
+        if !grid_size.is_power_of_two() || !(MIN_GRID_SIZE..=MAX_GRID_SIZE).contains(&grid_size) {
+            return Err(format!(
+                "particle mesh grid size must be a power of two between {MIN_GRID_SIZE} and \
+                 {MAX_GRID_SIZE}, got {grid_size}"
+            ));
+        }
+        if !(split_scale.is_finite() && split_scale > 0.0) {
+            return Err(format!(
+                "particle mesh split scale must be positive and finite, got {split_scale}"
+            ));
+        }
+        if let Some(cutoff) = p3m_cutoff
+            && !(cutoff.is_finite() && cutoff > 0.0)
+        {
+            return Err(format!(
+                "P3M cutoff must be positive and finite, got {cutoff}"
+            ));
+        }
+
+        let fft = Fft::new(2 * grid_size)?;
+        let kernel = Self::transformed_kernel(&fft, split_scale);
+        let short_range = p3m_cutoff.map(|cutoff| ShortRange {
+            cutoff,
+            octree: Octree::new(0.5, min_distance, max_force).with_leaf_threshold(8),
+        });
+
+        Ok(Self {
+            grid_size,
+            split_scale,
+            pair_force: PairForce::new(min_distance, max_force),
+            short_range,
+            fft,
+            kernel,
+            density: Vec::new(),
+            scratch: Vec::new(),
+            field: Vec::new(),
+            origin: Vector::ZERO,
+            cell_size: 1.0,
+            total_mass: 0.0,
+            center_of_mass: Vector::ZERO,
+        })
+    }
+
+    /// Sets the kernel that softens the short-range force between nearby
+    /// bodies, with `min_distance` as its softening length. Default is
+    /// `Softening::Clamp`.
+    pub fn with_softening(mut self, softening: Softening) -> Self {
+        self.pair_force.softening = softening;
+        self
+    }
+
+    pub fn grid_size(&self) -> usize {
+        self.grid_size
+    }
+
+    /// Transform of the long-range Green's function on the padded grid
+    ///
+    /// Distances are measured in cells and wrap around, so that each node
+    /// sees the masses on the unpadded part of the grid at their true
+    /// separation. The result is real because the kernel is even.
+    fn transformed_kernel(fft: &Fft, split_scale: Scalar) -> Vec<Scalar> {
+        let n = fft.size();
+        let wrapped = |index: usize| index.min(n - index) as Scalar;
+
+        let mut grid: Vec<Complex> = (0..n * n * n)
+            .map(|index| {
+                let (z, y, x) = (index / (n * n), index / n % n, index % n);
+                let r = Vector::new(wrapped(x), wrapped(y), wrapped(z)).length();
+                let potential = if r == 0.0 {
+                    FRAC_2_SQRT_PI / (2.0 * split_scale)
+                } else {
+                    libm::erf(r / (2.0 * split_scale)) / r
+                };
+                Complex::new(potential, 0.0)
+            })
+            .collect();
+        fft.transform_3d(&mut grid, &mut Vec::new(), Direction::Forward);
+
+        // Cloud-in-cell window sinc²(πm/n) along each axis, applied once by
+        // assignment and once by interpolation
+        let window: Vec<Scalar> = (0..n)
+            .map(|index| {
+                let angle = PI * wrapped(index) / n as Scalar;
+                if angle == 0.0 {
+                    1.0
+                } else {
+                    (angle.sin() / angle).powi(2)
+                }
+            })
+            .collect();
+        let normalization = (n * n * n) as Scalar;
+
+        grid.iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let (z, y, x) = (index / (n * n), index / n % n, index % n);
+                let smoothing = window[x] * window[y] * window[z];
+                value.re / (smoothing * smoothing * normalization)
+            })
+            .collect()
+    }
+
+    /// Grid coordinates of a position, in cells from node (0, 0, 0)
+    fn grid_coordinates(&self, position: Vector) -> Vector {
+        (position - self.origin) / self.cell_size
+    }
+
+    /// Lower corner node and cloud-in-cell weights of the upper nodes along
+    /// each axis, for grid coordinates within `low..=high`
+    fn cloud_in_cell(
+        coordinates: Vector,
+        low: Scalar,
+        high: Scalar,
+    ) -> Option<([usize; 3], Vector)> {
+        if !(coordinates.cmpge(Vector::splat(low)).all()
+            && coordinates.cmple(Vector::splat(high)).all())
+        {
+            return None;
+        }
+
+        let corner = coordinates.floor().min(Vector::splat(high - 1.0));
+        let weights = coordinates - corner;
+        Some((
+            [corner.x as usize, corner.y as usize, corner.z as usize],
+            weights,
+        ))
+    }
+
+    /// Calls `visit` with each of the eight nodes around a corner and its
+    /// cloud-in-cell weight
+    fn for_each_node(
+        corner: [usize; 3],
+        weights: Vector,
+        mut visit: impl FnMut([usize; 3], Scalar),
+    ) {
+        for dz in 0..2 {
+            for dy in 0..2 {
+                for dx in 0..2 {
+                    let weight = if dx == 1 { weights.x } else { 1.0 - weights.x }
+                        * if dy == 1 { weights.y } else { 1.0 - weights.y }
+                        * if dz == 1 { weights.z } else { 1.0 - weights.z };
+                    visit([corner[0] + dx, corner[1] + dy, corner[2] + dz], weight);
+                }
+            }
+        }
+    }
+
+    /// Gradient of the mesh potential at a position, without G, or `None`
+    /// outside the part of the grid that holds mass
+    fn mesh_field(&self, position: Vector) -> Option<Vector> {
+        if self.field.is_empty() {
+            return None;
+        }
+
+        let n = self.grid_size;
+        let (corner, weights) = Self::cloud_in_cell(
+            self.grid_coordinates(position),
+            MARGIN as Scalar,
+            (n - MARGIN) as Scalar,
+        )?;
+
+        let mut field = Vector::ZERO;
+        Self::for_each_node(corner, weights, |[x, y, z], weight| {
+            field += self.field[(z * n + y) * n + x] * weight;
+        });
+        Some(field)
+    }
+
+    /// Force on a body from the mesh, falling back to a point mass at the
+    /// center of mass for positions off the grid
+    fn mesh_force(&self, position: Vector, mass: Scalar, g: Scalar) -> Vector {
+        match self.mesh_field(position) {
+            Some(field) => field * (g * mass),
+            None if self.total_mass > 0.0 => self
+                .pair_force
+                .force(self.center_of_mass - position, g * mass * self.total_mass),
+            None => Vector::ZERO,
+        }
+    }
+
+    /// Split scale in world units
+    fn split_length(&self) -> Scalar {
+        self.split_scale * self.cell_size
+    }
+}
+
+impl GravitySolver for ParticleMesh {
+    fn name(&self) -> &'static str {
+        "particle_mesh"
+    }
+
+    fn build(&mut self, bodies: &[OctreeBody]) {
+        if let Some(short_range) = &mut self.short_range {
+            short_range.octree.build(bodies.iter().copied());
+        }
+
+        let Some(first) = bodies.first() else {
+            self.field.clear();
+            self.total_mass = 0.0;
+            return;
+        };
+
+        let (min, max, total_mass, weighted_sum) = bodies.iter().fold(
+            (first.position, first.position, 0.0, Vector::ZERO),
+            |(min, max, mass, weighted_sum), body| {
+                (
+                    min.min(body.position),
+                    max.max(body.position),
+                    mass + body.mass,
+                    weighted_sum + body.position * body.mass,
+                )
+            },
+        );
+        self.total_mass = total_mass;
+        self.center_of_mass = if total_mass > 0.0 {
+            weighted_sum / total_mass
+        } else {
+            (min + max) * 0.5
+        };
+
+        // Fit the bodies into the grid inside a margin of cells on each side
+        let n = self.grid_size;
+        let extent = (max - min).max_element();
+        let extent = if extent > 0.0 { extent } else { 1.0 };
+        self.cell_size = extent / (n - 1 - 2 * MARGIN) as Scalar;
+        self.origin = (min + max) * 0.5 - Vector::splat(self.cell_size * (n - 1) as Scalar * 0.5);
+
+        // Cloud-in-cell mass assignment onto the unpadded corner of the grid
+        let padded = self.fft.size();
+        self.density.clear();
+        self.density.resize(padded * padded * padded, Complex::ZERO);
+        for body in bodies {
+            let Some((corner, weights)) =
+                Self::cloud_in_cell(self.grid_coordinates(body.position), 0.0, (n - 1) as Scalar)
+            else {
+                continue;
+            };
+            Self::for_each_node(corner, weights, |[x, y, z], weight| {
+                self.density[(z * padded + y) * padded + x].re += body.mass * weight;
+            });
+        }
+
+        // Potential by convolution with the Green's function
+        self.fft
+            .transform_3d(&mut self.density, &mut self.scratch, Direction::Forward);
+        for (value, &kernel) in self.density.iter_mut().zip(&self.kernel) {
+            *value = *value * kernel;
+        }
+        self.fft
+            .transform_3d(&mut self.density, &mut self.scratch, Direction::Inverse);
+
+        // Fourth-order central differences of the potential at every node
+        // that cloud-in-cell interpolation can reach
+        let potential =
+            |x: usize, y: usize, z: usize| self.density[(z * padded + y) * padded + x].re;
+        let scale = 1.0 / (12.0 * self.cell_size * self.cell_size);
+        let difference =
+            |minus_two: Scalar, minus_one: Scalar, plus_one: Scalar, plus_two: Scalar| {
+                (8.0 * (plus_one - minus_one) - (plus_two - minus_two)) * scale
+            };
+
+        self.field.clear();
+        self.field.resize(n * n * n, Vector::ZERO);
+        let interior = MARGIN..=n - MARGIN;
+        for_each_chunk(&mut self.field, |offset, chunk| {
+            for (index, field) in (offset..).zip(chunk.iter_mut()) {
+                let (z, y, x) = (index / (n * n), index / n % n, index % n);
+                if !(interior.contains(&x) && interior.contains(&y) && interior.contains(&z)) {
+                    continue;
+                }
+                *field = Vector::new(
+                    difference(
+                        potential(x - 2, y, z),
+                        potential(x - 1, y, z),
+                        potential(x + 1, y, z),
+                        potential(x + 2, y, z),
+                    ),
+                    difference(
+                        potential(x, y - 2, z),
+                        potential(x, y - 1, z),
+                        potential(x, y + 1, z),
+                        potential(x, y + 2, z),
+                    ),
+                    difference(
+                        potential(x, y, z - 2),
+                        potential(x, y, z - 1),
+                        potential(x, y, z + 1),
+                        potential(x, y, z + 2),
+                    ),
+                );
+            }
+        });
+    }
+
+    fn force_at(
+        &self,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        let mut force = self.mesh_force(position, mass, g);
+
+        if let Some(short_range) = &self.short_range {
+            let split = self.split_length();
+            short_range.octree.for_each_body_within(
+                position,
+                short_range.cutoff * split,
+                |other| {
+                    if other.entity == exclude_entity {
+                        return;
+                    }
+                    let separation = other.position - position;
+                    force += self.pair_force.force(separation, g * mass * other.mass)
+                        * short_range_fraction(separation.length(), split);
+                },
+            );
+        }
+
+        force
+    }
+
+    /// The mesh force has no jerk, so only the short-range sum contributes
+    /// one; without the P3M correction the jerk is zero.
+    fn force_and_jerk_at(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        let mut force = self.mesh_force(position, mass, g);
+        let mut jerk = Vector::ZERO;
+
+        if let Some(short_range) = &self.short_range {
+            let split = self.split_length();
+            short_range.octree.for_each_body_within(
+                position,
+                short_range.cutoff * split,
+                |other| {
+                    if other.entity == exclude_entity {
+                        return;
+                    }
+                    let separation = other.position - position;
+                    let relative_velocity = other.velocity - velocity;
+                    let (pair_force, pair_jerk) = self.pair_force.force_and_jerk(
+                        separation,
+                        relative_velocity,
+                        g * mass * other.mass,
+                    );
+
+                    // d/dt of S(r)·F = S·dF/dt + F·S'(r)·dr/dt
+                    let distance = separation.length();
+                    let radial_velocity = if distance > 0.0 {
+                        separation.dot(relative_velocity) / distance
+                    } else {
+                        0.0
+                    };
+                    let fraction = short_range_fraction(distance, split);
+                    force += pair_force * fraction;
+                    jerk += pair_jerk * fraction
+                        + pair_force
+                            * (short_range_fraction_derivative(distance, split) * radial_velocity);
+                },
+            );
+        }
+
+        (force, jerk)
+    }
+
+    fn bounds(&self) -> Vec<Aabb3d> {
+        if self.field.is_empty() {
+            return Vec::new();
+        }
+        let size = self.cell_size * (self.grid_size - 1) as Scalar;
+        vec![Aabb3d::new(self.origin, self.origin + Vector::splat(size))]
+    }
+}
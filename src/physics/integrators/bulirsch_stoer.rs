@@ -0,0 +1,517 @@
+//! Bulirsch-Stoer extrapolation integration method
+//!
+//! The modified midpoint method has an error expansion in even powers of its
+//! substep, so integrating one step several times with more and more substeps
+//! and extrapolating the results to a zero substep cancels one power of h²
+//! per extra sequence. Each column of the extrapolation tableau gains two
+//! orders, and the difference between the last two columns estimates the
+//! error, which lets the method choose both its step and its order.
+
+use super::{
+    AccelerationField, AdaptiveKernel, Attempt, ForceEvaluations, Integrator, IntegratorParameters,
+    IntegratorReport, SharedAdaptiveState, SystemAccelerationField, step_body_adaptively,
+    step_system_adaptively,
+};
+use crate::physics::math::{Scalar, Vector};
+use serde::Deserialize;
+
+/// Number of substeps of each modified midpoint sequence (the harmonic
+/// sequence of Deuflhard)
+const SUBSTEPS: [usize; 8] = [2, 4, 6, 8, 10, 12, 14, 16];
+
+/// Column of the tableau aimed for on the first step
+const INITIAL_COLUMN: usize = 3;
+
+/// Default tolerance of the error control
+const DEFAULT_TOLERANCE: Scalar = 1e-10;
+
+/// Fraction of the error allowed to the column the step is sized for
+const ERROR_SAFETY: Scalar = 0.65;
+
+/// Fraction of the optimal step actually taken
+const STEP_SAFETY: Scalar = 0.94;
+
+/// Bounds on how much the step may shrink or grow after a single step
+const MIN_STEP_SCALE: Scalar = 0.02;
+const MAX_STEP_SCALE: Scalar = 4.0;
+
+/// Parameters accepted in the `[physics.integrator]` table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BulirschStoerParameters {
+    tolerance: Option<Scalar>,
+}
+
+/// State carried between steps of a system
+#[derive(Debug, Default)]
+pub(crate) struct StepState {
+    /// Column of the tableau the controller will aim for next
+    target_column: Option<usize>,
+    /// Order of the last accepted step
+    order: Option<usize>,
+}
+
+/// Buffers for the steps of one interval
+pub(crate) struct Work {
+    /// Force evaluations needed to complete each column
+    cost: [Scalar; SUBSTEPS.len()],
+    /// Acceleration of each body at the start of the step
+    initial_accelerations: Vec<Vector>,
+    /// Whether the initial accelerations are from before the last accepted step
+    stale: bool,
+    midpoint: Midpoint,
+}
+
+/// Position and velocity of every body
+type Snapshot = Vec<(Vector, Vector)>;
+
+/// Bulirsch-Stoer integrator - modified midpoint with Richardson extrapolation
+///
+/// # Algorithm
+///
+/// ```text
+/// For sequences k = 0, 1, 2, … with nₖ = 2, 4, 6, … substeps of h = dt/nₖ:
+///   z₀ = (x, v),  z₁ = z₀ + h·f(z₀)
+///   zₘ₊₁ = zₘ₋₁ + 2h·f(zₘ)                       for m = 1 … nₖ - 1
+///   Tₖ,₀ = (zₙ + zₙ₋₁ + h·f(zₙ)) / 2
+///
+/// Extrapolate to h → 0 in powers of h²:
+///   Tₖ,ⱼ = Tₖ,ⱼ₋₁ + (Tₖ,ⱼ₋₁ - Tₖ₋₁,ⱼ₋₁) / ((nₖ / nₖ₋ⱼ)² - 1)
+///
+/// Error of column k: |Tₖ,ₖ - Tₖ,ₖ₋₁|, scaled by the tolerance
+/// ```
+///
+/// with f(x, v) = (v, a(x)). Tₖ,ₖ is accurate to order 2k + 2.
+///
+/// # Step and Order Control
+///
+/// Each step aims for a target column of the tableau and is accepted at the
+/// first column from one below the target whose error is within tolerance.
+/// The optimal step for every column follows from its error, and the next
+/// target is the column that would cover the most time per force evaluation.
+/// A step that fails one column past its target is rejected and repeated
+/// with the step suggested for the target. Column choices run up to
+/// `SUBSTEPS.len() - 1`, so orders from 4 up to 16 are available.
+///
+/// # Mathematical Properties
+///
+/// - **Order of accuracy**: Adaptive, 2k + 2 at column k
+/// - **Force evaluations**: 1 + Σ nᵢ over the sequences used, so 21 at
+///   column 3 (order 8)
+/// - **Symplectic**: No; energy errors grow with time, but stay at the
+///   tolerance for smooth problems
+/// - **Adaptive**: Each call is split into as many internal steps as the
+///   error control requires
+///
+/// # Parameters
+///
+/// `tolerance` sets the error tolerance and defaults to 1e-10. The error of
+/// a body is scaled by `tolerance · (1 + |y|)` separately for its position
+/// and velocity:
+///
+/// ```toml
+/// [physics.integrator]
+/// type = "bulirsch_stoer"
+/// tolerance = 1e-10
+/// ```
+///
+/// # Step Size and Order
+///
+/// `step_system` remembers its step and target column between calls, and
+/// reports the longest internal step and the order of its last step through
+/// `Integrator::report`. It starts afresh after `Integrator::reset` and when
+/// a call spans a very different time than the last. The per-body `step`
+/// starts afresh on every call and reports nothing, since it runs for many
+/// bodies in parallel.
+///
+/// # Use Cases
+///
+/// **Ideal for:**
+/// - High-accuracy comparison runs of smooth few-body problems
+///
+/// **Consider alternatives:**
+/// - Use `ias15` for close encounters, where the extrapolation converges
+///   poorly and long steps are rejected
+/// - Use fixed-step symplectic methods for large systems in real time
+///
+/// # Reference
+///
+/// Hairer, Nørsett, Wanner (1993) "Solving Ordinary Differential Equations I:
+/// Nonstiff Problems", 2nd edition, Springer, section II.9.
+#[derive(Debug, Clone)]
+pub struct BulirschStoer {
+    tolerance: Scalar,
+    state: SharedAdaptiveState<StepState>,
+}
+
+impl BulirschStoer {
+    /// Create a Bulirsch-Stoer integrator with the given error tolerance
+    ///
+    /// # Errors
+    /// Returns a description of the problem if `tolerance` is not positive
+    /// and finite.
+    pub fn new(tolerance: Scalar) -> Result<Self, String> {
+        if !(tolerance.is_finite() && tolerance > 0.0) {
+            return Err(format!(
+                "Bulirsch-Stoer tolerance must be positive and finite (got {tolerance})"
+            ));
+        }
+
+        Ok(Self {
+            tolerance,
+            state: SharedAdaptiveState::default(),
+        })
+    }
+
+    /// Error tolerance of the step control
+    pub fn tolerance(&self) -> Scalar {
+        self.tolerance
+    }
+
+    /// Largest difference between two estimates of the new state, scaled by
+    /// the tolerance
+    fn scaled_error(
+        &self,
+        positions: &[Vector],
+        velocities: &[Vector],
+        estimate: &[(Vector, Vector)],
+        previous_estimate: &[(Vector, Vector)],
+    ) -> Scalar {
+        let mut largest: Scalar = 0.0;
+
+        for (i, (&(x, v), &(previous_x, previous_v))) in
+            estimate.iter().zip(previous_estimate).enumerate()
+        {
+            let position_scale = self.tolerance * (1.0 + positions[i].length().max(x.length()));
+            let velocity_scale = self.tolerance * (1.0 + velocities[i].length().max(v.length()));
+            let error = ((x - previous_x).length() / position_scale)
+                .max((v - previous_v).length() / velocity_scale);
+
+            // NaN from a singular evaluation must fail the step
+            largest = if error.is_nan() {
+                Scalar::INFINITY
+            } else {
+                largest.max(error)
+            };
+        }
+
+        largest
+    }
+}
+
+/// Buffers for the modified midpoint sequences of a system
+struct Midpoint {
+    previous: Snapshot,
+    current: Snapshot,
+    positions: Vec<Vector>,
+    accelerations: Vec<Vector>,
+}
+
+impl Midpoint {
+    fn new(body_count: usize) -> Self {
+        Self {
+            previous: vec![(Vector::ZERO, Vector::ZERO); body_count],
+            current: vec![(Vector::ZERO, Vector::ZERO); body_count],
+            positions: vec![Vector::ZERO; body_count],
+            accelerations: vec![Vector::ZERO; body_count],
+        }
+    }
+
+    /// Advance every body through `dt` in `substeps` modified midpoint
+    /// substeps, returning the smoothed end state
+    fn integrate(
+        &mut self,
+        positions: &[Vector],
+        velocities: &[Vector],
+        initial_accelerations: &[Vector],
+        dt: Scalar,
+        substeps: usize,
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> Snapshot {
+        let h = dt / substeps as Scalar;
+
+        // First substep is a plain Euler step
+        for i in 0..positions.len() {
+            self.previous[i] = (positions[i], velocities[i]);
+            self.current[i] = (
+                positions[i] + velocities[i] * h,
+                velocities[i] + initial_accelerations[i] * h,
+            );
+        }
+
+        // Leapfrog the rest, each substep reaching over the previous one
+        for _ in 1..substeps {
+            self.evaluate_current(evaluate);
+            for (i, (previous, current)) in
+                self.previous.iter_mut().zip(&mut self.current).enumerate()
+            {
+                let next = (
+                    previous.0 + current.1 * (2.0 * h),
+                    previous.1 + self.accelerations[i] * (2.0 * h),
+                );
+                *previous = *current;
+                *current = next;
+            }
+        }
+
+        // Smooth the end point with a final half step from both sides
+        self.evaluate_current(evaluate);
+        self.previous
+            .iter()
+            .zip(&self.current)
+            .zip(&self.accelerations)
+            .map(|((&(previous_x, previous_v), &(x, v)), &acceleration)| {
+                (
+                    (x + previous_x + v * h) * 0.5,
+                    (v + previous_v + acceleration * h) * 0.5,
+                )
+            })
+            .collect()
+    }
+
+    fn evaluate_current(&mut self, evaluate: &mut dyn FnMut(&[Vector], &mut [Vector])) {
+        for (position, &(current, _)) in self.positions.iter_mut().zip(&self.current) {
+            *position = current;
+        }
+        evaluate(&self.positions, &mut self.accelerations);
+    }
+}
+
+impl Default for BulirschStoer {
+    fn default() -> Self {
+        Self {
+            tolerance: DEFAULT_TOLERANCE,
+            state: SharedAdaptiveState::default(),
+        }
+    }
+}
+
+impl AdaptiveKernel for BulirschStoer {
+    type State = StepState;
+    type Work = Work;
+
+    fn begin(
+        &self,
+        _state: &mut StepState,
+        positions: &[Vector],
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> Work {
+        let body_count = positions.len();
+
+        let mut cost = [0.0; SUBSTEPS.len()];
+        let mut total = 1.0;
+        for (column, &substeps) in SUBSTEPS.iter().enumerate() {
+            total += substeps as Scalar;
+            cost[column] = total;
+        }
+
+        let mut initial_accelerations = vec![Vector::ZERO; body_count];
+        evaluate(positions, &mut initial_accelerations);
+
+        Work {
+            cost,
+            initial_accelerations,
+            stale: false,
+            midpoint: Midpoint::new(body_count),
+        }
+    }
+
+    fn attempt(
+        &self,
+        state: &mut StepState,
+        work: &mut Work,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        trial: Scalar,
+        step: Scalar,
+        must_accept: bool,
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> Attempt {
+        let Work {
+            cost,
+            initial_accelerations,
+            stale,
+            midpoint,
+        } = work;
+        let last_column = SUBSTEPS.len() - 1;
+        let target = state
+            .target_column
+            .unwrap_or(INITIAL_COLUMN)
+            .clamp(1, last_column - 1);
+
+        if *stale {
+            evaluate(positions, initial_accelerations);
+            *stale = false;
+        }
+
+        let mut tableau: Vec<Vec<Snapshot>> = Vec::with_capacity(SUBSTEPS.len());
+        let mut optimal_steps = [trial; SUBSTEPS.len()];
+        let mut accepted = None;
+
+        for column in 0..=(target + 1).min(last_column) {
+            let mut row = vec![midpoint.integrate(
+                positions,
+                velocities,
+                initial_accelerations,
+                trial,
+                SUBSTEPS[column],
+                evaluate,
+            )];
+
+            // Extrapolate the new row against the previous one
+            for j in 1..=column {
+                let ratio = SUBSTEPS[column] as Scalar / SUBSTEPS[column - j] as Scalar;
+                let denominator = ratio * ratio - 1.0;
+                let extrapolated = row[j - 1]
+                    .iter()
+                    .zip(&tableau[column - 1][j - 1])
+                    .map(|(&(x, v), &(previous_x, previous_v))| {
+                        (
+                            x + (x - previous_x) / denominator,
+                            v + (v - previous_v) / denominator,
+                        )
+                    })
+                    .collect();
+                row.push(extrapolated);
+            }
+
+            if column > 0 {
+                let error =
+                    self.scaled_error(positions, velocities, &row[column], &row[column - 1]);
+                let exponent = 1.0 / (2 * column + 1) as Scalar;
+                let scale = if error > 0.0 {
+                    STEP_SAFETY * (ERROR_SAFETY / error).powf(exponent)
+                } else {
+                    MAX_STEP_SCALE
+                };
+                optimal_steps[column] = trial * scale.clamp(MIN_STEP_SCALE, MAX_STEP_SCALE);
+
+                let converged = error <= 1.0;
+                if (converged && column + 1 >= target) || (must_accept && column > target) {
+                    tableau.push(row);
+                    accepted = Some(column);
+                    break;
+                }
+            }
+
+            tableau.push(row);
+        }
+
+        let Some(column) = accepted else {
+            return Attempt::Rejected {
+                retry_step: optimal_steps[target.min(tableau.len() - 1)],
+            };
+        };
+
+        let solution = &tableau[column][column];
+        for (i, &(position, velocity)) in solution.iter().enumerate() {
+            positions[i] = position;
+            velocities[i] = velocity;
+        }
+        *stale = true;
+        state.order = Some(2 * column + 2);
+
+        // Aim next for the column that covers the most time per force
+        // evaluation, moving at most one column at a time
+        let work = |column: usize| cost[column] / optimal_steps[column];
+        let (next_target, next_step) = if column >= 2 && work(column - 1) < 0.8 * work(column) {
+            (column - 1, optimal_steps[column - 1])
+        } else if column < last_column - 1 && work(column) < 0.9 * work(column - 1) {
+            (
+                column + 1,
+                optimal_steps[column] * cost[column + 1] / cost[column],
+            )
+        } else {
+            (column, optimal_steps[column])
+        };
+        state.target_column = Some(next_target.clamp(1, last_column - 1));
+
+        // A step cut short to end the interval does not hold the next back
+        // unless its error calls for a shorter one
+        Attempt::Accepted {
+            next_step: if next_step >= trial {
+                step.max(next_step)
+            } else {
+                next_step
+            },
+        }
+    }
+}
+
+impl Integrator for BulirschStoer {
+    fn clone_box(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn configure(&self, parameters: &IntegratorParameters) -> Result<Box<dyn Integrator>, String> {
+        let parameters: BulirschStoerParameters = toml::Value::Table(parameters.clone())
+            .try_into()
+            .map_err(|e| format!("Invalid Bulirsch-Stoer parameters: {}", e.message()))?;
+
+        Ok(Box::new(BulirschStoer::new(
+            parameters.tolerance.unwrap_or(DEFAULT_TOLERANCE),
+        )?))
+    }
+
+    fn step(
+        &self,
+        position: &mut Vector,
+        velocity: &mut Vector,
+        field: &dyn AccelerationField,
+        dt: Scalar,
+    ) {
+        step_body_adaptively(self, position, velocity, field, dt);
+    }
+
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        step_system_adaptively(self, &self.state, positions, velocities, field, dt);
+    }
+
+    fn reset(&self) {
+        self.state.reset();
+    }
+
+    fn report(&self) -> IntegratorReport {
+        let state = self.state.lock();
+
+        IntegratorReport {
+            step_size: state.longest_step,
+            order: state.kernel.order,
+            ..IntegratorReport::default()
+        }
+    }
+
+    fn convergence_order(&self) -> usize {
+        2 * SUBSTEPS.len()
+    }
+
+    fn is_symplectic(&self) -> bool {
+        false
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::AtLeast(7)
+    }
+
+    fn description(&self) -> &'static str {
+        "Modified midpoint with Richardson extrapolation and adaptive step and order"
+    }
+
+    fn name(&self) -> &'static str {
+        "bulirsch_stoer"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["bs", "gragg_bulirsch_stoer"]
+    }
+}
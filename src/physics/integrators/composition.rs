@@ -0,0 +1,330 @@
+//! Coefficient-driven drift-kick composition integrator
+//!
+//! Every splitting method for a separable Hamiltonian H = T(v) + V(x) is an
+//! alternating sequence of drifts (x += aᵢ·v·dt) and kicks (v += bᵢ·a(x)·dt).
+//! Symplectic Euler, velocity Verlet, PEFRL, and the Yoshida methods differ
+//! only in their coefficients. This integrator takes the coefficients as data,
+//! so new splitting methods can be tried from the configuration file:
+//!
+//! ```toml
+//! [physics.integrator]
+//! type = "composition"
+//! drift = [0.5, 0.5]
+//! kick = [1.0]
+//! order = 2
+//! ```
+//!
+//! The sequence starts with `drift[0]` and alternates, so `drift` holds either
+//! as many coefficients as `kick` (ending on a kick) or one more (ending on a
+//! drift). A zero coefficient may be used to start with a kick. Without any
+//! parameters the composition is velocity Verlet.
+
+use super::{
+    AccelerationField, ForceEvaluations, Integrator, IntegratorParameters, SystemAccelerationField,
+};
+use crate::physics::math::{Scalar, Vector};
+use serde::Deserialize;
+
+/// Tolerance on the sum of each set of coefficients
+const SUM_TOLERANCE: Scalar = 1e-9;
+
+/// Parameters accepted in the `[physics.integrator]` table
+///
+/// Coefficients left out are those of the composition being configured, so
+/// an empty table keeps it as it is.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CompositionParameters {
+    #[serde(default)]
+    drift: Option<Vec<Scalar>>,
+    #[serde(default)]
+    kick: Option<Vec<Scalar>>,
+    #[serde(default)]
+    order: Option<usize>,
+}
+
+/// Drift-kick composition integrator with configurable coefficients
+///
+/// Symplectic for any choice of coefficients, since every drift and kick is
+/// itself a symplectic map. Consistency requires the drift and kick
+/// coefficients to each sum to 1, which is checked on construction. The
+/// convergence order is taken from the configuration if declared, and
+/// otherwise estimated by Richardson extrapolation on an eccentric Kepler
+/// orbit.
+///
+/// # Computational Cost
+///
+/// One force evaluation per nonzero kick coefficient.
+///
+/// # Example
+///
+/// PEFRL written as a composition:
+///
+/// ```toml
+/// [physics.integrator]
+/// type = "composition"
+/// drift = [0.1786178958448091, -0.0662645826698185, 0.7752933736500188,
+///          -0.0662645826698185, 0.1786178958448091]
+/// kick = [0.7123418310626054, -0.2123418310626054, -0.2123418310626054,
+///         0.7123418310626054]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composition {
+    drift: Vec<Scalar>,
+    kick: Vec<Scalar>,
+    order: usize,
+}
+
+impl Composition {
+    /// Create a composition from its drift and kick coefficients
+    ///
+    /// # Arguments
+    /// * `drift` - Drift coefficients, applied first
+    /// * `kick` - Kick coefficients, alternating with the drifts
+    /// * `order` - Declared convergence order, or `None` to estimate it
+    ///
+    /// # Errors
+    /// Returns a description of the problem if the sequence lengths do not
+    /// alternate, a coefficient is not finite, a set of coefficients does not
+    /// sum to 1, or the declared order is zero.
+    pub fn new(
+        drift: Vec<Scalar>,
+        kick: Vec<Scalar>,
+        order: Option<usize>,
+    ) -> Result<Self, String> {
+        if kick.is_empty() {
+            return Err("Composition needs at least one kick coefficient".to_string());
+        }
+
+        if drift.len() != kick.len() && drift.len() != kick.len() + 1 {
+            return Err(format!(
+                "Composition needs as many drift coefficients as kick coefficients, or one more \
+                 (got {} drift, {} kick)",
+                drift.len(),
+                kick.len()
+            ));
+        }
+
+        for (label, coefficients) in [("drift", &drift), ("kick", &kick)] {
+            if coefficients
+                .iter()
+                .any(|coefficient| !coefficient.is_finite())
+            {
+                return Err(format!("Composition {label} coefficients must be finite"));
+            }
+
+            let sum: Scalar = coefficients.iter().sum();
+            if (sum - 1.0).abs() > SUM_TOLERANCE {
+                return Err(format!(
+                    "Composition {label} coefficients must sum to 1 (got {sum})"
+                ));
+            }
+        }
+
+        if order == Some(0) {
+            return Err("Composition order must be at least 1".to_string());
+        }
+
+        let mut composition = Self {
+            drift,
+            kick,
+            order: order.unwrap_or(1),
+        };
+        if order.is_none() {
+            composition.order = composition.estimate_order();
+        }
+
+        Ok(composition)
+    }
+
+    /// Whether the drift-kick sequence reads the same backwards, which makes
+    /// each step time-reversible
+    ///
+    /// Zero coefficients are skipped and neighbouring operations of the same
+    /// kind merged before comparing.
+    fn is_symmetric(&self) -> bool {
+        // (is kick, coefficient) for each operation in order
+        let mut sequence: Vec<(bool, Scalar)> = Vec::new();
+        for (i, &drift) in self.drift.iter().enumerate() {
+            let kick = self.kick.get(i).copied().unwrap_or(0.0);
+            for (is_kick, coefficient) in [(false, drift), (true, kick)] {
+                if coefficient == 0.0 {
+                    continue;
+                }
+                match sequence.last_mut() {
+                    Some((last_kind, last)) if *last_kind == is_kick => *last += coefficient,
+                    _ => sequence.push((is_kick, coefficient)),
+                }
+            }
+        }
+
+        sequence
+            .iter()
+            .zip(sequence.iter().rev())
+            .all(|(&(kind, a), &(reverse_kind, b))| {
+                kind == reverse_kind && (a - b).abs() <= SUM_TOLERANCE
+            })
+    }
+
+    /// Estimate the convergence order on an eccentric Kepler orbit
+    ///
+    /// Integrates over a fixed time with successively halved steps and
+    /// measures how fast the difference between consecutive results shrinks,
+    /// which needs no exact solution. The order is the rounded rate from the
+    /// finest pair of steps whose difference is still well above round-off.
+    /// The time is deliberately not a whole period, where errors of some
+    /// methods cancel and they appear to converge faster than they do.
+    fn estimate_order(&self) -> usize {
+        struct Kepler;
+
+        impl AccelerationField for Kepler {
+            fn at(&self, position: Vector) -> Vector {
+                let r = position.length();
+                -position / (r * r * r)
+            }
+        }
+
+        let eccentricity: Scalar = 0.5;
+        let duration: Scalar = 2.0;
+        let final_position = |steps: usize| {
+            let dt = duration / steps as Scalar;
+            let mut position = Vector::new(1.0 - eccentricity, 0.0, 0.0);
+            let mut velocity = Vector::new(
+                0.0,
+                ((1.0 + eccentricity) / (1.0 - eccentricity)).sqrt(),
+                0.0,
+            );
+            for _ in 0..steps {
+                self.step(&mut position, &mut velocity, &Kepler, dt);
+            }
+            position
+        };
+
+        let positions: Vec<Vector> = (6..=11).map(|power| final_position(1 << power)).collect();
+        let differences: Vec<Scalar> = positions
+            .windows(2)
+            .map(|pair| (pair[0] - pair[1]).length())
+            .collect();
+
+        differences
+            .windows(2)
+            .filter(|pair| pair[1] > 1e-11 && pair[0] > pair[1])
+            .map(|pair| (pair[0] / pair[1]).log2().round() as usize)
+            .next_back()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// Drift coefficients of the sequence
+    pub fn drift(&self) -> &[Scalar] {
+        &self.drift
+    }
+
+    /// Kick coefficients of the sequence
+    pub fn kick(&self) -> &[Scalar] {
+        &self.kick
+    }
+}
+
+impl Default for Composition {
+    /// Velocity Verlet in drift-kick-drift form
+    fn default() -> Self {
+        Self {
+            drift: vec![0.5, 0.5],
+            kick: vec![1.0],
+            order: 2,
+        }
+    }
+}
+
+impl Integrator for Composition {
+    fn clone_box(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn configure(&self, parameters: &IntegratorParameters) -> Result<Box<dyn Integrator>, String> {
+        let parameters: CompositionParameters =
+            toml::Value::Table(parameters.clone())
+                .try_into()
+                .map_err(|e| format!("Invalid composition parameters: {}", e.message()))?;
+
+        // The order of the current coefficients only carries over with them
+        let order = match (&parameters.drift, &parameters.kick) {
+            (None, None) => parameters.order.or(Some(self.order)),
+            _ => parameters.order,
+        };
+
+        Ok(Box::new(Composition::new(
+            parameters.drift.unwrap_or_else(|| self.drift.clone()),
+            parameters.kick.unwrap_or_else(|| self.kick.clone()),
+            order,
+        )?))
+    }
+
+    fn step(
+        &self,
+        position: &mut Vector,
+        velocity: &mut Vector,
+        field: &dyn AccelerationField,
+        dt: Scalar,
+    ) {
+        for (i, &drift) in self.drift.iter().enumerate() {
+            *position += *velocity * (drift * dt);
+
+            if let Some(&kick) = self.kick.get(i).filter(|&&kick| kick != 0.0) {
+                *velocity += field.at(*position) * (kick * dt);
+            }
+        }
+    }
+
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        let mut accelerations = vec![Vector::ZERO; positions.len()];
+
+        for (i, &drift) in self.drift.iter().enumerate() {
+            for (position, velocity) in positions.iter_mut().zip(velocities.iter()) {
+                *position += *velocity * (drift * dt);
+            }
+
+            if let Some(&kick) = self.kick.get(i).filter(|&&kick| kick != 0.0) {
+                field.evaluate(positions, &mut accelerations);
+                for (velocity, acceleration) in velocities.iter_mut().zip(&accelerations) {
+                    *velocity += *acceleration * (kick * dt);
+                }
+            }
+        }
+    }
+
+    fn convergence_order(&self) -> usize {
+        self.order
+    }
+
+    fn is_symplectic(&self) -> bool {
+        true
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        self.is_symmetric()
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(self.kick.iter().filter(|&&kick| kick != 0.0).count())
+    }
+
+    fn description(&self) -> &'static str {
+        "Drift-kick splitting with coefficients from the config file"
+    }
+
+    fn name(&self) -> &'static str {
+        "composition"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["splitting"]
+    }
+}
@@ -4,7 +4,7 @@
 //! It exhibits poor energy conservation in conservative systems, with energy typically
 //! drifting exponentially over time.
 
-use super::{AccelerationField, Integrator};
+use super::{AccelerationField, ForceEvaluations, Integrator};
 use crate::physics::math::{Scalar, Vector};
 
 /// Explicit Euler integrator (forward Euler method)
@@ -119,6 +119,22 @@ impl Integrator for ExplicitEuler {
         1
     }
 
+    fn is_symplectic(&self) -> bool {
+        false
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(1)
+    }
+
+    fn description(&self) -> &'static str {
+        "First-order explicit Euler, for comparison only"
+    }
+
     fn name(&self) -> &'static str {
         "explicit_euler"
     }
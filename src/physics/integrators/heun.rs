@@ -5,7 +5,7 @@
 //! Heun's method provides a good balance of simplicity and accuracy for
 //! short-duration simulations where energy conservation is not critical.
 
-use super::{AccelerationField, Integrator};
+use super::{AccelerationField, ForceEvaluations, Integrator, SystemAccelerationField};
 use crate::physics::math::{Scalar, Vector};
 
 /// Heun's method (Improved Euler method)
@@ -116,10 +116,56 @@ impl Integrator for Heun {
         *velocity += (k1_v + k2_v) * (dt * 0.5);
     }
 
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        // Stage 1: Evaluate every body at its current position (predictor)
+        let mut k1_v = vec![Vector::ZERO; positions.len()];
+        field.evaluate(positions, &mut k1_v);
+
+        // Stage 2: Evaluate every body at the predicted endpoint
+        let pos_predicted: Vec<Vector> = positions
+            .iter()
+            .zip(&*velocities)
+            .map(|(position, velocity)| *position + *velocity * dt)
+            .collect();
+        let mut k2_v = vec![Vector::ZERO; positions.len()];
+        field.evaluate(&pos_predicted, &mut k2_v);
+
+        // Average the slopes (corrector)
+        for (i, (position, velocity)) in positions.iter_mut().zip(velocities.iter_mut()).enumerate()
+        {
+            let k1_x = *velocity;
+            let k2_x = *velocity + k1_v[i] * dt;
+            *position += (k1_x + k2_x) * (dt * 0.5);
+            *velocity += (k1_v[i] + k2_v[i]) * (dt * 0.5);
+        }
+    }
+
     fn convergence_order(&self) -> usize {
         2
     }
 
+    fn is_symplectic(&self) -> bool {
+        false
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(2)
+    }
+
+    fn description(&self) -> &'static str {
+        "Second-order predictor-corrector averaging the start and predicted end slopes"
+    }
+
     fn name(&self) -> &'static str {
         "heun"
     }
@@ -4,24 +4,118 @@
 //! of motion in gravitational n-body simulations. Each integrator implements
 //! the `Integrator` trait, which requires self-description of its properties
 //! (name, aliases, convergence order) and the core integration step.
+//!
+//! Integrators have two entry points. `Integrator::step` advances one body
+//! against an `AccelerationField` in which every other body is held fixed.
+//! `Integrator::step_system` advances every body together against a
+//! `SystemAccelerationField`, re-evaluating the field between stages so that
+//! multi-stage methods see the other bodies at their intermediate positions
+//! and keep their convergence order in the N-body case. Both kinds of field
+//! can also report the jerk alongside the acceleration for Hermite methods.
+//!
+//! Integrators with their own step control implement `AdaptiveKernel`, which
+//! attempts one internal step at a time, and share the loop that splits each
+//! call into internal steps through `integrate_adaptively`.
 
 use crate::physics::math::{Scalar, Vector};
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use serde::Serialize;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
 
+pub mod bulirsch_stoer;
+pub mod composition;
 pub mod explicit_euler;
+pub mod gauss_legendre;
+pub mod hermite;
 pub mod heun;
+pub mod ias15;
 pub mod pefrl;
 pub mod registry;
 pub mod runge_kutta;
 pub mod symplectic_euler;
 pub mod velocity_verlet;
+pub mod wisdom_holman;
+pub mod yoshida;
 
+pub use bulirsch_stoer::BulirschStoer;
+pub use composition::Composition;
 pub use explicit_euler::ExplicitEuler;
+pub use gauss_legendre::{GaussLegendre4, GaussLegendre6};
+pub use hermite::Hermite4;
 pub use heun::Heun;
+pub use ias15::Ias15;
 pub use pefrl::Pefrl;
+pub use runge_kutta::DormandPrince;
 pub use runge_kutta::RungeKuttaFourthOrder;
 pub use runge_kutta::RungeKuttaSecondOrderMidpoint;
 pub use symplectic_euler::SymplecticEuler;
 pub use velocity_verlet::VelocityVerlet;
+pub use wisdom_holman::{WisdomHolman, WisdomHolmanCoordinates};
+pub use yoshida::{Yoshida4, Yoshida6, Yoshida8};
+
+/// Integrator-specific parameters from the `[physics.integrator]` config table
+///
+/// Holds every key of the table other than `type`.
+pub type IntegratorParameters = toml::Table;
+
+/// Force evaluations an integrator makes per step
+///
+/// One evaluation is the acceleration of every body. Integrators with
+/// iterative stage equations or their own step control need more evaluations
+/// the harder the step, and give the fewest they can take per internal step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForceEvaluations {
+    /// Exactly this many evaluations every step
+    Fixed(usize),
+    /// At least this many evaluations per internal step
+    AtLeast(usize),
+}
+
+impl ForceEvaluations {
+    /// Fewest evaluations per step
+    pub fn minimum(self) -> usize {
+        match self {
+            Self::Fixed(count) | Self::AtLeast(count) => count,
+        }
+    }
+
+    /// Whether every step costs the same number of evaluations
+    pub fn is_fixed(self) -> bool {
+        matches!(self, Self::Fixed(_))
+    }
+}
+
+impl fmt::Display for ForceEvaluations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(count) => f.pad(&count.to_string()),
+            Self::AtLeast(count) => f.pad(&format!("{count}+")),
+        }
+    }
+}
+
+/// Measurements an integrator reports about its last system step
+///
+/// Integrators with their own step or order control or an error estimate
+/// fill in what they found, so it can be shown through the simulation
+/// diagnostics. Fields are `None` for integrators that have nothing to
+/// report.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IntegratorReport {
+    /// Longest internal step taken during the last `step_system` call
+    pub step_size: Option<Scalar>,
+    /// Largest estimated local position error of any body during the last
+    /// `step_system` call
+    pub error_estimate: Option<Scalar>,
+    /// Order of the last internal step of an integrator that chooses its order
+    pub order: Option<usize>,
+    /// Steps of an implicit integrator whose stage equations reached the
+    /// iteration cap without converging, counted since it was configured or
+    /// last reset
+    pub unconverged_steps: Option<usize>,
+}
 
 /// Acceleration field trait for calculating accelerations at arbitrary positions
 ///
@@ -38,6 +132,436 @@ pub trait AccelerationField: Send + Sync {
     /// # Returns
     /// The acceleration vector at the given position
     fn at(&self, position: Vector) -> Vector;
+
+    /// Calculate acceleration and jerk for a body at a given position and velocity
+    ///
+    /// Jerk is the time derivative of the acceleration along the body's
+    /// trajectory. The default implementation takes a central difference of
+    /// the acceleration along the velocity, which is the jerk in a static
+    /// field. Fields whose sources move should override this.
+    ///
+    /// # Arguments
+    /// * `position` - The position at which to evaluate the acceleration
+    /// * `velocity` - The velocity of the body at that position
+    ///
+    /// # Returns
+    /// The acceleration and jerk vectors
+    fn with_jerk(&self, position: Vector, velocity: Vector) -> (Vector, Vector) {
+        jerk_along_velocity(|position| self.at(position), position, velocity)
+    }
+}
+
+/// Acceleration field for a whole system of bodies
+///
+/// Unlike `AccelerationField`, which describes the field seen by a single body
+/// while every other body stays put, this trait describes the field produced
+/// by all bodies together. Integrators update it with a complete set of
+/// positions whenever bodies have moved, so every stage of a multi-stage
+/// method is evaluated against a consistent snapshot of the system.
+pub trait SystemAccelerationField: Send + Sync {
+    /// Rebuild the field from a snapshot of every body's position
+    ///
+    /// # Arguments
+    /// * `positions` - Position of each body, indexed consistently across calls
+    fn update(&mut self, positions: &[Vector]);
+
+    /// Calculate the acceleration of one body at a given position
+    ///
+    /// The other bodies are taken at the positions of the latest `update`.
+    ///
+    /// # Arguments
+    /// * `index` - Index of the body whose acceleration is evaluated
+    /// * `position` - The position at which to evaluate the acceleration
+    fn at(&self, index: usize, position: Vector) -> Vector;
+
+    /// Rebuild the field from a snapshot of every body's position and velocity
+    ///
+    /// Fields that compute jerk from the motion of their sources override
+    /// this to record the velocities. The default ignores them.
+    ///
+    /// # Arguments
+    /// * `positions` - Position of each body, indexed consistently across calls
+    /// * `velocities` - Velocity of each body
+    fn update_with_velocities(&mut self, positions: &[Vector], velocities: &[Vector]) {
+        let _ = velocities;
+        self.update(positions);
+    }
+
+    /// Calculate the acceleration and jerk of one body at a given position
+    /// and velocity
+    ///
+    /// The other bodies are taken at the positions and velocities of the
+    /// latest `update_with_velocities`. The default implementation
+    /// differentiates along the body's own velocity with every other body
+    /// held still, so it misses the jerk from the motion of the sources;
+    /// fields of moving bodies override it.
+    ///
+    /// # Arguments
+    /// * `index` - Index of the body whose acceleration is evaluated
+    /// * `position` - The position at which to evaluate the acceleration
+    /// * `velocity` - The velocity of the body
+    fn at_with_jerk(&self, index: usize, position: Vector, velocity: Vector) -> (Vector, Vector) {
+        jerk_along_velocity(|position| self.at(index, position), position, velocity)
+    }
+
+    /// Gravitational parameter G·m of each body producing the field
+    ///
+    /// Integrators that solve part of the motion analytically, such as
+    /// Wisdom-Holman, need the masses behind the field. The default returns
+    /// `None` for fields that are not produced by the bodies as point masses.
+    fn gravitational_parameters(&self) -> Option<&[Scalar]> {
+        None
+    }
+
+    /// Calculate the acceleration of every body with all bodies at `positions`
+    ///
+    /// The default implementation updates the field and then evaluates each
+    /// body in parallel on the compute task pool.
+    ///
+    /// # Arguments
+    /// * `positions` - Position of each body
+    /// * `accelerations` - Output slice receiving each body's acceleration
+    fn evaluate(&mut self, positions: &[Vector], accelerations: &mut [Vector]) {
+        debug_assert_eq!(positions.len(), accelerations.len());
+
+        self.update(positions);
+
+        let field: &Self = self;
+        for_each_chunk(accelerations, |offset, chunk| {
+            for (i, acceleration) in chunk.iter_mut().enumerate() {
+                *acceleration = field.at(offset + i, positions[offset + i]);
+            }
+        });
+    }
+
+    /// Calculate the acceleration and jerk of every body with all bodies at
+    /// `positions` moving at `velocities`
+    ///
+    /// The default implementation updates the field and then evaluates each
+    /// body in parallel on the compute task pool.
+    ///
+    /// # Arguments
+    /// * `positions` - Position of each body
+    /// * `velocities` - Velocity of each body
+    /// * `derivatives` - Output slice receiving each body's acceleration and jerk
+    fn evaluate_with_jerk(
+        &mut self,
+        positions: &[Vector],
+        velocities: &[Vector],
+        derivatives: &mut [(Vector, Vector)],
+    ) {
+        debug_assert_eq!(positions.len(), derivatives.len());
+
+        self.update_with_velocities(positions, velocities);
+
+        let field: &Self = self;
+        for_each_chunk(derivatives, |offset, chunk| {
+            for (i, derivative) in chunk.iter_mut().enumerate() {
+                let index = offset + i;
+                *derivative = field.at_with_jerk(index, positions[index], velocities[index]);
+            }
+        });
+    }
+}
+
+/// Per-body view of a `SystemAccelerationField`
+///
+/// Adapts a system-wide field to the single-body `AccelerationField` used by
+/// `Integrator::step`, holding every other body at its last updated position.
+pub struct FrozenBodyField<'a, F: SystemAccelerationField + ?Sized> {
+    field: &'a F,
+    index: usize,
+}
+
+impl<'a, F: SystemAccelerationField + ?Sized> FrozenBodyField<'a, F> {
+    pub fn new(field: &'a F, index: usize) -> Self {
+        Self { field, index }
+    }
+}
+
+impl<F: SystemAccelerationField + ?Sized> AccelerationField for FrozenBodyField<'_, F> {
+    fn at(&self, position: Vector) -> Vector {
+        self.field.at(self.index, position)
+    }
+
+    fn with_jerk(&self, position: Vector, velocity: Vector) -> (Vector, Vector) {
+        self.field.at_with_jerk(self.index, position, velocity)
+    }
+}
+
+/// Acceleration and jerk of a body moving through a static field
+///
+/// Takes a central difference of the acceleration along the velocity, with a
+/// displacement near the cube root of machine epsilon relative to the scale
+/// of the position to balance truncation against round-off.
+fn jerk_along_velocity(
+    acceleration_at: impl Fn(Vector) -> Vector,
+    position: Vector,
+    velocity: Vector,
+) -> (Vector, Vector) {
+    let acceleration = acceleration_at(position);
+
+    let speed = velocity.length();
+    if speed == 0.0 {
+        return (acceleration, Vector::ZERO);
+    }
+
+    let interval = Scalar::EPSILON.cbrt() * position.length().max(1.0) / speed;
+    let ahead = acceleration_at(position + velocity * interval);
+    let behind = acceleration_at(position - velocity * interval);
+
+    (acceleration, (ahead - behind) / (2.0 * interval))
+}
+
+/// Split `items` into one chunk per compute thread and run `f` on each chunk
+/// in parallel, passing the index of the chunk's first item
+pub(crate) fn for_each_chunk<T: Send>(items: &mut [T], f: impl Fn(usize, &mut [T]) + Sync) {
+    let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let chunk_size = items.len().div_ceil(task_pool.thread_num().max(1)).max(1);
+
+    task_pool.scope(|scope| {
+        let f = &f;
+        for (chunk_index, chunk) in items.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(async move { f(chunk_index * chunk_size, chunk) });
+        }
+    });
+}
+
+/// Advance one body through a static field by integrating it as a system of
+/// one body
+///
+/// Lets integrators written for whole systems provide `Integrator::step`.
+/// `integrate` receives the body's position and velocity as one-element
+/// slices, along with a function that fills in the acceleration at a given
+/// position.
+pub(crate) fn step_as_system(
+    position: &mut Vector,
+    velocity: &mut Vector,
+    field: &dyn AccelerationField,
+    integrate: impl FnOnce(&mut [Vector], &mut [Vector], &mut dyn FnMut(&[Vector], &mut [Vector])),
+) {
+    let mut positions = [*position];
+    let mut velocities = [*velocity];
+
+    integrate(
+        &mut positions,
+        &mut velocities,
+        &mut |positions, accelerations| accelerations[0] = field.at(positions[0]),
+    );
+
+    *position = positions[0];
+    *velocity = velocities[0];
+}
+
+/// Shortest step, as a fraction of the requested interval, that an adaptive
+/// integrator may still reject, so that a singular configuration cannot
+/// stall the integration
+pub(crate) const MIN_STEP_FRACTION: Scalar = 1e-12;
+
+/// Outcome of one internal step attempted by an `AdaptiveKernel`
+pub(crate) enum Attempt {
+    /// The step was taken and the positions and velocities advanced
+    Accepted {
+        /// Step to try next
+        next_step: Scalar,
+    },
+    /// The step failed its error control and left the state untouched
+    Rejected {
+        /// Shorter step to try instead
+        retry_step: Scalar,
+    },
+}
+
+/// Error-controlled step of an adaptive integrator
+///
+/// `integrate_adaptively` splits each interval into internal steps and keeps
+/// track of their length, leaving the kernel to attempt one internal step at
+/// a time and to size the next.
+pub(crate) trait AdaptiveKernel {
+    /// What the kernel carries from one internal step to the next, including
+    /// across calls to `step_system`
+    type State: Default;
+
+    /// Buffers used within one interval
+    type Work;
+
+    /// Prepare the buffers for an interval starting at `positions`
+    ///
+    /// `evaluate` fills in the acceleration of every body with all bodies at
+    /// the given positions.
+    fn begin(
+        &self,
+        state: &mut Self::State,
+        positions: &[Vector],
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> Self::Work;
+
+    /// First step to try in an interval, given the step the previous
+    /// interval would have taken next
+    fn first_step(&self, next_step: Option<Scalar>, interval: Scalar) -> Scalar {
+        next_step.unwrap_or(interval)
+    }
+
+    /// Attempt an internal step of length `trial`
+    ///
+    /// `step` is the step the controller asked for, which `trial` falls short
+    /// of when it is cut to end the interval. A kernel must accept the step
+    /// when `must_accept` is set, since it is already too short to reject.
+    #[allow(clippy::too_many_arguments)]
+    fn attempt(
+        &self,
+        state: &mut Self::State,
+        work: &mut Self::Work,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        trial: Scalar,
+        step: Scalar,
+        must_accept: bool,
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> Attempt;
+}
+
+/// Factor by which an interval may differ from the previous one before the
+/// state learned over the previous one is discarded
+const MAX_INTERVAL_CHANGE: Scalar = 16.0;
+
+/// State of an adaptive integration carried between intervals
+#[derive(Debug, Default)]
+pub(crate) struct AdaptiveState<S> {
+    /// Step the controller will try next
+    pub next_step: Option<Scalar>,
+    /// Longest step accepted during the last interval
+    pub longest_step: Option<Scalar>,
+    /// Length of the last interval
+    pub last_interval: Option<Scalar>,
+    /// State of the kernel
+    pub kernel: S,
+}
+
+/// Advance a system through `interval` in as many internal steps as the
+/// kernel's error control requires
+///
+/// `evaluate` fills in the acceleration of every body with all bodies at the
+/// given positions.
+pub(crate) fn integrate_adaptively<K: AdaptiveKernel>(
+    kernel: &K,
+    state: &mut AdaptiveState<K::State>,
+    positions: &mut [Vector],
+    velocities: &mut [Vector],
+    interval: Scalar,
+    evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+) {
+    debug_assert_eq!(positions.len(), velocities.len());
+
+    // A step sized for a very different interval, or for integrating the
+    // other way in time, is no guide to this one
+    let change = state.last_interval.map_or(1.0, |last| interval / last);
+    if !(1.0 / MAX_INTERVAL_CHANGE..=MAX_INTERVAL_CHANGE).contains(&change) {
+        *state = AdaptiveState::default();
+    }
+    state.last_interval = Some(interval);
+
+    state.longest_step = None;
+    let mut work = kernel.begin(&mut state.kernel, positions, evaluate);
+
+    let mut remaining = interval;
+    let mut step = kernel.first_step(state.next_step, interval);
+
+    while remaining > interval * Scalar::EPSILON {
+        let trial = step.min(remaining);
+        let must_accept = trial <= interval * MIN_STEP_FRACTION;
+
+        match kernel.attempt(
+            &mut state.kernel,
+            &mut work,
+            positions,
+            velocities,
+            trial,
+            step,
+            must_accept,
+            evaluate,
+        ) {
+            Attempt::Rejected { retry_step } => step = retry_step,
+            Attempt::Accepted { next_step } => {
+                state.longest_step = Some(state.longest_step.map_or(trial, |step| step.max(trial)));
+                step = next_step;
+                remaining -= trial;
+            }
+        }
+    }
+
+    state.next_step = Some(step);
+}
+
+/// `AdaptiveState` held by an integrator so that `step_system` can carry it
+/// from one call to the next through `&self`
+#[derive(Debug, Default)]
+pub(crate) struct SharedAdaptiveState<S>(Mutex<AdaptiveState<S>>);
+
+impl<S: Default> SharedAdaptiveState<S> {
+    pub fn lock(&self) -> MutexGuard<'_, AdaptiveState<S>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Forget the ongoing integration
+    pub fn reset(&self) {
+        *self.lock() = AdaptiveState::default();
+    }
+}
+
+impl<S: Default> Clone for SharedAdaptiveState<S> {
+    /// Starts afresh rather than cloning the state of an ongoing integration
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// `Integrator::step` of an adaptive integrator, starting afresh on every
+/// call since it runs for many bodies in parallel
+pub(crate) fn step_body_adaptively<K: AdaptiveKernel>(
+    kernel: &K,
+    position: &mut Vector,
+    velocity: &mut Vector,
+    field: &dyn AccelerationField,
+    dt: Scalar,
+) {
+    step_as_system(
+        position,
+        velocity,
+        field,
+        |positions, velocities, evaluate| {
+            integrate_adaptively(
+                kernel,
+                &mut AdaptiveState::default(),
+                positions,
+                velocities,
+                dt,
+                evaluate,
+            );
+        },
+    );
+}
+
+/// `Integrator::step_system` of an adaptive integrator, continuing from the
+/// state of the previous call
+pub(crate) fn step_system_adaptively<K: AdaptiveKernel>(
+    kernel: &K,
+    state: &SharedAdaptiveState<K::State>,
+    positions: &mut [Vector],
+    velocities: &mut [Vector],
+    field: &mut dyn SystemAccelerationField,
+    dt: Scalar,
+) {
+    integrate_adaptively(
+        kernel,
+        &mut state.lock(),
+        positions,
+        velocities,
+        dt,
+        &mut |positions, accelerations| field.evaluate(positions, accelerations),
+    );
 }
 
 /// Base trait for all integrators with capability discovery
@@ -52,6 +576,28 @@ pub trait Integrator: Send + Sync {
     /// the concrete type, supporting true generic discovery.
     fn clone_box(&self) -> Box<dyn Integrator>;
 
+    /// Create a boxed instance configured from integrator-specific parameters
+    ///
+    /// The default accepts only an empty set of parameters, since most
+    /// integrators have nothing to configure. Integrators that take
+    /// parameters override this to validate them and build a new instance.
+    ///
+    /// # Errors
+    /// Returns a description of the problem if the parameters are unknown or
+    /// invalid for this integrator.
+    fn configure(&self, parameters: &IntegratorParameters) -> Result<Box<dyn Integrator>, String> {
+        if parameters.is_empty() {
+            Ok(self.clone_box())
+        } else {
+            let names: Vec<&str> = parameters.keys().map(String::as_str).collect();
+            Err(format!(
+                "Integrator '{}' takes no parameters, but got: {}",
+                self.name(),
+                names.join(", ")
+            ))
+        }
+    }
+
     /// Advance a single body's state by one time step using an acceleration field
     ///
     /// This method calculates accelerations at intermediate positions as needed for
@@ -70,6 +616,73 @@ pub trait Integrator: Send + Sync {
         dt: Scalar,
     );
 
+    /// Advance every body in the system by one time step together
+    ///
+    /// Positions and velocities are indexed consistently with the field. The
+    /// default implementation adapts the per-body `step`: the field is updated
+    /// once at the start of the step and each body is then advanced in
+    /// parallel while the others are held at their starting positions. That is
+    /// exact for single-stage methods, while multi-stage methods override this
+    /// to update the field between stages.
+    ///
+    /// # Arguments
+    /// * `positions` - Mutable slice of every body's position
+    /// * `velocities` - Mutable slice of every body's velocity
+    /// * `field` - System-wide acceleration field
+    /// * `dt` - Time step
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        debug_assert_eq!(positions.len(), velocities.len());
+
+        field.update(positions);
+
+        let field: &dyn SystemAccelerationField = field;
+        let mut states: Vec<(Vector, Vector)> = positions
+            .iter()
+            .copied()
+            .zip(velocities.iter().copied())
+            .collect();
+
+        for_each_chunk(&mut states, |offset, chunk| {
+            for (i, (position, velocity)) in chunk.iter_mut().enumerate() {
+                self.step(
+                    position,
+                    velocity,
+                    &FrozenBodyField::new(field, offset + i),
+                    dt,
+                );
+            }
+        });
+
+        for ((position, velocity), (new_position, new_velocity)) in
+            positions.iter_mut().zip(velocities.iter_mut()).zip(states)
+        {
+            *position = new_position;
+            *velocity = new_velocity;
+        }
+    }
+
+    /// Forget any state carried between `step_system` calls
+    ///
+    /// Called when the system is replaced, so that an integrator does not
+    /// start the new system from the steps and corrections it learned on the
+    /// old one. The default does nothing, which suits integrators that carry
+    /// no state.
+    fn reset(&self) {}
+
+    /// Report what the integrator chose during its last `step_system` call
+    ///
+    /// The default reports nothing, which suits integrators that take exactly
+    /// the step they are given.
+    fn report(&self) -> IntegratorReport {
+        IntegratorReport::default()
+    }
+
     /// Returns the convergence order of this integration method
     ///
     /// The convergence order indicates how the error scales with timestep:
@@ -78,6 +691,22 @@ pub trait Integrator: Send + Sync {
     /// - Order 4: Error ~ O(dt⁴)
     fn convergence_order(&self) -> usize;
 
+    /// Returns whether each step is a symplectic map, preserving phase-space
+    /// volume and keeping energy errors bounded over long runs
+    fn is_symplectic(&self) -> bool;
+
+    /// Returns whether stepping forward and then back by the same timestep
+    /// returns to the starting state
+    fn is_time_reversible(&self) -> bool;
+
+    /// Returns how many force evaluations each step makes
+    ///
+    /// Used to compare integrators at equal cost rather than equal timestep.
+    fn force_evaluations(&self) -> ForceEvaluations;
+
+    /// Returns a one-line description of the method
+    fn description(&self) -> &'static str;
+
     /// Returns the canonical name of this integrator
     ///
     /// This is the primary identifier used in configuration files
@@ -6,7 +6,7 @@
 //! and N-body simulations where energy conservation over millions of timesteps
 //! is critical.
 
-use super::{AccelerationField, Integrator};
+use super::{AccelerationField, ForceEvaluations, Integrator, SystemAccelerationField};
 use crate::physics::math::{Scalar, Vector};
 
 /// PEFRL integrator - a 4th order symplectic integrator
@@ -179,10 +179,60 @@ impl Integrator for Pefrl {
         *position += *velocity * (Pefrl::XI * dt);
     }
 
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        // Same palindromic sequence as `step`, with each kick evaluated after
+        // every body has completed the preceding drift
+        let mut accelerations = vec![Vector::ZERO; positions.len()];
+
+        let drift = |positions: &mut [Vector], velocities: &[Vector], coefficient: Scalar| {
+            for (position, velocity) in positions.iter_mut().zip(velocities) {
+                *position += *velocity * (coefficient * dt);
+            }
+        };
+        let mut kick = |positions: &[Vector], velocities: &mut [Vector], coefficient: Scalar| {
+            field.evaluate(positions, &mut accelerations);
+            for (velocity, acceleration) in velocities.iter_mut().zip(&accelerations) {
+                *velocity += *acceleration * (coefficient * dt);
+            }
+        };
+
+        drift(positions, velocities, Pefrl::XI);
+        kick(positions, velocities, Pefrl::COEFF_A);
+        drift(positions, velocities, Pefrl::CHI);
+        kick(positions, velocities, Pefrl::LAMBDA);
+        drift(positions, velocities, Pefrl::COEFF_B);
+        kick(positions, velocities, Pefrl::LAMBDA);
+        drift(positions, velocities, Pefrl::CHI);
+        kick(positions, velocities, Pefrl::COEFF_A);
+        drift(positions, velocities, Pefrl::XI);
+    }
+
     fn convergence_order(&self) -> usize {
         4
     }
 
+    fn is_symplectic(&self) -> bool {
+        true
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        true
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(4)
+    }
+
+    fn description(&self) -> &'static str {
+        "Fourth-order symplectic Forest-Ruth composition with optimized coefficients"
+    }
+
     fn name(&self) -> &'static str {
         "pefrl"
     }
@@ -1,23 +1,41 @@
 //! Registry pattern for dynamic integrator management
 //!
 //! The registry serves as a discovery and factory mechanism for integrators.
-//! Each integrator is self-describing, providing its own name, aliases, and
-//! convergence order. The registry queries this metadata during initialization
-//! to build lookup tables for name resolution and instantiation.
+//! Each integrator is self-describing, providing its own name, aliases,
+//! convergence order, geometric properties, cost, and a short description.
+//! The registry queries this metadata during initialization to build lookup
+//! tables for name resolution and instantiation.
 //!
-//! The registry stores integrator instances indexed by name. Since all integrators
-//! are zero-sized types (ZSTs), cloning simply creates new Box allocations
-//! without any state copying.
+//! The registry stores integrator instances indexed by name. Most integrators
+//! are zero-sized types (ZSTs), so cloning simply creates new Box allocations
+//! without any state copying. Integrators that take parameters, such as the
+//! coefficient-driven composition, are built from the stored instance by
+//! `Integrator::configure`.
 
-use super::Integrator;
+use super::{ForceEvaluations, Integrator, IntegratorParameters};
 use bevy::prelude::*;
+use serde::Serialize;
 use std::collections::HashMap;
 
+/// Self-description of a registered integrator, as listed by
+/// `--list-integrators`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntegratorDescription {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub order: usize,
+    pub symplectic: bool,
+    pub time_reversible: bool,
+    pub force_evaluations: ForceEvaluations,
+    pub description: String,
+}
+
 /// Registry for runtime integrator registration
 ///
 /// The registry maintains instances of each integrator indexed by name. When an integrator
-/// is requested, the registry creates a new boxed instance via `clone_box()`. Since all
-/// integrators are stateless ZSTs, this is essentially just creating a new Box allocation.
+/// is requested, the registry creates a new boxed instance configured with the requested
+/// parameters via `Integrator::configure()`. For the stateless ZSTs that take no parameters,
+/// this is essentially just creating a new Box allocation.
 #[derive(Resource)]
 pub struct IntegratorRegistry {
     /// Maps names (canonical and aliases) to integrator instances
@@ -39,8 +57,10 @@ impl IntegratorRegistry {
     /// Returns self for method chaining.
     pub fn with_standard_integrators(mut self) -> Self {
         use super::{
-            ExplicitEuler, Heun, Pefrl, RungeKuttaFourthOrder, RungeKuttaSecondOrderMidpoint,
-            SymplecticEuler, VelocityVerlet,
+            BulirschStoer, Composition, DormandPrince, ExplicitEuler, GaussLegendre4,
+            GaussLegendre6, Hermite4, Heun, Ias15, Pefrl, RungeKuttaFourthOrder,
+            RungeKuttaSecondOrderMidpoint, SymplecticEuler, VelocityVerlet, WisdomHolman, Yoshida4,
+            Yoshida6, Yoshida8,
         };
 
         self.register_integrator(Box::new(ExplicitEuler));
@@ -49,7 +69,18 @@ impl IntegratorRegistry {
         self.register_integrator(Box::new(Heun));
         self.register_integrator(Box::new(RungeKuttaSecondOrderMidpoint));
         self.register_integrator(Box::new(RungeKuttaFourthOrder));
+        self.register_integrator(Box::new(DormandPrince::default()));
         self.register_integrator(Box::new(Pefrl));
+        self.register_integrator(Box::new(Yoshida4));
+        self.register_integrator(Box::new(Yoshida6));
+        self.register_integrator(Box::new(Yoshida8));
+        self.register_integrator(Box::new(Hermite4));
+        self.register_integrator(Box::new(Composition::default()));
+        self.register_integrator(Box::new(Ias15::default()));
+        self.register_integrator(Box::new(BulirschStoer::default()));
+        self.register_integrator(Box::new(GaussLegendre4::default()));
+        self.register_integrator(Box::new(GaussLegendre6::default()));
+        self.register_integrator(Box::new(WisdomHolman::default()));
 
         self
     }
@@ -76,10 +107,16 @@ impl IntegratorRegistry {
         }
     }
 
-    pub fn create(&self, name: &str) -> Result<Box<dyn Integrator>, String> {
+    /// Create an integrator by name or alias, configured with `parameters`
+    ///
+    /// Pass an empty table for integrators that take no parameters.
+    pub fn create(
+        &self,
+        name: &str,
+        parameters: &IntegratorParameters,
+    ) -> Result<Box<dyn Integrator>, String> {
         self.integrators
             .get(name)
-            .map(|integrator| integrator.clone_box())
             .ok_or_else(|| {
                 let available = self.list_available();
                 let aliases = self.list_aliases();
@@ -90,7 +127,8 @@ impl IntegratorRegistry {
                     available.join(", "),
                     alias_names.join(", ")
                 )
-            })
+            })?
+            .configure(parameters)
     }
 
     pub fn list_available(&self) -> Vec<String> {
@@ -121,6 +159,32 @@ impl IntegratorRegistry {
         aliases.sort_by(|a, b| a.0.cmp(&b.0));
         aliases
     }
+
+    /// Describe every registered integrator, sorted by canonical name
+    pub fn describe_available(&self) -> Vec<IntegratorDescription> {
+        self.list_available()
+            .into_iter()
+            .filter_map(|name| self.integrators.get(&name))
+            .map(|integrator| {
+                let mut aliases: Vec<String> = integrator
+                    .aliases()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+                aliases.sort();
+
+                IntegratorDescription {
+                    name: integrator.name().to_string(),
+                    aliases,
+                    order: integrator.convergence_order(),
+                    symplectic: integrator.is_symplectic(),
+                    time_reversible: integrator.is_time_reversible(),
+                    force_evaluations: integrator.force_evaluations(),
+                    description: integrator.description().to_string(),
+                }
+            })
+            .collect()
+    }
 }
 
 impl Default for IntegratorRegistry {
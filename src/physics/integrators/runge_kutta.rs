@@ -5,9 +5,17 @@
 //! While these methods provide excellent accuracy for smooth problems, they are
 //! non-symplectic and exhibit energy drift in conservative systems, making them
 //! less suitable for long-term orbital mechanics than symplectic alternatives.
+//!
+//! The embedded Dormand-Prince pair also estimates the local truncation error
+//! of every step, which it reports and can use to choose its own step size.
 
-use super::{AccelerationField, Integrator};
+use super::{
+    AccelerationField, AdaptiveKernel, Attempt, ForceEvaluations, Integrator, IntegratorParameters,
+    IntegratorReport, SharedAdaptiveState, SystemAccelerationField, step_body_adaptively,
+    step_system_adaptively,
+};
 use crate::physics::math::{Scalar, Vector};
+use serde::Deserialize;
 
 /// Second-order Runge-Kutta method (Midpoint method)
 ///
@@ -103,10 +111,55 @@ impl Integrator for RungeKuttaSecondOrderMidpoint {
         *velocity += k2_v * dt;
     }
 
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        // Stage 1: Evaluate every body at its current position
+        let mut k1_v = vec![Vector::ZERO; positions.len()];
+        field.evaluate(positions, &mut k1_v);
+
+        // Stage 2: Evaluate every body at the midpoint
+        let pos_mid: Vec<Vector> = positions
+            .iter()
+            .zip(&*velocities)
+            .map(|(position, velocity)| *position + *velocity * (dt * 0.5))
+            .collect();
+        let mut k2_v = vec![Vector::ZERO; positions.len()];
+        field.evaluate(&pos_mid, &mut k2_v);
+
+        // Update using midpoint derivative
+        for (i, (position, velocity)) in positions.iter_mut().zip(velocities.iter_mut()).enumerate()
+        {
+            let k2_x = *velocity + k1_v[i] * (dt * 0.5);
+            *position += k2_x * dt;
+            *velocity += k2_v[i] * dt;
+        }
+    }
+
     fn convergence_order(&self) -> usize {
         2
     }
 
+    fn is_symplectic(&self) -> bool {
+        false
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(2)
+    }
+
+    fn description(&self) -> &'static str {
+        "Second-order Runge-Kutta taking the slope at the midpoint"
+    }
+
     fn name(&self) -> &'static str {
         "runge_kutta_second_order_midpoint"
     }
@@ -257,10 +310,73 @@ impl Integrator for RungeKuttaFourthOrder {
         *velocity += (k1_v + k2_v * 2.0 + k3_v * 2.0 + k4_v) * (dt / 6.0);
     }
 
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        let n = positions.len();
+
+        // Stage 1: k1 with every body at its current position
+        let k1_x: Vec<Vector> = velocities.to_vec();
+        let mut k1_v = vec![Vector::ZERO; n];
+        field.evaluate(positions, &mut k1_v);
+
+        // Stage 2: k2 at midpoint using k1
+        let pos_k2: Vec<Vector> = (0..n)
+            .map(|i| positions[i] + k1_x[i] * (dt * 0.5))
+            .collect();
+        let k2_x: Vec<Vector> = (0..n)
+            .map(|i| velocities[i] + k1_v[i] * (dt * 0.5))
+            .collect();
+        let mut k2_v = vec![Vector::ZERO; n];
+        field.evaluate(&pos_k2, &mut k2_v);
+
+        // Stage 3: k3 at midpoint using k2
+        let pos_k3: Vec<Vector> = (0..n)
+            .map(|i| positions[i] + k2_x[i] * (dt * 0.5))
+            .collect();
+        let k3_x: Vec<Vector> = (0..n)
+            .map(|i| velocities[i] + k2_v[i] * (dt * 0.5))
+            .collect();
+        let mut k3_v = vec![Vector::ZERO; n];
+        field.evaluate(&pos_k3, &mut k3_v);
+
+        // Stage 4: k4 at endpoint using k3
+        let pos_k4: Vec<Vector> = (0..n).map(|i| positions[i] + k3_x[i] * dt).collect();
+        let k4_x: Vec<Vector> = (0..n).map(|i| velocities[i] + k3_v[i] * dt).collect();
+        let mut k4_v = vec![Vector::ZERO; n];
+        field.evaluate(&pos_k4, &mut k4_v);
+
+        // Combine stages using RK4 weights
+        for i in 0..n {
+            positions[i] += (k1_x[i] + k2_x[i] * 2.0 + k3_x[i] * 2.0 + k4_x[i]) * (dt / 6.0);
+            velocities[i] += (k1_v[i] + k2_v[i] * 2.0 + k3_v[i] * 2.0 + k4_v[i]) * (dt / 6.0);
+        }
+    }
+
     fn convergence_order(&self) -> usize {
         4
     }
 
+    fn is_symplectic(&self) -> bool {
+        false
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(4)
+    }
+
+    fn description(&self) -> &'static str {
+        "Classical fourth-order Runge-Kutta"
+    }
+
     fn name(&self) -> &'static str {
         "runge_kutta_fourth_order"
     }
@@ -269,3 +385,419 @@ impl Integrator for RungeKuttaFourthOrder {
         vec!["rk4"]
     }
 }
+
+/// Dormand-Prince stage coefficients, row `i` giving the weights of the
+/// earlier stages in stage `i`
+///
+/// The last row holds the weights of the 5th order solution, so the last
+/// stage is evaluated at the new state.
+const DORMAND_PRINCE_STAGES: [[Scalar; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+        0.0,
+        0.0,
+    ],
+    [
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+        0.0,
+    ],
+    [
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+
+/// Difference between the 5th and the embedded 4th order weights
+const DORMAND_PRINCE_ERROR_WEIGHTS: [Scalar; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+/// Default tolerance of the error control
+const DEFAULT_TOLERANCE: Scalar = 1e-8;
+
+/// Fraction of the optimal step actually taken, leaving room for the error
+/// estimate to be optimistic
+const STEP_SAFETY: Scalar = 0.9;
+
+/// Bounds on how much the step may shrink or grow after a single step
+const MIN_STEP_SCALE: Scalar = 0.2;
+const MAX_STEP_SCALE: Scalar = 5.0;
+
+/// Parameters accepted in the `[physics.integrator]` table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DormandPrinceParameters {
+    adaptive: Option<bool>,
+    tolerance: Option<Scalar>,
+}
+
+/// State carried between steps of a system
+#[derive(Debug, Default)]
+pub(crate) struct DormandPrinceState {
+    /// Largest estimated position error of any body during the last call
+    largest_error: Option<Scalar>,
+}
+
+/// Stage buffers for the steps of one interval
+pub(crate) struct DormandPrinceStages {
+    /// Velocity of every body at each stage
+    velocities: Vec<Vec<Vector>>,
+    /// Acceleration of every body at each stage, the first holding the
+    /// acceleration at the start of the step
+    accelerations: Vec<Vec<Vector>>,
+    positions: Vec<Vector>,
+}
+
+/// Dormand-Prince embedded Runge-Kutta 5(4) integrator (RK45)
+///
+/// Seven stages give two solutions at once: the 5th order solution that is
+/// kept, and an embedded 4th order solution whose difference from it
+/// estimates the local truncation error of the step. The last stage is
+/// evaluated at the new state, so in adaptive mode it is reused as the first
+/// stage of the following step ("first same as last").
+///
+/// # Algorithm
+///
+/// ```text
+/// For stages i = 1 … 7, with the Dormand-Prince coefficients aᵢⱼ:
+///   xᵢ = x(t) + dt · Σⱼ aᵢⱼ·vⱼ
+///   vᵢ = v(t) + dt · Σⱼ aᵢⱼ·a(xⱼ)
+///
+/// 5th order update, with weights bᵢ:
+///   x(t+dt) = x(t) + dt · Σᵢ bᵢ·vᵢ
+///   v(t+dt) = v(t) + dt · Σᵢ bᵢ·a(xᵢ)
+///
+/// Error estimate, with eᵢ the difference from the 4th order weights:
+///   δx = dt · Σᵢ eᵢ·vᵢ,  δv = dt · Σᵢ eᵢ·a(xᵢ)
+/// ```
+///
+/// # Mathematical Properties
+///
+/// - **Order of accuracy**: O(dt⁵), with a 4th order error estimate
+/// - **Force evaluations**: 7 per step, or 6 per internal step in adaptive
+///   mode once the last stage is reused
+/// - **Symplectic**: No, energy drifts linearly with time like RK4
+///
+/// # Parameters
+///
+/// By default every call takes exactly the step it is given, and only
+/// reports its error estimate. With `adaptive = true` each call is split into
+/// as many internal steps as are needed to keep the scaled error of each
+/// below `tolerance`, which defaults to 1e-8:
+///
+/// ```toml
+/// [physics.integrator]
+/// type = "dormand_prince"
+/// adaptive = true
+/// tolerance = 1e-8
+/// ```
+///
+/// The error of a body is scaled by `tolerance · (1 + |y|)` separately for
+/// its position and velocity, so the tolerance is absolute for small values
+/// and relative for large ones.
+///
+/// # Error Estimate
+///
+/// `step_system` reports the largest estimated position error of any body
+/// through `Integrator::report`, along with the longest internal step in
+/// adaptive mode. The per-body `step` reports nothing, since it runs for
+/// many bodies in parallel.
+///
+/// # Use Cases
+///
+/// **Ideal for:**
+/// - A non-symplectic baseline with a known error, to compare against
+///   `velocity_verlet` and `pefrl`
+/// - Short runs where the error of each step should be controlled directly
+///
+/// **Consider alternatives:**
+/// - Use symplectic methods for long-term conservative dynamics
+///
+/// # Reference
+///
+/// Dormand, Prince (1980) "A family of embedded Runge-Kutta formulae",
+/// Journal of Computational and Applied Mathematics 6, 19-26.
+#[derive(Debug, Clone)]
+pub struct DormandPrince {
+    tolerance: Option<Scalar>,
+    state: SharedAdaptiveState<DormandPrinceState>,
+}
+
+impl DormandPrince {
+    /// Create a Dormand-Prince integrator that takes exactly the step given
+    pub fn fixed() -> Self {
+        Self {
+            tolerance: None,
+            state: SharedAdaptiveState::default(),
+        }
+    }
+
+    /// Create a Dormand-Prince integrator that chooses its own internal steps
+    ///
+    /// # Errors
+    /// Returns a description of the problem if `tolerance` is not positive
+    /// and finite.
+    pub fn adaptive(tolerance: Scalar) -> Result<Self, String> {
+        if !(tolerance.is_finite() && tolerance > 0.0) {
+            return Err(format!(
+                "Dormand-Prince tolerance must be positive and finite (got {tolerance})"
+            ));
+        }
+
+        Ok(Self {
+            tolerance: Some(tolerance),
+            state: SharedAdaptiveState::default(),
+        })
+    }
+
+    /// Tolerance of the error control, or `None` for fixed steps
+    pub fn tolerance(&self) -> Option<Scalar> {
+        self.tolerance
+    }
+}
+
+impl Default for DormandPrince {
+    fn default() -> Self {
+        Self::fixed()
+    }
+}
+
+impl AdaptiveKernel for DormandPrince {
+    type State = DormandPrinceState;
+    type Work = DormandPrinceStages;
+
+    fn begin(
+        &self,
+        state: &mut DormandPrinceState,
+        positions: &[Vector],
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> DormandPrinceStages {
+        let body_count = positions.len();
+        let stage_count = DORMAND_PRINCE_STAGES.len();
+        state.largest_error = None;
+
+        let mut stages = DormandPrinceStages {
+            velocities: vec![vec![Vector::ZERO; body_count]; stage_count],
+            accelerations: vec![vec![Vector::ZERO; body_count]; stage_count],
+            positions: vec![Vector::ZERO; body_count],
+        };
+        evaluate(positions, &mut stages.accelerations[0]);
+        stages
+    }
+
+    /// The fixed integrator takes the whole interval in one step
+    fn first_step(&self, next_step: Option<Scalar>, interval: Scalar) -> Scalar {
+        match self.tolerance {
+            Some(_) => next_step.unwrap_or(interval),
+            None => interval,
+        }
+    }
+
+    fn attempt(
+        &self,
+        state: &mut DormandPrinceState,
+        stages: &mut DormandPrinceStages,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        trial: Scalar,
+        step: Scalar,
+        must_accept: bool,
+        evaluate: &mut dyn FnMut(&[Vector], &mut [Vector]),
+    ) -> Attempt {
+        let body_count = positions.len();
+        let stage_count = DORMAND_PRINCE_STAGES.len();
+        let DormandPrinceStages {
+            velocities: stage_velocities,
+            accelerations: stage_accelerations,
+            positions: stage_positions,
+        } = stages;
+
+        stage_velocities[0].copy_from_slice(velocities);
+        for stage in 1..stage_count {
+            let weights = &DORMAND_PRINCE_STAGES[stage];
+            for i in 0..body_count {
+                let mut position_change = Vector::ZERO;
+                let mut velocity_change = Vector::ZERO;
+                for (j, &weight) in weights.iter().enumerate().take(stage) {
+                    position_change += stage_velocities[j][i] * weight;
+                    velocity_change += stage_accelerations[j][i] * weight;
+                }
+                stage_positions[i] = positions[i] + position_change * trial;
+                stage_velocities[stage][i] = velocities[i] + velocity_change * trial;
+            }
+            evaluate(stage_positions, &mut stage_accelerations[stage]);
+        }
+
+        // The last stage's position and velocity are the new state
+        let new_positions = &*stage_positions;
+        let new_velocities = &stage_velocities[stage_count - 1];
+
+        let mut largest_error: Scalar = 0.0;
+        let mut scaled_error: Scalar = 0.0;
+        for i in 0..body_count {
+            let mut position_error = Vector::ZERO;
+            let mut velocity_error = Vector::ZERO;
+            for (stage, &weight) in DORMAND_PRINCE_ERROR_WEIGHTS.iter().enumerate() {
+                position_error += stage_velocities[stage][i] * weight;
+                velocity_error += stage_accelerations[stage][i] * weight;
+            }
+            let position_error = position_error.length() * trial;
+            let velocity_error = velocity_error.length() * trial;
+
+            largest_error = largest_error.max(position_error);
+            if let Some(tolerance) = self.tolerance {
+                let position_scale =
+                    tolerance * (1.0 + positions[i].length().max(new_positions[i].length()));
+                let velocity_scale =
+                    tolerance * (1.0 + velocities[i].length().max(new_velocities[i].length()));
+                scaled_error = scaled_error
+                    .max(position_error / position_scale)
+                    .max(velocity_error / velocity_scale);
+            }
+        }
+
+        // Scale of the step the error allows, relative to this one
+        let scale = self.tolerance.map(|_| {
+            let scale = if scaled_error > 0.0 {
+                STEP_SAFETY * scaled_error.powf(-1.0 / 5.0)
+            } else {
+                MAX_STEP_SCALE
+            };
+            scale.clamp(MIN_STEP_SCALE, MAX_STEP_SCALE)
+        });
+
+        if (scaled_error > 1.0 || scaled_error.is_nan()) && !must_accept {
+            return Attempt::Rejected {
+                retry_step: trial * scale.unwrap_or(MIN_STEP_SCALE),
+            };
+        }
+
+        positions.copy_from_slice(new_positions);
+        velocities.copy_from_slice(new_velocities);
+        stage_accelerations.swap(0, stage_count - 1);
+
+        state.largest_error = Some(
+            state
+                .largest_error
+                .map_or(largest_error, |error| error.max(largest_error)),
+        );
+
+        // A step cut short to end the interval does not hold the next back
+        // unless its error calls for a shorter one
+        let next_step = match scale {
+            Some(scale) if scale >= 1.0 => step.max(trial * scale),
+            Some(scale) => trial * scale,
+            None => step,
+        };
+        Attempt::Accepted { next_step }
+    }
+}
+
+impl Integrator for DormandPrince {
+    fn clone_box(&self) -> Box<dyn Integrator> {
+        Box::new(self.clone())
+    }
+
+    fn configure(&self, parameters: &IntegratorParameters) -> Result<Box<dyn Integrator>, String> {
+        let parameters: DormandPrinceParameters = toml::Value::Table(parameters.clone())
+            .try_into()
+            .map_err(|e| format!("Invalid Dormand-Prince parameters: {}", e.message()))?;
+
+        match (parameters.adaptive.unwrap_or(false), parameters.tolerance) {
+            (true, tolerance) => Ok(Box::new(DormandPrince::adaptive(
+                tolerance.unwrap_or(DEFAULT_TOLERANCE),
+            )?)),
+            (false, None) => Ok(Box::new(DormandPrince::fixed())),
+            (false, Some(_)) => {
+                Err("Dormand-Prince tolerance only applies with adaptive = true".to_string())
+            }
+        }
+    }
+
+    fn step(
+        &self,
+        position: &mut Vector,
+        velocity: &mut Vector,
+        field: &dyn AccelerationField,
+        dt: Scalar,
+    ) {
+        step_body_adaptively(self, position, velocity, field, dt);
+    }
+
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        step_system_adaptively(self, &self.state, positions, velocities, field, dt);
+    }
+
+    fn reset(&self) {
+        self.state.reset();
+    }
+
+    fn report(&self) -> IntegratorReport {
+        let state = self.state.lock();
+
+        IntegratorReport {
+            step_size: self.tolerance.and(state.longest_step),
+            error_estimate: state.kernel.largest_error,
+            ..IntegratorReport::default()
+        }
+    }
+
+    fn convergence_order(&self) -> usize {
+        5
+    }
+
+    fn is_symplectic(&self) -> bool {
+        false
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        if self.tolerance.is_some() {
+            ForceEvaluations::AtLeast(6)
+        } else {
+            ForceEvaluations::Fixed(7)
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        "Fifth-order embedded Runge-Kutta pair with a local error estimate and optional step control"
+    }
+
+    fn name(&self) -> &'static str {
+        "dormand_prince"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["rk45", "dopri5"]
+    }
+}
@@ -5,7 +5,7 @@
 //! accuracy, it often outperforms higher-order non-symplectic methods in
 //! long-term energy conservation.
 
-use super::{AccelerationField, Integrator};
+use super::{AccelerationField, ForceEvaluations, Integrator};
 use crate::physics::math::{Scalar, Vector};
 
 /// Symplectic Euler integrator (also known as semi-implicit Euler)
@@ -126,6 +126,22 @@ impl Integrator for SymplecticEuler {
         1
     }
 
+    fn is_symplectic(&self) -> bool {
+        true
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        false
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(1)
+    }
+
+    fn description(&self) -> &'static str {
+        "First-order symplectic Euler, velocity updated before position"
+    }
+
     fn name(&self) -> &'static str {
         "symplectic_euler"
     }
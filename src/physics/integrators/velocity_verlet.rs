@@ -5,7 +5,7 @@
 //! conservation. This second-order symplectic integrator is widely regarded
 //! as the best general-purpose method for Hamiltonian systems.
 
-use super::{AccelerationField, Integrator};
+use super::{AccelerationField, ForceEvaluations, Integrator, SystemAccelerationField};
 use crate::physics::math::{Scalar, Vector};
 
 /// Velocity Verlet integrator
@@ -126,10 +126,51 @@ impl Integrator for VelocityVerlet {
         *velocity += (accel_old + accel_new) * (0.5 * dt);
     }
 
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        // Same scheme as `step`, but every body drifts before the new
+        // accelerations are evaluated so they see each other's new positions
+        let mut accel_old = vec![Vector::ZERO; positions.len()];
+        field.evaluate(positions, &mut accel_old);
+
+        for ((position, velocity), accel) in positions.iter_mut().zip(&*velocities).zip(&accel_old)
+        {
+            *position += *velocity * dt + *accel * (0.5 * dt * dt);
+        }
+
+        let mut accel_new = vec![Vector::ZERO; positions.len()];
+        field.evaluate(positions, &mut accel_new);
+
+        for ((velocity, old), new) in velocities.iter_mut().zip(&accel_old).zip(&accel_new) {
+            *velocity += (*old + *new) * (0.5 * dt);
+        }
+    }
+
     fn convergence_order(&self) -> usize {
         2
     }
 
+    fn is_symplectic(&self) -> bool {
+        true
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        true
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(2)
+    }
+
+    fn description(&self) -> &'static str {
+        "Second-order symplectic leapfrog averaging the start and end accelerations"
+    }
+
     fn name(&self) -> &'static str {
         "velocity_verlet"
     }
@@ -0,0 +1,479 @@
+//! Wisdom-Holman mixed-variable symplectic integrator
+//!
+//! For a system dominated by one central mass, the Hamiltonian splits into
+//! the Keplerian motion of every other body about the central mass and a
+//! small interaction between them. Each step drifts the bodies along their
+//! Kepler orbits, solved exactly by `kepler_drift`, and kicks them with the
+//! interaction alone, so the error is proportional to the ratio of the
+//! interaction to the central force rather than to the central force itself.
+//!
+//! The interaction is taken from the system acceleration field, with the
+//! Keplerian part subtracted analytically. Splitting the Hamiltonian this way
+//! needs the masses behind the field, which it reads from
+//! `SystemAccelerationField::gravitational_parameters`.
+
+use super::{
+    AccelerationField, ForceEvaluations, Integrator, IntegratorParameters, SystemAccelerationField,
+};
+use crate::physics::kepler::kepler_drift;
+use crate::physics::math::{Scalar, Vector};
+use serde::Deserialize;
+
+/// Canonical coordinates the Hamiltonian is split in
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WisdomHolmanCoordinates {
+    /// Each body relative to the center of mass of the central body and every
+    /// body closer to it, the original coordinates of Wisdom and Holman
+    #[default]
+    Jacobi,
+    /// Positions relative to the central body and barycentric momenta, after
+    /// Duncan, Levison and Lee
+    DemocraticHeliocentric,
+}
+
+/// Parameters accepted in the `[physics.integrator]` table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WisdomHolmanParameters {
+    coordinates: Option<WisdomHolmanCoordinates>,
+}
+
+/// Wisdom-Holman integrator - 2nd order mixed-variable symplectic splitting
+///
+/// # Algorithm
+///
+/// Drift-kick-drift in Jacobi coordinates:
+/// ```text
+/// drift(dt/2): every body follows its Kepler orbit about the interior mass
+/// kick(dt):    v' += (a' + G·Mᵢ·r'/|r'|³)·dt
+/// drift(dt/2)
+/// ```
+///
+/// where `a'` is the field's acceleration in Jacobi coordinates and the
+/// second term cancels its Keplerian part. In democratic heliocentric
+/// coordinates each drift is split around a linear "jump" of the positions
+/// by the momentum of the central body, and the kick removes the central
+/// body's attraction.
+///
+/// The body with the largest mass is taken as the central body. In Jacobi
+/// coordinates the others are nested by their distance from it at the start
+/// of each step.
+///
+/// # Mathematical Properties
+///
+/// - **Order of accuracy**: O(ε·dt²), with ε the ratio of the interaction to
+///   the central force
+/// - **Force evaluations**: 1 per step
+/// - **Symplectic**: Yes
+/// - **Time-reversible**: Yes
+///
+/// # Parameters
+///
+/// `coordinates` selects `"jacobi"` (the default) or
+/// `"democratic_heliocentric"`:
+///
+/// ```toml
+/// [physics.integrator]
+/// type = "wisdom_holman"
+/// coordinates = "jacobi"
+/// ```
+///
+/// # Limitations
+///
+/// The per-body `step`, used by block timesteps, and fields that do not
+/// report their masses have no central body to split around. Both fall back
+/// to drift-kick-drift leapfrog in the full field. The Keplerian part is
+/// subtracted exactly, so any error of the field in the central force, such
+/// as from the Barnes-Hut approximation, ends up in the interaction.
+///
+/// # Use Cases
+///
+/// **Ideal for:**
+/// - Planetary systems and other hierarchies around one dominant mass
+///
+/// **Consider alternatives:**
+/// - Use Velocity Verlet or a higher order symplectic method when no single
+///   body dominates
+/// - Use IAS15 for close encounters between the orbiting bodies
+///
+/// # Reference
+///
+/// Wisdom, Holman (1991) "Symplectic maps for the N-body problem", The
+/// Astronomical Journal 102, 1528. DOI: 10.1086/115978
+///
+/// Duncan, Levison, Lee (1998) "A multiple time step symplectic algorithm for
+/// integrating close encounters", The Astronomical Journal 116, 2067.
+/// DOI: 10.1086/300541
+#[derive(Debug, Copy, Clone, Default)]
+pub struct WisdomHolman {
+    coordinates: WisdomHolmanCoordinates,
+}
+
+impl WisdomHolman {
+    /// Create a Wisdom-Holman integrator splitting in the given coordinates
+    pub fn new(coordinates: WisdomHolmanCoordinates) -> Self {
+        Self { coordinates }
+    }
+
+    /// Coordinates the Hamiltonian is split in
+    pub fn coordinates(&self) -> WisdomHolmanCoordinates {
+        self.coordinates
+    }
+
+    /// Drift-kick-drift leapfrog in the full field, for when there is no
+    /// central body to split around
+    fn leapfrog(
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        let mut accelerations = vec![Vector::ZERO; positions.len()];
+
+        for (position, &velocity) in positions.iter_mut().zip(velocities.iter()) {
+            *position += velocity * (0.5 * dt);
+        }
+        field.evaluate(positions, &mut accelerations);
+        for ((position, velocity), &acceleration) in positions
+            .iter_mut()
+            .zip(velocities.iter_mut())
+            .zip(&accelerations)
+        {
+            *velocity += acceleration * dt;
+            *position += *velocity * (0.5 * dt);
+        }
+    }
+
+    /// One drift-kick-drift step in Jacobi coordinates
+    fn step_jacobi(
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        gravitational_parameters: &[Scalar],
+        central: usize,
+        dt: Scalar,
+    ) {
+        let body_count = positions.len();
+
+        // Central body first, then the others from the inside out
+        let mut order: Vec<usize> = (0..body_count).filter(|&i| i != central).collect();
+        order.sort_by(|&a, &b| {
+            let distance = |i: usize| positions[i].distance_squared(positions[central]);
+            distance(a).total_cmp(&distance(b))
+        });
+        order.insert(0, central);
+
+        let masses: Vec<Scalar> = order.iter().map(|&i| gravitational_parameters[i]).collect();
+        let interior: Vec<Scalar> = masses
+            .iter()
+            .scan(0.0, |total, &mass| {
+                *total += mass;
+                Some(*total)
+            })
+            .collect();
+
+        let gather = |values: &[Vector]| -> Vec<Vector> {
+            let ordered: Vec<Vector> = order.iter().map(|&i| values[i]).collect();
+            to_jacobi(&ordered, &masses, &interior)
+        };
+        let mut jacobi_positions = gather(positions);
+        let mut jacobi_velocities = gather(velocities);
+
+        let drift =
+            |jacobi_positions: &mut [Vector], jacobi_velocities: &mut [Vector], dt: Scalar| {
+                jacobi_positions[0] += jacobi_velocities[0] * dt;
+                for k in 1..body_count {
+                    kepler_drift(
+                        &mut jacobi_positions[k],
+                        &mut jacobi_velocities[k],
+                        interior[k],
+                        dt,
+                    );
+                }
+            };
+
+        drift(&mut jacobi_positions, &mut jacobi_velocities, 0.5 * dt);
+
+        let ordered = from_jacobi(&jacobi_positions, &masses, &interior);
+        for (&i, &position) in order.iter().zip(&ordered) {
+            positions[i] = position;
+        }
+        let mut accelerations = vec![Vector::ZERO; body_count];
+        field.evaluate(positions, &mut accelerations);
+        let jacobi_accelerations = gather(&accelerations);
+
+        for k in 1..body_count {
+            let r = jacobi_positions[k].length();
+            let keplerian = if r > 0.0 {
+                jacobi_positions[k] * (interior[k] / (r * r * r))
+            } else {
+                Vector::ZERO
+            };
+            jacobi_velocities[k] += (jacobi_accelerations[k] + keplerian) * dt;
+        }
+
+        drift(&mut jacobi_positions, &mut jacobi_velocities, 0.5 * dt);
+
+        let ordered_positions = from_jacobi(&jacobi_positions, &masses, &interior);
+        let ordered_velocities = from_jacobi(&jacobi_velocities, &masses, &interior);
+        for (k, &i) in order.iter().enumerate() {
+            positions[i] = ordered_positions[k];
+            velocities[i] = ordered_velocities[k];
+        }
+    }
+
+    /// One step in democratic heliocentric coordinates
+    ///
+    /// The Kepler drift and the jump are each split in half around the kick.
+    fn step_democratic_heliocentric(
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        gravitational_parameters: &[Scalar],
+        central: usize,
+        dt: Scalar,
+    ) {
+        let central_mass = gravitational_parameters[central];
+        let total_mass: Scalar = gravitational_parameters.iter().sum();
+        let barycenter = positions
+            .iter()
+            .zip(gravitational_parameters)
+            .map(|(&position, &mass)| position * mass)
+            .sum::<Vector>()
+            / total_mass;
+        let barycentric_velocity = velocities
+            .iter()
+            .zip(gravitational_parameters)
+            .map(|(&velocity, &mass)| velocity * mass)
+            .sum::<Vector>()
+            / total_mass;
+
+        // Heliocentric positions and barycentric velocities; the central
+        // body's entries are unused
+        let mut relative_positions: Vec<Vector> = positions
+            .iter()
+            .map(|&position| position - positions[central])
+            .collect();
+        let mut relative_velocities: Vec<Vector> = velocities
+            .iter()
+            .map(|&velocity| velocity - barycentric_velocity)
+            .collect();
+        relative_positions[central] = Vector::ZERO;
+        relative_velocities[central] = Vector::ZERO;
+
+        let jump =
+            |relative_positions: &mut [Vector], relative_velocities: &[Vector], dt: Scalar| {
+                let momentum = relative_velocities
+                    .iter()
+                    .zip(gravitational_parameters)
+                    .map(|(&velocity, &mass)| velocity * mass)
+                    .sum::<Vector>();
+                let shift = momentum * (dt / central_mass);
+                for (i, position) in relative_positions.iter_mut().enumerate() {
+                    if i != central {
+                        *position += shift;
+                    }
+                }
+            };
+        let drift =
+            |relative_positions: &mut [Vector], relative_velocities: &mut [Vector], dt: Scalar| {
+                for (i, (position, velocity)) in relative_positions
+                    .iter_mut()
+                    .zip(relative_velocities.iter_mut())
+                    .enumerate()
+                {
+                    if i != central {
+                        kepler_drift(position, velocity, central_mass, dt);
+                    }
+                }
+            };
+        let to_inertial =
+            |relative_positions: &[Vector], barycenter: Vector, out: &mut [Vector]| {
+                let central_position = barycenter
+                    - relative_positions
+                        .iter()
+                        .zip(gravitational_parameters)
+                        .map(|(&position, &mass)| position * mass)
+                        .sum::<Vector>()
+                        / total_mass;
+                for (position, &relative) in out.iter_mut().zip(relative_positions) {
+                    *position = central_position + relative;
+                }
+            };
+
+        drift(&mut relative_positions, &mut relative_velocities, 0.5 * dt);
+        jump(&mut relative_positions, &relative_velocities, 0.5 * dt);
+
+        to_inertial(
+            &relative_positions,
+            barycenter + barycentric_velocity * (0.5 * dt),
+            positions,
+        );
+        let mut accelerations = vec![Vector::ZERO; positions.len()];
+        field.evaluate(positions, &mut accelerations);
+        for (i, (velocity, &acceleration)) in relative_velocities
+            .iter_mut()
+            .zip(&accelerations)
+            .enumerate()
+        {
+            let r = relative_positions[i].length();
+            if i != central && r > 0.0 {
+                let keplerian = relative_positions[i] * (central_mass / (r * r * r));
+                *velocity += (acceleration + keplerian) * dt;
+            }
+        }
+
+        jump(&mut relative_positions, &relative_velocities, 0.5 * dt);
+        drift(&mut relative_positions, &mut relative_velocities, 0.5 * dt);
+
+        to_inertial(
+            &relative_positions,
+            barycenter + barycentric_velocity * dt,
+            positions,
+        );
+        let central_momentum = relative_velocities
+            .iter()
+            .zip(gravitational_parameters)
+            .map(|(&velocity, &mass)| velocity * mass)
+            .sum::<Vector>();
+        for (velocity, &relative) in velocities.iter_mut().zip(&relative_velocities) {
+            *velocity = barycentric_velocity + relative;
+        }
+        velocities[central] = barycentric_velocity - central_momentum / central_mass;
+    }
+}
+
+/// Convert vectors of bodies ordered from the inside out to Jacobi
+/// coordinates
+///
+/// Entry 0 becomes the center of mass of the whole system and entry `k` is
+/// taken relative to the center of mass of entries `0..k`. Positions,
+/// velocities and accelerations all transform this way.
+fn to_jacobi(values: &[Vector], masses: &[Scalar], interior: &[Scalar]) -> Vec<Vector> {
+    let mut jacobi = vec![Vector::ZERO; values.len()];
+    let mut weighted = values[0] * masses[0];
+
+    for k in 1..values.len() {
+        jacobi[k] = values[k] - weighted / interior[k - 1];
+        weighted += values[k] * masses[k];
+    }
+    jacobi[0] = weighted / interior[values.len() - 1];
+
+    jacobi
+}
+
+/// Convert Jacobi coordinates back, inverting `to_jacobi`
+fn from_jacobi(jacobi: &[Vector], masses: &[Scalar], interior: &[Scalar]) -> Vec<Vector> {
+    let mut values = vec![Vector::ZERO; jacobi.len()];
+    let mut weighted = jacobi[0] * interior[jacobi.len() - 1];
+
+    for k in (1..jacobi.len()).rev() {
+        // Center of mass of the bodies inside body k
+        let inner = (weighted - jacobi[k] * masses[k]) / interior[k];
+        values[k] = jacobi[k] + inner;
+        weighted = inner * interior[k - 1];
+    }
+    values[0] = weighted / masses[0];
+
+    values
+}
+
+impl Integrator for WisdomHolman {
+    fn clone_box(&self) -> Box<dyn Integrator> {
+        Box::new(*self)
+    }
+
+    fn configure(&self, parameters: &IntegratorParameters) -> Result<Box<dyn Integrator>, String> {
+        let parameters: WisdomHolmanParameters = toml::Value::Table(parameters.clone())
+            .try_into()
+            .map_err(|e| format!("Invalid Wisdom-Holman parameters: {}", e.message()))?;
+
+        Ok(Box::new(Self::new(
+            parameters.coordinates.unwrap_or_default(),
+        )))
+    }
+
+    fn step(
+        &self,
+        position: &mut Vector,
+        velocity: &mut Vector,
+        field: &dyn AccelerationField,
+        dt: Scalar,
+    ) {
+        *position += *velocity * (0.5 * dt);
+        *velocity += field.at(*position) * dt;
+        *position += *velocity * (0.5 * dt);
+    }
+
+    fn step_system(
+        &self,
+        positions: &mut [Vector],
+        velocities: &mut [Vector],
+        field: &mut dyn SystemAccelerationField,
+        dt: Scalar,
+    ) {
+        let Some(gravitational_parameters) = field.gravitational_parameters().map(<[_]>::to_vec)
+        else {
+            Self::leapfrog(positions, velocities, field, dt);
+            return;
+        };
+
+        let central = gravitational_parameters
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+        let Some(central) = central.filter(|&i| gravitational_parameters[i] > 0.0) else {
+            Self::leapfrog(positions, velocities, field, dt);
+            return;
+        };
+
+        match self.coordinates {
+            WisdomHolmanCoordinates::Jacobi => Self::step_jacobi(
+                positions,
+                velocities,
+                field,
+                &gravitational_parameters,
+                central,
+                dt,
+            ),
+            WisdomHolmanCoordinates::DemocraticHeliocentric => Self::step_democratic_heliocentric(
+                positions,
+                velocities,
+                field,
+                &gravitational_parameters,
+                central,
+                dt,
+            ),
+        }
+    }
+
+    fn convergence_order(&self) -> usize {
+        2
+    }
+
+    fn is_symplectic(&self) -> bool {
+        true
+    }
+
+    fn is_time_reversible(&self) -> bool {
+        true
+    }
+
+    fn force_evaluations(&self) -> ForceEvaluations {
+        ForceEvaluations::Fixed(1)
+    }
+
+    fn description(&self) -> &'static str {
+        "Second-order mixed-variable symplectic splitting about a dominant mass"
+    }
+
+    fn name(&self) -> &'static str {
+        "wisdom_holman"
+    }
+
+    fn aliases(&self) -> Vec<&'static str> {
+        vec!["wh", "mixed_variable_symplectic"]
+    }
+}
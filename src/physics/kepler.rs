@@ -0,0 +1,119 @@
+//! Universal-variable solution of the two-body problem
+//!
+//! Advances a body around a fixed point mass by solving Kepler's equation in
+//! the universal anomaly `s`, which covers elliptic, parabolic and hyperbolic
+//! orbits with the same formulae:
+//!
+//! ```text
+//! t = r₀·s·c₁(βs²) + η₀·s²·c₂(βs²) + μ·s³·c₃(βs²)
+//! ```
+//!
+//! where `β = 2μ/r₀ - v₀²`, `η₀ = r₀·v₀` and `cₖ` are the Stumpff functions.
+//! The new state follows from the Gauss f and g functions, so the orbit is
+//! advanced exactly up to round-off whatever the length of the step.
+//!
+//! # Reference
+//!
+//! Danby (1992) "Fundamentals of Celestial Mechanics", 2nd edition,
+//! Willmann-Bell, sections 6.9 and 6.13.
+
+use crate::physics::math::{Scalar, Vector};
+use std::f64::consts::TAU;
+
+/// Cap on Laguerre-Conway iterations for the universal anomaly
+const MAX_ITERATIONS: usize = 50;
+
+/// Degree of the Laguerre-Conway iteration
+const LAGUERRE_DEGREE: Scalar = 5.0;
+
+/// Stumpff functions c₀, c₁, c₂ and c₃ of `z`
+///
+/// Near zero the closed forms lose precision to cancellation, so the series
+/// is summed instead.
+fn stumpff(z: Scalar) -> [Scalar; 4] {
+    if z.abs() <= 1.0 {
+        // cₖ(z) = Σ (-z)ʲ / (2j + k)!
+        let mut c: [Scalar; 4] = [0.0; 4];
+        for (k, value) in c.iter_mut().enumerate() {
+            let mut term = 1.0 / (1..=k).map(|i| i as Scalar).product::<Scalar>();
+            let mut j = 0;
+            while j == 0 || term.abs() > Scalar::EPSILON * value.abs() {
+                *value += term;
+                j += 1;
+                let n = (2 * j + k) as Scalar;
+                term *= -z / (n * (n - 1.0));
+            }
+        }
+        c
+    } else if z > 0.0 {
+        let root = z.sqrt();
+        let c0 = root.cos();
+        let c1 = root.sin() / root;
+        [c0, c1, (1.0 - c0) / z, (1.0 - c1) / z]
+    } else {
+        let root = (-z).sqrt();
+        let c0 = root.cosh();
+        let c1 = root.sinh() / root;
+        [c0, c1, (1.0 - c0) / z, (1.0 - c1) / z]
+    }
+}
+
+/// Advance a body on a Kepler orbit around a fixed point mass at the origin
+///
+/// Without an attracting mass, or with the body at the origin, it moves in a
+/// straight line instead.
+///
+/// # Arguments
+/// * `position` - Position relative to the attracting mass, updated in place
+/// * `velocity` - Velocity relative to the attracting mass, updated in place
+/// * `mu` - Gravitational parameter G·M of the two-body problem
+/// * `dt` - Time to advance, which may be negative
+pub fn kepler_drift(position: &mut Vector, velocity: &mut Vector, mu: Scalar, dt: Scalar) {
+    let r0 = position.length();
+    if mu <= 0.0 || r0 == 0.0 {
+        *position += *velocity * dt;
+        return;
+    }
+
+    let eta0 = position.dot(*velocity);
+    let beta = 2.0 * mu / r0 - velocity.length_squared();
+    let zeta0 = mu - beta * r0;
+
+    // Whole periods of a bound orbit bring the body back where it started,
+    // so only the remainder needs solving
+    let mut t = dt;
+    if beta > 0.0 {
+        let period = TAU * mu / (beta * beta.sqrt());
+        t -= (dt / period).trunc() * period;
+    }
+
+    let mut s = t / r0;
+    let mut c = stumpff(beta * s * s);
+    for _ in 0..MAX_ITERATIONS {
+        let f = r0 * s * c[1] + eta0 * s * s * c[2] + mu * s * s * s * c[3] - t;
+        let df = r0 * c[0] + eta0 * s * c[1] + mu * s * s * c[2];
+        let ddf = eta0 * c[0] + zeta0 * s * c[1];
+
+        let n = LAGUERRE_DEGREE;
+        let discriminant = ((n - 1.0) * (n - 1.0) * df * df - n * (n - 1.0) * f * ddf)
+            .abs()
+            .sqrt();
+        let ds = -n * f / (df + discriminant.copysign(df));
+
+        s += ds;
+        c = stumpff(beta * s * s);
+        if ds.abs() <= 2.0 * Scalar::EPSILON * s.abs() {
+            break;
+        }
+    }
+
+    let r = r0 * c[0] + eta0 * s * c[1] + mu * s * s * c[2];
+    let f = 1.0 - mu * s * s * c[2] / r0;
+    let g = t - mu * s * s * s * c[3];
+    let df = -mu * s * c[1] / (r * r0);
+    let dg = 1.0 - mu * s * s * c[2] / r;
+
+    let (x0, v0) = (*position, *velocity);
+    *position = x0 * f + v0 * g;
+    *velocity = x0 * df + v0 * dg;
+}
@@ -0,0 +1,835 @@
+//! Linear Barnes-Hut octree stored in a flat, depth-first arena
+//!
+//! Each body gets a Morton key that interleaves the bits of its cell
+//! coordinates at the finest depth the octree allows, with the x, y, and z
+//! bits ordered like `Octant` indices. Sorting the keys lines the bodies up so
+//! that every octree node covers one contiguous run of them, and the three key
+//! bits at a node's depth tell which child each body belongs to.
+//!
+//! Nodes are laid out depth-first, each recording how many nodes its subtree
+//! spans. A traversal walks the arena front to back: it steps into a node's
+//! first child by moving to the next node, and skips an accepted node's whole
+//! subtree by jumping past it, with no recursion or pointer chasing. Bodies
+//! are stored in key order, so the bodies of a leaf and the leaves of nearby
+//! nodes sit next to each other in memory.
+//!
+//! Construction computes the keys in parallel, radix-sorts them, splits the
+//! top of the tree into subtrees of similar size, and builds those subtrees
+//! in parallel on the compute task pool before joining them under the top
+//! levels.
+//!
+//! The subdivision, node bounds, aggregated moments, and opening criterion
+//! match `Octree`, so both produce the same forces up to round-off. The
+//! exception is a body lying exactly on a splitting plane, which `Octree`
+//! puts in the lower octant and the Morton key in the upper one.
+//!
+//! # Reference
+//!
+//! Warren & Salmon (1993) "A parallel hashed oct-tree N-body algorithm",
+//! Proceedings of Supercomputing '93, 12-21.
+
+use crate::physics::aabb3d::Aabb3d;
+use crate::physics::gravity::{PairForce, Softening};
+use crate::physics::integrators::for_each_chunk;
+use crate::physics::math::{Matrix, Scalar, Vector, VectorExt};
+use crate::physics::octree::{
+    DEFAULT_RELATIVE_ACCURACY, MAX_OCTREE_DEPTH, MultipoleOrder, NodeAcceptance, OctreeBody,
+    OpeningCriterion, quadrupole_force, quadrupole_force_and_jerk, quadrupole_moment,
+    quadrupole_potential, record_accelerations,
+};
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::Entity;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use std::ops::Range;
+
+/// Cells per axis at the deepest level of the octree
+const CELLS_PER_AXIS: u64 = 1 << MAX_OCTREE_DEPTH;
+
+/// Low bits of each sort entry holding the index of its body, below the
+/// Morton key
+const INDEX_BITS: usize = 32;
+
+/// Bits of a Morton key, three per depth
+const KEY_BITS: usize = 3 * MAX_OCTREE_DEPTH;
+
+/// Bits sorted per radix sort pass
+const RADIX_BITS: usize = 11;
+
+/// Radix sort passes over the key bits
+const RADIX_PASSES: usize = KEY_BITS.div_ceil(RADIX_BITS);
+
+/// Subtrees built per compute thread, so that uneven subtrees still keep
+/// every thread busy
+const SUBTREES_PER_THREAD: usize = 4;
+
+/// Fewest bodies worth a subtree task of their own
+const MIN_BODIES_PER_SUBTREE: usize = 1024;
+
+/// A node of the linear octree
+///
+/// Leaves hold their bodies exactly like the external nodes of `Octree`;
+/// the aggregated moments of a leaf are only used to build its parent.
+#[derive(Debug, Clone, Copy)]
+struct LinearNode {
+    bounds: Aabb3d,
+    size_squared: Scalar,            // Squared diagonal of the bounds
+    center_of_mass: Vector,          // Weighted average position of all contained bodies
+    center_of_mass_velocity: Vector, // Weighted average velocity of all contained bodies
+    total_mass: Scalar,              // Sum of all contained body masses
+    quadrupole: Matrix,              // Quadrupole moment about the center of mass
+    first_body: u32,                 // Index of the first contained body in key order
+    body_count: u32,                 // Number of contained bodies
+    subtree_len: u32,                // Nodes in this node's subtree, itself included
+    is_leaf: bool,                   // Whether the node holds its bodies directly
+}
+
+impl LinearNode {
+    /// Leaf over `range` with its moments still to be filled in
+    fn empty(range: Range<usize>, bounds: Aabb3d) -> Self {
+        Self {
+            bounds,
+            size_squared: bounds.min.distance_squared(bounds.max),
+            center_of_mass: bounds.center(),
+            center_of_mass_velocity: Vector::ZERO,
+            total_mass: 0.0,
+            quadrupole: Matrix::ZERO,
+            first_body: range.start as u32,
+            body_count: range.len() as u32,
+            subtree_len: 1,
+            is_leaf: true,
+        }
+    }
+
+    fn bodies(&self) -> Range<usize> {
+        let first = self.first_body as usize;
+        first..first + self.body_count as usize
+    }
+}
+
+/// Barnes-Hut octree with nodes in a flat, depth-first arena
+///
+/// A drop-in alternative to `Octree` with the same parameters and forces,
+/// whose construction scales across the compute task pool and whose
+/// traversal reads memory in order. Selected with
+/// `OctreeLayout::Linear`.
+///
+/// # Performance Characteristics
+///
+/// * Tree construction: O(N) key computation and radix sort, then O(N log N)
+///   node building split across threads
+/// * Force calculation per body: O(log N) average, O(N) worst case
+/// * Memory usage: O(N) for bodies + O(N) for tree nodes, reused across builds
+#[derive(Debug)]
+pub struct LinearOctree {
+    pub theta: Scalar,                       // Barnes-Hut approximation parameter
+    pub min_distance: Scalar,                // Minimum distance for force calculation
+    pub max_force: Scalar,                   // Maximum force magnitude
+    pub softening: Softening,                // Softening kernel of the pair force
+    pub leaf_threshold: usize,               // Maximum bodies per leaf node
+    pub multipole_order: MultipoleOrder,     // Expansion order of approximated nodes
+    pub opening_criterion: OpeningCriterion, // Test for approximating a node
+    pub relative_accuracy: Scalar,           // α of the relative acceleration criterion
+    nodes: Vec<LinearNode>,                  // Depth-first node arena
+    bodies: Vec<OctreeBody>,                 // Bodies in key order
+    keys: Vec<u128>,                         // Sorted Morton keys above each body's input index
+    scratch: Vec<u128>,                      // Radix sort buffer
+    subtrees: Vec<Vec<LinearNode>>,          // Arenas of the subtrees built in parallel
+    accelerations: EntityHashMap<Scalar>,    // Latest acceleration magnitude of each body
+}
+
+impl LinearOctree {
+    /// Creates a new linear octree with the specified parameters.
+    ///
+    /// The parameters mean the same as for `Octree::new`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use stardrift::physics::linear_octree::LinearOctree;
+    ///
+    /// let octree = LinearOctree::new(0.5, 0.01, 1e6);
+    /// ```
+    pub fn new(theta: Scalar, min_distance: Scalar, max_force: Scalar) -> Self {
+        Self {
+            theta,
+            min_distance,
+            max_force,
+            softening: Softening::Clamp,
+            leaf_threshold: 4,
+            multipole_order: MultipoleOrder::Monopole,
+            opening_criterion: OpeningCriterion::Geometric,
+            relative_accuracy: DEFAULT_RELATIVE_ACCURACY,
+            nodes: Vec::new(),
+            bodies: Vec::new(),
+            keys: Vec::new(),
+            scratch: Vec::new(),
+            subtrees: Vec::new(),
+            accelerations: EntityHashMap::default(),
+        }
+    }
+
+    /// Sets the maximum number of bodies allowed in a leaf node before
+    /// subdivision. Default is 4.
+    pub fn with_leaf_threshold(mut self, leaf_threshold: usize) -> Self {
+        self.leaf_threshold = leaf_threshold;
+        self
+    }
+
+    /// Sets the kernel that softens the force between close bodies. Default
+    /// is `Softening::Clamp`.
+    pub fn with_softening(mut self, softening: Softening) -> Self {
+        self.softening = softening;
+        self
+    }
+
+    /// Sets the order of the multipole expansion used for approximated
+    /// nodes. Default is `MultipoleOrder::Monopole`.
+    pub fn with_multipole_order(mut self, multipole_order: MultipoleOrder) -> Self {
+        self.multipole_order = multipole_order;
+        self
+    }
+
+    /// Sets the test that decides whether a node is far enough from a body to
+    /// be approximated. Default is `OpeningCriterion::Geometric`.
+    pub fn with_opening_criterion(mut self, opening_criterion: OpeningCriterion) -> Self {
+        self.opening_criterion = opening_criterion;
+        self
+    }
+
+    /// Sets α of the relative acceleration criterion. Default is 0.0005.
+    pub fn with_relative_accuracy(mut self, relative_accuracy: Scalar) -> Self {
+        self.relative_accuracy = relative_accuracy;
+        self
+    }
+
+    /// Records the acceleration of each body, for the relative acceleration
+    /// criterion, as `Octree::record_accelerations` does.
+    pub fn record_accelerations(&mut self, bodies: &[OctreeBody], accelerations: &[Vector]) {
+        record_accelerations(
+            &mut self.accelerations,
+            self.opening_criterion,
+            bodies,
+            accelerations,
+        );
+    }
+
+    /// Returns the bounding boxes of all nodes in the octree, in depth-first
+    /// order.
+    pub fn bounds(&self) -> Vec<Aabb3d> {
+        self.nodes.iter().map(|node| node.bounds).collect()
+    }
+
+    /// Builds the octree from a collection of bodies.
+    ///
+    /// The root bounds are the bounding box of the bodies with 10% padding,
+    /// as for `Octree::build`. Buffers from the previous build are reused.
+    pub fn build(&mut self, bodies: &[OctreeBody]) {
+        self.nodes.clear();
+        self.bodies.clear();
+        self.keys.clear();
+
+        let Some(first) = bodies.first() else {
+            return;
+        };
+
+        let (min, max) =
+            bodies
+                .iter()
+                .fold((first.position, first.position), |(min, max), body| {
+                    (
+                        min.component_min(body.position),
+                        max.component_max(body.position),
+                    )
+                });
+        let padding = (max - min) * 0.1;
+        let bounds = Aabb3d::new(min - padding, max + padding);
+
+        // Cells per unit length on each axis; a flat axis keeps every body in
+        // its lower half, as `Octree` does
+        let extent = bounds.max - bounds.min;
+        let scale = Vector::new(
+            cells_per_unit(extent.x),
+            cells_per_unit(extent.y),
+            cells_per_unit(extent.z),
+        );
+
+        self.keys.resize(bodies.len(), 0);
+        for_each_chunk(&mut self.keys, |offset, chunk| {
+            for (i, key) in chunk.iter_mut().enumerate() {
+                let index = offset + i;
+                *key = morton_key(bodies[index].position, bounds.min, scale) << INDEX_BITS
+                    | index as u128;
+            }
+        });
+
+        radix_sort(&mut self.keys, &mut self.scratch);
+
+        self.bodies
+            .extend(self.keys.iter().map(|&key| bodies[body_index(key)]));
+
+        let builder = NodeBuilder {
+            bodies: &self.bodies,
+            keys: &self.keys,
+            leaf_threshold: self.leaf_threshold,
+            multipole_order: self.multipole_order,
+        };
+
+        let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+        let subtree_size = (bodies.len() / (task_pool.thread_num().max(1) * SUBTREES_PER_THREAD))
+            .max(MIN_BODIES_PER_SUBTREE);
+
+        // Split the top of the tree into subtrees of at most `subtree_size`
+        // bodies and build them in parallel
+        let mut tasks = Vec::new();
+        builder.collect_subtrees(0..bodies.len(), 0, bounds, subtree_size, &mut tasks);
+
+        if self.subtrees.len() < tasks.len() {
+            self.subtrees.resize_with(tasks.len(), Vec::new);
+        }
+        for_each_chunk(&mut self.subtrees[..tasks.len()], |offset, chunk| {
+            for (i, nodes) in chunk.iter_mut().enumerate() {
+                let (range, depth, bounds) = &tasks[offset + i];
+                nodes.clear();
+                builder.build_subtree(range.clone(), *depth, *bounds, nodes);
+            }
+        });
+
+        // Join the subtrees under the top levels of the tree
+        let mut subtrees = self.subtrees[..tasks.len()].iter();
+        builder.assemble(
+            0..bodies.len(),
+            0,
+            bounds,
+            subtree_size,
+            &mut self.nodes,
+            &mut subtrees,
+        );
+    }
+
+    /// Softened pair force shared with the other gravity solvers
+    #[inline]
+    fn pair_force(&self) -> PairForce {
+        PairForce::new(self.min_distance, self.max_force).with_softening(self.softening)
+    }
+
+    /// Calculate force at an arbitrary position, excluding a specific entity.
+    ///
+    /// Equivalent to `Octree::calculate_force_at_position`.
+    pub fn calculate_force_at_position(
+        &self,
+        position: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Vector {
+        let pair_force = self.pair_force();
+        let acceptance = self.opening_criterion.acceptance(
+            self.theta,
+            self.relative_accuracy,
+            &self.accelerations,
+            exclude_entity,
+            g,
+        );
+        let mut force = Vector::ZERO;
+        let mut index = 0;
+
+        while let Some(node) = self.nodes.get(index) {
+            if node.is_leaf {
+                for other in &self.bodies[node.bodies()] {
+                    if other.entity != exclude_entity {
+                        force += pair_force.force(other.position - position, g * mass * other.mass);
+                    }
+                }
+            } else if Self::accepts(node, position, acceptance) {
+                force +=
+                    pair_force.force(node.center_of_mass - position, g * mass * node.total_mass);
+                if self.multipole_order == MultipoleOrder::Quadrupole {
+                    force += quadrupole_force(
+                        &pair_force,
+                        position - node.center_of_mass,
+                        mass,
+                        node.total_mass,
+                        &node.quadrupole,
+                        g,
+                    );
+                }
+            } else {
+                // Open the node: its first child is the next node
+                index += 1;
+                continue;
+            }
+            index += node.subtree_len as usize;
+        }
+
+        force
+    }
+
+    /// Calculate force and its time derivative at an arbitrary position and
+    /// velocity, excluding a specific entity.
+    ///
+    /// Equivalent to `Octree::calculate_force_and_jerk_at_position`.
+    pub fn calculate_force_and_jerk_at_position(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        let pair_force = self.pair_force();
+        let acceptance = self.opening_criterion.acceptance(
+            self.theta,
+            self.relative_accuracy,
+            &self.accelerations,
+            exclude_entity,
+            g,
+        );
+        let mut force = Vector::ZERO;
+        let mut jerk = Vector::ZERO;
+        let mut index = 0;
+
+        while let Some(node) = self.nodes.get(index) {
+            if node.is_leaf {
+                for other in &self.bodies[node.bodies()] {
+                    if other.entity != exclude_entity {
+                        let (body_force, body_jerk) = pair_force.force_and_jerk(
+                            other.position - position,
+                            other.velocity - velocity,
+                            g * mass * other.mass,
+                        );
+                        force += body_force;
+                        jerk += body_jerk;
+                    }
+                }
+            } else if Self::accepts(node, position, acceptance) {
+                let (node_force, node_jerk) = pair_force.force_and_jerk(
+                    node.center_of_mass - position,
+                    node.center_of_mass_velocity - velocity,
+                    g * mass * node.total_mass,
+                );
+                force += node_force;
+                jerk += node_jerk;
+                if self.multipole_order == MultipoleOrder::Quadrupole {
+                    let (quadrupole_force, quadrupole_jerk) = quadrupole_force_and_jerk(
+                        &pair_force,
+                        position - node.center_of_mass,
+                        velocity - node.center_of_mass_velocity,
+                        mass,
+                        node.total_mass,
+                        &node.quadrupole,
+                        g,
+                    );
+                    force += quadrupole_force;
+                    jerk += quadrupole_jerk;
+                }
+            } else {
+                index += 1;
+                continue;
+            }
+            index += node.subtree_len as usize;
+        }
+
+        (force, jerk)
+    }
+
+    /// Gravitational potential at an arbitrary position, excluding a specific
+    /// entity.
+    ///
+    /// Equivalent to `Octree::calculate_potential_at_position`.
+    pub fn calculate_potential_at_position(
+        &self,
+        position: Vector,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Scalar {
+        let pair_force = self.pair_force();
+        let acceptance = self.opening_criterion.acceptance(
+            self.theta,
+            self.relative_accuracy,
+            &self.accelerations,
+            exclude_entity,
+            g,
+        );
+        let mut potential = 0.0;
+        let mut index = 0;
+
+        while let Some(node) = self.nodes.get(index) {
+            if node.is_leaf {
+                for other in &self.bodies[node.bodies()] {
+                    if other.entity != exclude_entity {
+                        potential +=
+                            pair_force.potential(position.distance(other.position), g * other.mass);
+                    }
+                }
+            } else if Self::accepts(node, position, acceptance) {
+                potential += pair_force
+                    .potential(position.distance(node.center_of_mass), g * node.total_mass);
+                if self.multipole_order == MultipoleOrder::Quadrupole {
+                    potential += quadrupole_potential(
+                        &pair_force,
+                        position - node.center_of_mass,
+                        &node.quadrupole,
+                        g,
+                    );
+                }
+            } else {
+                index += 1;
+                continue;
+            }
+            index += node.subtree_len as usize;
+        }
+
+        potential
+    }
+
+    /// Total gravitational potential energy of `bodies`, which must be the
+    /// bodies of the latest `build`; see `Octree::potential_energy`
+    ///
+    /// The bodies are evaluated in key order, as for forces.
+    pub fn potential_energy(&self, bodies: &[OctreeBody], g: Scalar) -> Scalar {
+        debug_assert_eq!(bodies.len(), self.bodies.len());
+
+        let mut energies = vec![0.0; self.bodies.len()];
+        self.for_each_body_in_key_order(&mut energies, |body| {
+            0.5 * body.mass * self.calculate_potential_at_position(body.position, body.entity, g)
+        });
+        energies.iter().sum()
+    }
+
+    /// Opening criterion, as in `Octree`
+    #[inline]
+    fn accepts(node: &LinearNode, position: Vector, acceptance: NodeAcceptance) -> bool {
+        acceptance.accepts(
+            position,
+            &node.bounds,
+            node.center_of_mass,
+            node.total_mass,
+            node.size_squared,
+            position.distance_squared(node.center_of_mass),
+        )
+    }
+
+    /// Evaluate `eval` for every body in key order, so that consecutive
+    /// evaluations walk much the same nodes, and write each result to the
+    /// body's position in the input of the latest `build`
+    pub(crate) fn for_each_body_in_key_order<T: Copy + Default + Send>(
+        &self,
+        out: &mut [T],
+        eval: impl Fn(&OctreeBody) -> T + Sync,
+    ) {
+        debug_assert_eq!(self.bodies.len(), out.len());
+
+        let mut sorted = vec![T::default(); self.bodies.len()];
+        for_each_chunk(&mut sorted, |offset, chunk| {
+            for (value, body) in chunk.iter_mut().zip(&self.bodies[offset..]) {
+                *value = eval(body);
+            }
+        });
+
+        for (&key, value) in self.keys.iter().zip(sorted) {
+            out[body_index(key)] = value;
+        }
+    }
+}
+
+/// Cells of the deepest level per unit length along an axis of `extent`
+fn cells_per_unit(extent: Scalar) -> Scalar {
+    if extent > 0.0 {
+        CELLS_PER_AXIS as Scalar / extent
+    } else {
+        0.0
+    }
+}
+
+/// Index of the body of a sort entry
+#[inline]
+fn body_index(key: u128) -> usize {
+    key as u32 as usize
+}
+
+/// Octant of the body of a sort entry at `depth`
+#[inline]
+fn octant_at(key: u128, depth: usize) -> usize {
+    (key >> (INDEX_BITS + 3 * (MAX_OCTREE_DEPTH - 1 - depth))) as usize & 0b111
+}
+
+/// Morton key of `position` in a box starting at `min`, with the cell bits of
+/// each depth ordered x, y, z from least significant like `Octant` indices
+fn morton_key(position: Vector, min: Vector, scale: Vector) -> u128 {
+    // Float to integer casts saturate, so positions on the far edge land in
+    // the last cell
+    let cell =
+        |axis: usize| (((position[axis] - min[axis]) * scale[axis]) as u64).min(CELLS_PER_AXIS - 1);
+
+    spread_bits(cell(0)) | spread_bits(cell(1)) << 1 | spread_bits(cell(2)) << 2
+}
+
+/// Spread the low `MAX_OCTREE_DEPTH` bits of `cell` out to every third bit
+fn spread_bits(cell: u64) -> u128 {
+    // Spreads up to 21 bits by repeated shift-and-mask
+    fn spread_21(mut x: u64) -> u64 {
+        x &= 0x1f_ffff;
+        x = (x | x << 32) & 0x001f_0000_0000_ffff;
+        x = (x | x << 16) & 0x001f_0000_ff00_00ff;
+        x = (x | x << 8) & 0x100f_00f0_0f00_f00f;
+        x = (x | x << 4) & 0x10c3_0c30_c30c_30c3;
+        x = (x | x << 2) & 0x1249_2492_4924_9249;
+        x
+    }
+
+    const HALF: usize = MAX_OCTREE_DEPTH / 2;
+    spread_21(cell & ((1 << HALF) - 1)) as u128 | (spread_21(cell >> HALF) as u128) << (3 * HALF)
+}
+
+/// Stable least-significant-digit radix sort of `keys` by their Morton keys,
+/// which keeps bodies with equal keys in input order
+fn radix_sort(keys: &mut Vec<u128>, scratch: &mut Vec<u128>) {
+    const BUCKETS: usize = 1 << RADIX_BITS;
+    let digit =
+        |key: u128, pass: usize| (key >> (INDEX_BITS + pass * RADIX_BITS)) as usize & (BUCKETS - 1);
+
+    scratch.clear();
+    scratch.resize(keys.len(), 0);
+
+    // Count the digits of every pass in a single read of the keys
+    let mut counts = vec![[0usize; BUCKETS]; RADIX_PASSES];
+    for &key in keys.iter() {
+        for (pass, pass_counts) in counts.iter_mut().enumerate() {
+            pass_counts[digit(key, pass)] += 1;
+        }
+    }
+
+    for (pass, offsets) in counts.iter_mut().enumerate() {
+        // A digit shared by every key leaves the order unchanged
+        if offsets.contains(&keys.len()) {
+            continue;
+        }
+
+        let mut total = 0;
+        for offset in offsets.iter_mut() {
+            let count = *offset;
+            *offset = total;
+            total += count;
+        }
+
+        for &key in keys.iter() {
+            let slot = &mut offsets[digit(key, pass)];
+            scratch[*slot] = key;
+            *slot += 1;
+        }
+        std::mem::swap(keys, scratch);
+    }
+}
+
+/// Builds nodes over the sorted bodies
+struct NodeBuilder<'a> {
+    bodies: &'a [OctreeBody],
+    keys: &'a [u128],
+    leaf_threshold: usize,
+    multipole_order: MultipoleOrder,
+}
+
+impl NodeBuilder<'_> {
+    fn is_leaf(&self, range: &Range<usize>, depth: usize) -> bool {
+        depth >= MAX_OCTREE_DEPTH || range.len() <= self.leaf_threshold
+    }
+
+    /// Whether a node stays in the top levels rather than becoming the root
+    /// of a subtree built on its own
+    fn is_split(&self, range: &Range<usize>, depth: usize, subtree_size: usize) -> bool {
+        !self.is_leaf(range, depth) && range.len() > subtree_size
+    }
+
+    /// Ranges of bodies in each octant of the node over `range` at `depth`
+    fn octant_ranges(&self, range: Range<usize>, depth: usize) -> [Range<usize>; 8] {
+        let mut start = range.start;
+
+        std::array::from_fn(|octant| {
+            let end = start
+                + self.keys[start..range.end]
+                    .partition_point(|&key| octant_at(key, depth) <= octant);
+            let octant_range = start..end;
+            start = end;
+            octant_range
+        })
+    }
+
+    fn collect_subtrees(
+        &self,
+        range: Range<usize>,
+        depth: usize,
+        bounds: Aabb3d,
+        subtree_size: usize,
+        tasks: &mut Vec<(Range<usize>, usize, Aabb3d)>,
+    ) {
+        if !self.is_split(&range, depth, subtree_size) {
+            tasks.push((range, depth, bounds));
+            return;
+        }
+
+        let octants = bounds.octants();
+        for (octant, octant_range) in self.octant_ranges(range, depth).into_iter().enumerate() {
+            if !octant_range.is_empty() {
+                self.collect_subtrees(
+                    octant_range,
+                    depth + 1,
+                    octants[octant],
+                    subtree_size,
+                    tasks,
+                );
+            }
+        }
+    }
+
+    fn build_subtree(
+        &self,
+        range: Range<usize>,
+        depth: usize,
+        bounds: Aabb3d,
+        nodes: &mut Vec<LinearNode>,
+    ) {
+        if self.is_leaf(&range, depth) {
+            nodes.push(self.leaf(range, bounds));
+        } else {
+            self.build_internal(
+                range,
+                depth,
+                bounds,
+                nodes,
+                |range, depth, bounds, nodes| self.build_subtree(range, depth, bounds, nodes),
+            );
+        }
+    }
+
+    /// Rebuild the top levels above the subtrees from `collect_subtrees`,
+    /// appending each subtree in turn where its root belongs
+    fn assemble(
+        &self,
+        range: Range<usize>,
+        depth: usize,
+        bounds: Aabb3d,
+        subtree_size: usize,
+        nodes: &mut Vec<LinearNode>,
+        subtrees: &mut std::slice::Iter<'_, Vec<LinearNode>>,
+    ) {
+        if !self.is_split(&range, depth, subtree_size) {
+            if let Some(subtree) = subtrees.next() {
+                nodes.extend_from_slice(subtree);
+            }
+            return;
+        }
+
+        self.build_internal(
+            range,
+            depth,
+            bounds,
+            nodes,
+            |range, depth, bounds, nodes| {
+                self.assemble(range, depth, bounds, subtree_size, nodes, subtrees)
+            },
+        );
+    }
+
+    /// Push an internal node, build its children after it with
+    /// `build_child`, and aggregate their moments into it
+    fn build_internal(
+        &self,
+        range: Range<usize>,
+        depth: usize,
+        bounds: Aabb3d,
+        nodes: &mut Vec<LinearNode>,
+        mut build_child: impl FnMut(Range<usize>, usize, Aabb3d, &mut Vec<LinearNode>),
+    ) {
+        let index = nodes.len();
+        nodes.push(LinearNode::empty(range.clone(), bounds));
+
+        let octants = bounds.octants();
+        let mut children = [0; 8];
+        let mut child_count = 0;
+        for (octant, octant_range) in self
+            .octant_ranges(range.clone(), depth)
+            .into_iter()
+            .enumerate()
+        {
+            if !octant_range.is_empty() {
+                children[child_count] = nodes.len();
+                child_count += 1;
+                build_child(octant_range, depth + 1, octants[octant], nodes);
+            }
+        }
+        let children = &children[..child_count];
+
+        // Combine the children's moments, shifting each child's quadrupole
+        // from its own center of mass to the node's
+        let (total_mass, weighted_sum, momentum) = children.iter().fold(
+            (0.0, Vector::ZERO, Vector::ZERO),
+            |(mass_acc, pos_acc, momentum_acc), &child| {
+                let child = &nodes[child];
+                (
+                    mass_acc + child.total_mass,
+                    pos_acc + child.center_of_mass * child.total_mass,
+                    momentum_acc + child.center_of_mass_velocity * child.total_mass,
+                )
+            },
+        );
+        let (center_of_mass, center_of_mass_velocity) = if total_mass > 0.0 {
+            (weighted_sum / total_mass, momentum / total_mass)
+        } else {
+            (bounds.center(), Vector::ZERO)
+        };
+        let quadrupole = match self.multipole_order {
+            MultipoleOrder::Monopole => Matrix::ZERO,
+            MultipoleOrder::Quadrupole => children.iter().fold(Matrix::ZERO, |moment, &child| {
+                let child = &nodes[child];
+                moment
+                    + child.quadrupole
+                    + quadrupole_moment(child.center_of_mass - center_of_mass, child.total_mass)
+            }),
+        };
+
+        let subtree_len = (nodes.len() - index) as u32;
+        nodes[index] = LinearNode {
+            center_of_mass,
+            center_of_mass_velocity,
+            total_mass,
+            quadrupole,
+            subtree_len,
+            is_leaf: false,
+            ..nodes[index]
+        };
+    }
+
+    fn leaf(&self, range: Range<usize>, bounds: Aabb3d) -> LinearNode {
+        let bodies = &self.bodies[range.clone()];
+
+        let (total_mass, weighted_sum, momentum) = bodies.iter().fold(
+            (0.0, Vector::ZERO, Vector::ZERO),
+            |(mass_acc, pos_acc, momentum_acc), body| {
+                (
+                    mass_acc + body.mass,
+                    pos_acc + body.position * body.mass,
+                    momentum_acc + body.velocity * body.mass,
+                )
+            },
+        );
+        let (center_of_mass, center_of_mass_velocity) = if total_mass > 0.0 {
+            (weighted_sum / total_mass, momentum / total_mass)
+        } else {
+            (bounds.center(), Vector::ZERO)
+        };
+        let quadrupole = match self.multipole_order {
+            MultipoleOrder::Monopole => Matrix::ZERO,
+            MultipoleOrder::Quadrupole => bodies.iter().fold(Matrix::ZERO, |moment, body| {
+                moment + quadrupole_moment(body.position - center_of_mass, body.mass)
+            }),
+        };
+
+        LinearNode {
+            center_of_mass,
+            center_of_mass_velocity,
+            total_mass,
+            quadrupole,
+            ..LinearNode::empty(range, bounds)
+        }
+    }
+}
@@ -7,6 +7,9 @@ pub type Scalar = f64;
 /// 3D vector type for positions, velocities, and forces
 pub type Vector = bevy::math::DVec3;
 
+/// 3×3 matrix type for tensors such as multipole moments
+pub type Matrix = bevy::math::DMat3;
+
 /// Extension trait for Vector operations
 pub trait VectorExt {
     /// Returns component-wise minimum of two vectors
@@ -1,6 +1,12 @@
 pub mod aabb3d;
+pub mod block_timestep;
+pub mod collisions;
 pub mod components;
+pub mod gravity;
 pub mod integrators;
+pub mod kepler;
+pub mod linear_octree;
 pub mod math;
 pub mod octree;
 pub mod resources;
+pub mod timestep;
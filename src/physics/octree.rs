@@ -1,18 +1,166 @@
 use crate::physics::aabb3d::Aabb3d;
-use crate::physics::math::{Scalar, Vector, VectorExt};
+use crate::physics::gravity::{PairForce, Softening};
+use crate::physics::integrators::for_each_chunk;
+use crate::physics::math::{Matrix, Scalar, Vector, VectorExt};
+use bevy::ecs::entity::EntityHashMap;
 use bevy::prelude::Entity;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Maximum depth allowed for the octree to prevent stack overflow
 /// and performance degradation. Depth of 24 provides spatial resolution
 /// down to ~10^-7 of the root node size, which is sufficient for any
 /// realistic simulation while preventing pathological cases.
-const MAX_OCTREE_DEPTH: usize = 24;
+pub(crate) const MAX_OCTREE_DEPTH: usize = 24;
+
+/// Highest order of the multipole expansion used for approximated nodes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MultipoleOrder {
+    /// Each node acts as a point mass at its center of mass
+    #[default]
+    Monopole,
+    /// Each node adds the field of its quadrupole moment about its center of mass
+    Quadrupole,
+}
+
+/// How the nodes of the Barnes-Hut octree are stored and built
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OctreeLayout {
+    /// `Octree`: nodes own their children through boxes recycled by a pool,
+    /// built recursively on one thread
+    #[default]
+    Boxed,
+    /// `LinearOctree`: nodes sit depth-first in a flat arena, built from
+    /// sorted Morton keys in parallel
+    Linear,
+}
+
+/// Test deciding whether a node of the Barnes-Hut octree is far enough from
+/// a body to be approximated by its multipole expansion
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningCriterion {
+    /// Accept a node when s/d < θ, for s the diagonal of its bounds and d
+    /// the distance to its center of mass
+    #[default]
+    Geometric,
+    /// Salmon-Warren criterion: accept a node when bmax/d < θ, for bmax the
+    /// greatest distance from its center of mass to a corner of its bounds.
+    /// Unlike the diagonal, bmax shrinks when the mass sits near the middle
+    /// of the node and grows when it sits near a corner.
+    Bmax,
+    /// GADGET's relative criterion: accept a node when its estimated force
+    /// error G·M·l²/d⁴, for l its longest side, is below α times the body's
+    /// acceleration on the previous step. Nodes containing the body are
+    /// always opened. Bodies without a recorded acceleration fall back to
+    /// `Geometric`.
+    ///
+    /// α bounds the estimate of each accepted node, not the total, and the
+    /// errors of more and more accepted nodes add up as α falls. On a
+    /// 4,000-body Plummer sphere the RMS relative force error falls roughly
+    /// as α^0.7: about 0.35α at α = 0.02, near α at 5·10⁻⁴, and up to 1.2α
+    /// at 2.5·10⁻⁴.
+    RelativeAcceleration,
+}
+
+impl OpeningCriterion {
+    /// The test for one force evaluation on the body of `entity`, whose
+    /// latest acceleration magnitude is looked up in `accelerations`
+    #[inline]
+    pub(crate) fn acceptance(
+        self,
+        theta: Scalar,
+        relative_accuracy: Scalar,
+        accelerations: &EntityHashMap<Scalar>,
+        entity: Entity,
+        g: Scalar,
+    ) -> NodeAcceptance {
+        match self {
+            OpeningCriterion::Geometric => NodeAcceptance::Geometric { theta },
+            OpeningCriterion::Bmax => NodeAcceptance::Bmax { theta },
+            OpeningCriterion::RelativeAcceleration => match accelerations.get(&entity) {
+                Some(&acceleration) => NodeAcceptance::RelativeAcceleration {
+                    g,
+                    tolerance: relative_accuracy * acceleration,
+                },
+                None => NodeAcceptance::Geometric { theta },
+            },
+        }
+    }
+}
+
+/// An `OpeningCriterion` resolved for one force evaluation
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum NodeAcceptance {
+    Geometric { theta: Scalar },
+    Bmax { theta: Scalar },
+    RelativeAcceleration { g: Scalar, tolerance: Scalar },
+}
+
+impl NodeAcceptance {
+    /// Whether a node may be approximated from `position`
+    ///
+    /// # Arguments
+    /// * `bounds` - Bounds of the node
+    /// * `size_squared` - Squared diagonal of `bounds`
+    /// * `distance_squared` - Squared distance from `position` to the node's
+    ///   center of mass
+    #[inline]
+    pub(crate) fn accepts(
+        self,
+        position: Vector,
+        bounds: &Aabb3d,
+        center_of_mass: Vector,
+        total_mass: Scalar,
+        size_squared: Scalar,
+        distance_squared: Scalar,
+    ) -> bool {
+        match self {
+            NodeAcceptance::Geometric { theta } => size_squared < distance_squared * theta * theta,
+            NodeAcceptance::Bmax { theta } => {
+                let farthest_corner =
+                    (center_of_mass - bounds.min).max(bounds.max - center_of_mass);
+                farthest_corner.length_squared() < distance_squared * theta * theta
+            }
+            NodeAcceptance::RelativeAcceleration { g, tolerance } => {
+                let side = (bounds.max - bounds.min).max_element();
+                g * total_mass * side * side < tolerance * distance_squared * distance_squared
+                    && !bounds.contains(position)
+            }
+        }
+    }
+}
+
+/// Smallest share of the root's diagonal the bodies may span before a refit
+/// falls back to a full rebuild
+///
+/// A fresh build pads the bodies by 10% on each side, so they start out
+/// spanning about 0.83 of it. Once a contracting system has shrunk well
+/// inside the root, its top levels no longer separate anything.
+const MIN_REFIT_ROOT_FILL: Scalar = 0.5;
+
+/// Default α of `OpeningCriterion::RelativeAcceleration`, which on a Plummer
+/// sphere gives about the force error of the geometric criterion at θ = 0.5
+/// with a fifth fewer interactions
+pub(crate) const DEFAULT_RELATIVE_ACCURACY: Scalar = 0.0005;
+
+/// What `Octree::refit` did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefitOutcome {
+    /// The tree was kept, with this many bodies moved to a new leaf
+    Refitted { reinserted: usize },
+    /// The tree was rebuilt from scratch
+    Rebuilt,
+}
 
 /// Represents one of the eight octants in 3D space relative to a center point
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Octant {
+pub(crate) enum Octant {
     LeftBottomBack = 0b000,   // x-, y-, z-
     RightBottomBack = 0b001,  // x+, y-, z-
     LeftTopBack = 0b010,      // x-, y+, z-
@@ -37,7 +185,7 @@ impl Octant {
 
     /// Determines which octant a position falls into relative to a center point
     #[inline]
-    fn from_position(position: Vector, center: Vector) -> Self {
+    pub(crate) fn from_position(position: Vector, center: Vector) -> Self {
         let index = ((position.x > center.x) as usize)
             | (((position.y > center.y) as usize) << 1)
             | (((position.z > center.z) as usize) << 2);
@@ -47,12 +195,15 @@ impl Octant {
 
     /// Returns the array index (0-7) for this octant
     #[inline]
-    fn index(self) -> usize {
+    pub(crate) fn index(self) -> usize {
         self as usize
     }
 }
 
-/// A body in the octree with position, mass, and entity identifier
+/// A body in the octree with position, velocity, mass, and entity identifier
+///
+/// The velocity is only used for jerk calculations and may be left at zero
+/// when only forces are needed.
 ///
 /// The Entity field is required for self-exclusion during force calculations.
 /// While position-based exclusion might seem simpler (comparing positions with ==),
@@ -67,6 +218,7 @@ impl Octant {
 #[derive(Debug, Clone, Copy)]
 pub struct OctreeBody {
     pub position: Vector,
+    pub velocity: Vector,
     pub mass: Scalar,
     pub entity: Entity,
 }
@@ -160,7 +312,15 @@ impl OctreeNodePool {
 ///   - 1.0: Maximum approximation (fastest, accuracy sufficient for many visual effects)
 /// * `min_distance` - Minimum distance for force calculations to prevent singularities
 /// * `max_force` - Maximum force magnitude to maintain numerical stability
+/// * `softening` - Kernel keeping close forces finite; `min_distance` is its
+///   softening length, and only the legacy clamp applies `max_force`
 /// * `leaf_threshold` - Maximum bodies per leaf node before subdivision
+/// * `multipole_order` - Whether approximated nodes act as point masses or
+///   also carry their quadrupole moment
+/// * `refit_max_reinserted` - With refitting enabled, the share of bodies that
+///   may move to a new leaf between full rebuilds
+/// * `opening_criterion` - Which test decides that a node is far enough away
+///   to approximate, using `theta` or `relative_accuracy`
 ///
 /// # Performance Characteristics
 ///
@@ -170,13 +330,20 @@ impl OctreeNodePool {
 #[derive(Debug)]
 pub struct Octree {
     pub root: Option<OctreeNode>,
-    pub theta: Scalar,                  // Barnes-Hut approximation parameter
-    pub min_distance: Scalar,           // Minimum distance for force calculation
-    pub max_force: Scalar,              // Maximum force magnitude
-    pub leaf_threshold: usize,          // Maximum bodies per leaf node
-    min_distance_squared: Scalar,       // Cached value to avoid repeated multiplication
-    node_pool: OctreeNodePool,          // Pool for reusing node allocations
-    force_calculation_count: AtomicU64, // Counter for force calculations performed
+    pub theta: Scalar,                        // Barnes-Hut approximation parameter
+    pub min_distance: Scalar,                 // Minimum distance for force calculation
+    pub max_force: Scalar,                    // Maximum force magnitude
+    pub softening: Softening,                 // Softening kernel of the pair force
+    pub leaf_threshold: usize,                // Maximum bodies per leaf node
+    pub multipole_order: MultipoleOrder,      // Expansion order of approximated nodes
+    pub refit_max_reinserted: Option<Scalar>, // Refit threshold, or None to always rebuild
+    pub opening_criterion: OpeningCriterion,  // Test for approximating a node
+    pub relative_accuracy: Scalar,            // α of the relative acceleration criterion
+    node_pool: OctreeNodePool,                // Pool for reusing node allocations
+    force_calculation_count: AtomicU64,       // Counter for force calculations performed
+    body_count: usize,                        // Bodies in the tree
+    reinserted_since_rebuild: usize,          // Bodies moved to a new leaf since the last build
+    accelerations: EntityHashMap<Scalar>,     // Latest acceleration magnitude of each body
 }
 
 impl Octree {
@@ -189,7 +356,7 @@ impl Octree {
     ///   - 0.5 for good accuracy/performance balance (recommended)
     ///   - 1.0 for maximum speed with acceptable visual accuracy
     /// * `min_distance` - Minimum distance between bodies to prevent force singularities.
-    ///                    Forces are calculated as if bodies are at least this far apart.
+    ///   Forces are calculated as if bodies are at least this far apart.
     /// * `max_force` - Maximum allowed force magnitude. Forces exceeding this are clamped.
     ///
     /// # Example
@@ -205,10 +372,17 @@ impl Octree {
             theta,
             min_distance,
             max_force,
+            softening: Softening::Clamp,
             leaf_threshold: 4,
-            min_distance_squared: min_distance * min_distance,
+            multipole_order: MultipoleOrder::Monopole,
+            refit_max_reinserted: None,
+            opening_criterion: OpeningCriterion::Geometric,
+            relative_accuracy: DEFAULT_RELATIVE_ACCURACY,
             node_pool: OctreeNodePool::new(),
             force_calculation_count: AtomicU64::new(0),
+            body_count: 0,
+            reinserted_since_rebuild: 0,
+            accelerations: EntityHashMap::default(),
         }
     }
 
@@ -227,6 +401,90 @@ impl Octree {
         self
     }
 
+    /// Sets the kernel that softens the force between close bodies, with
+    /// `min_distance` as its softening length.
+    ///
+    /// Default is `Softening::Clamp`, the legacy clamp on distance and force.
+    pub fn with_softening(mut self, softening: Softening) -> Self {
+        self.softening = softening;
+        self
+    }
+
+    /// Sets the order of the multipole expansion used for approximated nodes.
+    ///
+    /// Quadrupole moments cost a little more to build and evaluate, but cut the
+    /// force error of each approximated node from O(θ²) to O(θ³), so the same
+    /// accuracy is reached at a larger theta.
+    ///
+    /// Default is `MultipoleOrder::Monopole`.
+    pub fn with_multipole_order(mut self, multipole_order: MultipoleOrder) -> Self {
+        self.multipole_order = multipole_order;
+        self
+    }
+
+    /// Refits the tree to moved bodies instead of rebuilding it when used as a
+    /// `GravitySolver`.
+    ///
+    /// Refitting pays off because bodies move very little per step. It falls
+    /// back to a full rebuild once more than `max_reinserted` of the bodies
+    /// have moved to a new leaf since the last full build; see `refit`.
+    ///
+    /// Default is to rebuild every time.
+    pub fn with_refit(mut self, max_reinserted: Scalar) -> Self {
+        self.refit_max_reinserted = Some(max_reinserted);
+        self
+    }
+
+    /// Sets the test that decides whether a node is far enough from a body to
+    /// be approximated.
+    ///
+    /// `OpeningCriterion::RelativeAcceleration` compares each node's estimated
+    /// error against `relative_accuracy` times the body's acceleration from
+    /// `record_accelerations`.
+    ///
+    /// Default is `OpeningCriterion::Geometric`.
+    pub fn with_opening_criterion(mut self, opening_criterion: OpeningCriterion) -> Self {
+        self.opening_criterion = opening_criterion;
+        self
+    }
+
+    /// Sets α, the largest force error the relative acceleration criterion
+    /// accepts from one node as a fraction of the body's acceleration.
+    ///
+    /// Default is 0.0005.
+    pub fn with_relative_accuracy(mut self, relative_accuracy: Scalar) -> Self {
+        self.relative_accuracy = relative_accuracy;
+        self
+    }
+
+    /// Records the acceleration of each body, for the relative acceleration
+    /// criterion of later force calculations.
+    ///
+    /// The accelerations are kept by entity across builds until the next
+    /// call, and are ignored by the other criteria.
+    ///
+    /// # Arguments
+    ///
+    /// * `bodies` - The bodies the accelerations belong to
+    /// * `accelerations` - Acceleration of each of `bodies`
+    pub fn record_accelerations(&mut self, bodies: &[OctreeBody], accelerations: &[Vector]) {
+        record_accelerations(
+            &mut self.accelerations,
+            self.opening_criterion,
+            bodies,
+            accelerations,
+        );
+    }
+
+    /// Returns the number of body and node interactions evaluated since the
+    /// octree was created.
+    ///
+    /// The count measures the cost of force calculations independently of
+    /// the machine running them.
+    pub fn force_calculation_count(&self) -> u64 {
+        self.force_calculation_count.load(Ordering::Relaxed)
+    }
+
     /// Returns the bounding boxes of all nodes in the octree.
     ///
     /// Useful for visualization and debugging purposes to see the spatial subdivision.
@@ -301,19 +559,108 @@ impl Octree {
         let padded_min = min - padding;
         let padded_max = max + padding;
         let bounds = Aabb3d::new(padded_min, padded_max);
+        self.body_count = bodies_vec.len();
+        self.reinserted_since_rebuild = 0;
         self.root = Some(Self::build_node(
             bounds,
             bodies_vec,
             self.leaf_threshold,
+            self.multipole_order,
             &mut self.node_pool,
             0, // Start at depth 0
         ));
     }
 
+    /// Updates the tree to the new state of the bodies it was built from.
+    ///
+    /// Rather than rebuilding, the existing cells are kept: each body's
+    /// state is updated in its leaf, bodies that left their leaf's bounds are
+    /// reinserted from the root, emptied nodes are pruned, and the masses,
+    /// centers of mass, and quadrupole moments are recomputed bottom-up.
+    /// Leaves that overflow are subdivided as in `build`.
+    ///
+    /// Falls back to `build` when the tree no longer fits the bodies: when
+    /// bodies were added or removed, a body left the root bounds, the bodies
+    /// have contracted to span less than half of the root, or more than the
+    /// `refit_max_reinserted` share of the bodies (25% if unset) has moved to
+    /// a new leaf since the last full build.
+    ///
+    /// # Performance
+    ///
+    /// O(N) plus O(log N) per reinserted body, against O(N log N) for
+    /// `build`.
+    pub fn refit(&mut self, bodies: &[OctreeBody]) -> RefitOutcome {
+        let Some(root) = self.root.as_mut() else {
+            self.build(bodies.iter().copied());
+            return RefitOutcome::Rebuilt;
+        };
+
+        let root_bounds = root.bounds();
+        let (min, max) = bodies.iter().fold(
+            (
+                Vector::splat(Scalar::INFINITY),
+                Vector::splat(Scalar::NEG_INFINITY),
+            ),
+            |(min, max), body| {
+                (
+                    min.component_min(body.position),
+                    max.component_max(body.position),
+                )
+            },
+        );
+        let fits = bodies.len() == self.body_count
+            && min.cmpge(root_bounds.min).all()
+            && max.cmple(root_bounds.max).all()
+            && min.distance_squared(max)
+                >= root_bounds.min.distance_squared(root_bounds.max)
+                    * MIN_REFIT_ROOT_FILL
+                    * MIN_REFIT_ROOT_FILL;
+        if !fits {
+            self.build(bodies.iter().copied());
+            return RefitOutcome::Rebuilt;
+        }
+
+        let indices: EntityHashMap<usize> = bodies
+            .iter()
+            .enumerate()
+            .map(|(index, body)| (body.entity, index))
+            .collect();
+
+        let mut escaped = Vec::new();
+        if !root.update_bodies(bodies, &indices, &mut escaped, &mut self.node_pool) {
+            self.build(bodies.iter().copied());
+            return RefitOutcome::Rebuilt;
+        }
+
+        let max_reinserted = self.refit_max_reinserted.unwrap_or(0.25);
+        let reinserted = self.reinserted_since_rebuild + escaped.len();
+        if reinserted as Scalar > max_reinserted * bodies.len() as Scalar {
+            self.build(bodies.iter().copied());
+            return RefitOutcome::Rebuilt;
+        }
+
+        for body in &escaped {
+            root.insert(
+                *body,
+                self.leaf_threshold,
+                self.multipole_order,
+                &mut self.node_pool,
+                0,
+            );
+        }
+        root.update_moments(self.multipole_order);
+        self.reinserted_since_rebuild = reinserted;
+
+        RefitOutcome::Refitted {
+            reinserted: escaped.len(),
+        }
+    }
+
     fn build_node(
         bounds: Aabb3d,
         bodies: Vec<OctreeBody>,
         leaf_threshold: usize,
+        multipole_order: MultipoleOrder,
         pool: &mut OctreeNodePool,
         depth: usize,
     ) -> OctreeNode {
@@ -371,6 +718,7 @@ impl Octree {
                         octants[i],
                         bodies_in_octant,
                         leaf_threshold,
+                        multipole_order,
                         pool,
                         depth + 1, // Increment depth for child nodes
                     )));
@@ -381,18 +729,33 @@ impl Octree {
 
         // Calculate aggregate properties for Barnes-Hut approximation
         // This allows treating this entire node as a single point mass when viewed from far away
-        let (total_mass, weighted_sum) = bodies
-            .iter()
-            .fold((0.0, Vector::ZERO), |(mass_acc, pos_acc), body| {
-                (mass_acc + body.mass, pos_acc + body.position * body.mass)
-            });
+        let (total_mass, weighted_sum, momentum) = bodies.iter().fold(
+            (0.0, Vector::ZERO, Vector::ZERO),
+            |(mass_acc, pos_acc, momentum_acc), body| {
+                (
+                    mass_acc + body.mass,
+                    pos_acc + body.position * body.mass,
+                    momentum_acc + body.velocity * body.mass,
+                )
+            },
+        );
 
-        // Center of mass is the weighted average position
+        // Center of mass is the weighted average position, and it moves with
+        // the weighted average velocity
         // Handle edge case of zero total mass (shouldn't happen in practice)
-        let center_of_mass = if total_mass > 0.0 {
-            weighted_sum / total_mass
+        let (center_of_mass, center_of_mass_velocity) = if total_mass > 0.0 {
+            (weighted_sum / total_mass, momentum / total_mass)
         } else {
-            bounds.center()
+            (bounds.center(), Vector::ZERO)
+        };
+
+        // Traceless quadrupole moment about the center of mass,
+        // Q = Σ m·(3·x·xᵀ - |x|²·I) for x the offset of each body
+        let quadrupole = match multipole_order {
+            MultipoleOrder::Monopole => Matrix::ZERO,
+            MultipoleOrder::Quadrupole => bodies.iter().fold(Matrix::ZERO, |moment, body| {
+                moment + quadrupole_moment(body.position - center_of_mass, body.mass)
+            }),
         };
 
         // Debug assertions to verify invariants
@@ -431,11 +794,19 @@ impl Octree {
         OctreeNode::Internal {
             bounds,
             center_of_mass,
+            center_of_mass_velocity,
             total_mass,
+            quadrupole,
             children,
         }
     }
 
+    /// Softened pair force shared with the other gravity solvers
+    #[inline]
+    fn pair_force(&self) -> PairForce {
+        PairForce::new(self.min_distance, self.max_force).with_softening(self.softening)
+    }
+
     #[inline]
     fn calculate_force_from_point(
         &self,
@@ -444,21 +815,76 @@ impl Octree {
         point_mass: Scalar,
         g: Scalar,
     ) -> Vector {
-        let direction = point_position - body.position;
-        let distance_squared = direction.length_squared();
+        self.force_calculation_count.fetch_add(1, Ordering::Relaxed);
 
-        // Clamp distance to minimum to prevent singularities
-        // This ensures forces remain finite but don't vanish
-        let clamped_distance_squared = distance_squared.max(self.min_distance_squared);
+        self.pair_force()
+            .force(point_position - body.position, g * body.mass * point_mass)
+    }
 
-        self.force_calculation_count.fetch_add(1, Ordering::Relaxed);
+    /// Force on `body` from the quadrupole moment of a node, on top of the
+    /// node's monopole force; see `quadrupole_force`
+    #[inline]
+    fn calculate_quadrupole_force(
+        &self,
+        body: &OctreeBody,
+        center_of_mass: Vector,
+        total_mass: Scalar,
+        quadrupole: &Matrix,
+        g: Scalar,
+    ) -> Vector {
+        quadrupole_force(
+            &self.pair_force(),
+            body.position - center_of_mass,
+            body.mass,
+            total_mass,
+            quadrupole,
+            g,
+        )
+    }
 
-        let distance = clamped_distance_squared.sqrt();
-        let direction_normalized = direction / distance;
-        let force_magnitude = g * body.mass * point_mass / clamped_distance_squared;
-        let force_magnitude = force_magnitude.min(self.max_force);
+    /// Force on `body` from the quadrupole moment of a node and the time
+    /// derivative of that force; see `quadrupole_force_and_jerk`
+    #[inline]
+    fn calculate_quadrupole_force_and_jerk(
+        &self,
+        body: &OctreeBody,
+        center_of_mass: Vector,
+        center_of_mass_velocity: Vector,
+        total_mass: Scalar,
+        quadrupole: &Matrix,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        quadrupole_force_and_jerk(
+            &self.pair_force(),
+            body.position - center_of_mass,
+            body.velocity - center_of_mass_velocity,
+            body.mass,
+            total_mass,
+            quadrupole,
+            g,
+        )
+    }
 
-        direction_normalized * force_magnitude
+    /// Force on `body` from a point mass and the time derivative of that force
+    ///
+    /// The force matches `calculate_force_from_point`; see
+    /// `PairForce::force_and_jerk` for the derivative.
+    #[inline]
+    fn calculate_force_and_jerk_from_point(
+        &self,
+        body: &OctreeBody,
+        point_position: Vector,
+        point_velocity: Vector,
+        point_mass: Scalar,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        self.force_calculation_count.fetch_add(1, Ordering::Relaxed);
+
+        self.pair_force().force_and_jerk(
+            point_position - body.position,
+            point_velocity - body.velocity,
+            g * body.mass * point_mass,
+        )
     }
 
     /// Calculate force at an arbitrary position, excluding a specific entity.
@@ -470,7 +896,9 @@ impl Octree {
     /// # Barnes-Hut Algorithm
     ///
     /// For each node, if s/d < theta (where s is node size and d is distance):
-    /// - Treat the node as a single point mass at its center of mass
+    /// - Treat the node as a single point mass at its center of mass, plus its
+    ///   quadrupole moment when `multipole_order` is `Quadrupole`
+    ///
     /// Otherwise:
     /// - Recursively calculate forces from child nodes
     ///
@@ -498,10 +926,225 @@ impl Octree {
     ) -> Vector {
         let temp_body = OctreeBody {
             position,
+            velocity: Vector::ZERO,
             mass,
             entity: exclude_entity,
         };
-        self.traverse_tree_for_force(&temp_body, self.root.as_ref(), g)
+        let acceptance = self.acceptance(exclude_entity, g);
+        self.traverse_tree_for_force(&temp_body, self.root.as_ref(), acceptance, g)
+    }
+
+    /// Calculate force and its time derivative at an arbitrary position and velocity,
+    /// excluding a specific entity.
+    ///
+    /// Uses the same Barnes-Hut traversal as `calculate_force_at_position`.
+    /// Leaf bodies contribute the exact derivative of their pairwise force from
+    /// their stored velocities, and approximated nodes contribute the
+    /// derivative of their monopole force, and of their quadrupole force
+    /// when `multipole_order` is `Quadrupole`, moving with the node's
+    /// center-of-mass velocity. The force matches
+    /// `calculate_force_at_position`. Dividing both results by `mass` gives
+    /// the acceleration and jerk used by Hermite integrators.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position at which to evaluate the force
+    /// * `velocity` - The velocity of the body at that position
+    /// * `mass` - The mass of the body for which force is being calculated
+    /// * `exclude_entity` - Entity to exclude from force calculation (typically the body itself)
+    /// * `g` - Gravitational constant
+    ///
+    /// # Returns
+    ///
+    /// The total force vector and its time derivative.
+    pub fn calculate_force_and_jerk_at_position(
+        &self,
+        position: Vector,
+        velocity: Vector,
+        mass: Scalar,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        let temp_body = OctreeBody {
+            position,
+            velocity,
+            mass,
+            entity: exclude_entity,
+        };
+        let acceptance = self.acceptance(exclude_entity, g);
+        self.traverse_tree_for_force_and_jerk(&temp_body, self.root.as_ref(), acceptance, g)
+    }
+
+    /// Gravitational potential at an arbitrary position, excluding a specific
+    /// entity.
+    ///
+    /// Walks the tree with the same opening criterion as
+    /// `calculate_force_at_position` and sums `PairForce::potential` over the
+    /// bodies and approximated nodes it reaches, plus each node's quadrupole
+    /// term when `multipole_order` is `Quadrupole`. The result is the
+    /// potential energy per unit mass of a body at `position`, whose negative
+    /// gradient is the force per unit mass.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position at which to evaluate the potential
+    /// * `exclude_entity` - Entity to exclude from the sum (typically the body itself)
+    /// * `g` - Gravitational constant
+    pub fn calculate_potential_at_position(
+        &self,
+        position: Vector,
+        exclude_entity: Entity,
+        g: Scalar,
+    ) -> Scalar {
+        let acceptance = self.acceptance(exclude_entity, g);
+        self.traverse_tree_for_potential(
+            position,
+            exclude_entity,
+            self.root.as_ref(),
+            acceptance,
+            g,
+        )
+    }
+
+    /// Total gravitational potential energy of `bodies`, which must be the
+    /// bodies the tree was built from
+    ///
+    /// Sums ½·m·Φ over the bodies, with Φ from
+    /// `calculate_potential_at_position`, so that each pair is counted once.
+    /// The bodies are evaluated in parallel on the compute task pool.
+    pub fn potential_energy(&self, bodies: &[OctreeBody], g: Scalar) -> Scalar {
+        let mut energies = vec![0.0; bodies.len()];
+        for_each_chunk(&mut energies, |offset, chunk| {
+            for (energy, body) in chunk.iter_mut().zip(&bodies[offset..]) {
+                *energy = 0.5
+                    * body.mass
+                    * self.calculate_potential_at_position(body.position, body.entity, g);
+            }
+        });
+        energies.iter().sum()
+    }
+
+    /// Calls `visit` with every body within `radius` of `center`.
+    ///
+    /// Subtrees whose bounds lie entirely farther away than `radius` are
+    /// skipped, so the cost grows with the number of nearby bodies rather
+    /// than with the size of the tree.
+    pub fn for_each_body_within(
+        &self,
+        center: Vector,
+        radius: Scalar,
+        mut visit: impl FnMut(&OctreeBody),
+    ) {
+        if let Some(root) = &self.root {
+            root.visit_bodies_within(center, radius * radius, &mut visit);
+        }
+    }
+
+    /// Every body within `radius` of `center` and its distance, nearest
+    /// first.
+    ///
+    /// Prunes subtrees by their bounds as `for_each_body_within` does.
+    pub fn bodies_within(&self, center: Vector, radius: Scalar) -> Vec<(Entity, Scalar)> {
+        let mut found = Vec::new();
+        self.for_each_body_within(center, radius, |body| {
+            found.push((body.entity, body.position.distance(center)));
+        });
+        found.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        found
+    }
+
+    /// The `k` bodies nearest to `position` and their distances, nearest
+    /// first.
+    ///
+    /// Fewer are returned if the tree holds fewer bodies. A body at
+    /// `position` itself is included at distance zero, so ask for one more
+    /// to find the neighbours of a body in the tree.
+    ///
+    /// # Algorithm
+    ///
+    /// Nodes are visited in order of the distance from `position` to their
+    /// bounds, and the search stops once the nearest unvisited node is
+    /// farther away than the `k`-th nearest body found so far. The cost
+    /// grows with `k` and the depth of the tree rather than with its size.
+    pub fn nearest_neighbors(&self, position: Vector, k: usize) -> Vec<(Entity, Scalar)> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // Nodes still to visit, nearest on top, and the nearest bodies found
+        // so far, farthest on top
+        let mut nodes = BinaryHeap::from([Reverse(ByDistance {
+            distance_squared: root.bounds().distance_squared_to(position),
+            item: root,
+        })]);
+        let mut nearest: BinaryHeap<ByDistance<Entity>> = BinaryHeap::with_capacity(k + 1);
+        let kth_distance_squared = |nearest: &BinaryHeap<ByDistance<Entity>>| {
+            if nearest.len() < k {
+                Scalar::INFINITY
+            } else {
+                nearest
+                    .peek()
+                    .map_or(Scalar::INFINITY, |kth| kth.distance_squared)
+            }
+        };
+
+        while let Some(Reverse(ByDistance {
+            distance_squared,
+            item: node,
+        })) = nodes.pop()
+        {
+            if distance_squared > kth_distance_squared(&nearest) {
+                break;
+            }
+
+            match node {
+                OctreeNode::Internal { children, .. } => {
+                    for child in children.iter().flatten() {
+                        let distance_squared = child.bounds().distance_squared_to(position);
+                        if distance_squared <= kth_distance_squared(&nearest) {
+                            nodes.push(Reverse(ByDistance {
+                                distance_squared,
+                                item: &**child,
+                            }));
+                        }
+                    }
+                }
+                OctreeNode::External { bodies, .. } => {
+                    for body in bodies {
+                        let distance_squared = body.position.distance_squared(position);
+                        if distance_squared < kth_distance_squared(&nearest) {
+                            nearest.push(ByDistance {
+                                distance_squared,
+                                item: body.entity,
+                            });
+                            if nearest.len() > k {
+                                nearest.pop();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        nearest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|found| (found.item, found.distance_squared.sqrt()))
+            .collect()
+    }
+
+    /// The opening criterion for a force calculation on `entity`
+    fn acceptance(&self, entity: Entity, g: Scalar) -> NodeAcceptance {
+        self.opening_criterion.acceptance(
+            self.theta,
+            self.relative_accuracy,
+            &self.accelerations,
+            entity,
+            g,
+        )
     }
 
     /// Recursively traverses the octree to calculate forces using Barnes-Hut approximation.
@@ -513,20 +1156,25 @@ impl Octree {
     ///
     /// # Barnes-Hut Criterion
     ///
-    /// A node can be treated as a single mass if: s/d < theta
+    /// With the default geometric criterion a node can be treated as a single
+    /// mass if: s/d < theta
     /// - s = size of the node (diagonal of bounding box)
     /// - d = distance from the body to the node's center of mass
     /// - theta = accuracy parameter (0 = exact, larger = more approximation)
     ///
+    /// The other criteria are described on `OpeningCriterion`.
+    ///
     /// # Arguments
     ///
     /// * `body` - The body for which we're calculating forces
     /// * `node` - Current node being evaluated
+    /// * `acceptance` - Opening criterion for this body
     /// * `g` - Gravitational constant
     fn traverse_tree_for_force(
         &self,
         body: &OctreeBody,
         node: Option<&OctreeNode>,
+        acceptance: NodeAcceptance,
         g: Scalar,
     ) -> Vector {
         match node {
@@ -534,6 +1182,7 @@ impl Octree {
                 bounds,
                 center_of_mass,
                 total_mass,
+                quadrupole,
                 children,
                 ..
             }) => {
@@ -545,13 +1194,38 @@ impl Octree {
 
                 // Barnes-Hut criterion: if s/d < theta, treat as single body
                 // This is the key optimization - distant groups of bodies are treated as one
-                if size_squared < distance_squared * self.theta * self.theta {
-                    self.calculate_force_from_point(body, *center_of_mass, *total_mass, g)
+                if acceptance.accepts(
+                    body.position,
+                    bounds,
+                    *center_of_mass,
+                    *total_mass,
+                    size_squared,
+                    distance_squared,
+                ) {
+                    let force =
+                        self.calculate_force_from_point(body, *center_of_mass, *total_mass, g);
+                    match self.multipole_order {
+                        MultipoleOrder::Monopole => force,
+                        MultipoleOrder::Quadrupole => {
+                            force
+                                + self.calculate_quadrupole_force(
+                                    body,
+                                    *center_of_mass,
+                                    *total_mass,
+                                    quadrupole,
+                                    g,
+                                )
+                        }
+                    }
                 } else {
                     let mut force = Vector::ZERO;
                     children.iter().for_each(|child| {
-                        force +=
-                            self.traverse_tree_for_force(body, child.as_ref().map(|v| &**v), g);
+                        force += self.traverse_tree_for_force(
+                            body,
+                            child.as_ref().map(|v| &**v),
+                            acceptance,
+                            g,
+                        );
                     });
                     force
                 }
@@ -574,6 +1248,315 @@ impl Octree {
             None => Vector::ZERO,
         }
     }
+
+    /// Recursively traverses the octree to sum the potential at `position`,
+    /// using the same opening criterion as `traverse_tree_for_force`.
+    fn traverse_tree_for_potential(
+        &self,
+        position: Vector,
+        exclude_entity: Entity,
+        node: Option<&OctreeNode>,
+        acceptance: NodeAcceptance,
+        g: Scalar,
+    ) -> Scalar {
+        let pair_force = self.pair_force();
+
+        match node {
+            Some(OctreeNode::Internal {
+                bounds,
+                center_of_mass,
+                total_mass,
+                quadrupole,
+                children,
+                ..
+            }) => {
+                let distance_squared = position.distance_squared(*center_of_mass);
+                let size_squared = bounds.min.distance_squared(bounds.max);
+
+                if acceptance.accepts(
+                    position,
+                    bounds,
+                    *center_of_mass,
+                    *total_mass,
+                    size_squared,
+                    distance_squared,
+                ) {
+                    let potential = pair_force.potential(distance_squared.sqrt(), g * *total_mass);
+                    match self.multipole_order {
+                        MultipoleOrder::Monopole => potential,
+                        MultipoleOrder::Quadrupole => {
+                            potential
+                                + quadrupole_potential(
+                                    &pair_force,
+                                    position - *center_of_mass,
+                                    quadrupole,
+                                    g,
+                                )
+                        }
+                    }
+                } else {
+                    children
+                        .iter()
+                        .map(|child| {
+                            self.traverse_tree_for_potential(
+                                position,
+                                exclude_entity,
+                                child.as_ref().map(|v| &**v),
+                                acceptance,
+                                g,
+                            )
+                        })
+                        .sum()
+                }
+            }
+            Some(OctreeNode::External { bodies, .. }) => bodies
+                .iter()
+                .filter(|other_body| other_body.entity != exclude_entity)
+                .map(|other_body| {
+                    pair_force
+                        .potential(position.distance(other_body.position), g * other_body.mass)
+                })
+                .sum(),
+            None => 0.0,
+        }
+    }
+
+    /// Recursively traverses the octree to calculate forces and their time
+    /// derivatives, using the same opening criterion as `traverse_tree_for_force`.
+    fn traverse_tree_for_force_and_jerk(
+        &self,
+        body: &OctreeBody,
+        node: Option<&OctreeNode>,
+        acceptance: NodeAcceptance,
+        g: Scalar,
+    ) -> (Vector, Vector) {
+        match node {
+            Some(OctreeNode::Internal {
+                bounds,
+                center_of_mass,
+                center_of_mass_velocity,
+                total_mass,
+                quadrupole,
+                children,
+                ..
+            }) => {
+                let distance_squared = body.position.distance_squared(*center_of_mass);
+                let size_squared = bounds.min.distance_squared(bounds.max);
+
+                if acceptance.accepts(
+                    body.position,
+                    bounds,
+                    *center_of_mass,
+                    *total_mass,
+                    size_squared,
+                    distance_squared,
+                ) {
+                    let (force, jerk) = self.calculate_force_and_jerk_from_point(
+                        body,
+                        *center_of_mass,
+                        *center_of_mass_velocity,
+                        *total_mass,
+                        g,
+                    );
+                    match self.multipole_order {
+                        MultipoleOrder::Monopole => (force, jerk),
+                        MultipoleOrder::Quadrupole => {
+                            let (quadrupole_force, quadrupole_jerk) = self
+                                .calculate_quadrupole_force_and_jerk(
+                                    body,
+                                    *center_of_mass,
+                                    *center_of_mass_velocity,
+                                    *total_mass,
+                                    quadrupole,
+                                    g,
+                                );
+                            (force + quadrupole_force, jerk + quadrupole_jerk)
+                        }
+                    }
+                } else {
+                    children
+                        .iter()
+                        .fold((Vector::ZERO, Vector::ZERO), |(force, jerk), child| {
+                            let (child_force, child_jerk) = self.traverse_tree_for_force_and_jerk(
+                                body,
+                                child.as_ref().map(|v| &**v),
+                                acceptance,
+                                g,
+                            );
+                            (force + child_force, jerk + child_jerk)
+                        })
+                }
+            }
+            Some(OctreeNode::External { bodies, .. }) => bodies
+                .iter()
+                .filter(|other_body| other_body.entity != body.entity)
+                .fold((Vector::ZERO, Vector::ZERO), |(force, jerk), other_body| {
+                    let (body_force, body_jerk) = self.calculate_force_and_jerk_from_point(
+                        body,
+                        other_body.position,
+                        other_body.velocity,
+                        other_body.mass,
+                        g,
+                    );
+                    (force + body_force, jerk + body_jerk)
+                }),
+            None => (Vector::ZERO, Vector::ZERO),
+        }
+    }
+}
+
+/// Replaces `accelerations` with the acceleration magnitude of each of
+/// `bodies`, if `opening_criterion` uses them
+pub(crate) fn record_accelerations(
+    accelerations: &mut EntityHashMap<Scalar>,
+    opening_criterion: OpeningCriterion,
+    bodies: &[OctreeBody],
+    new_accelerations: &[Vector],
+) {
+    debug_assert_eq!(bodies.len(), new_accelerations.len());
+
+    accelerations.clear();
+    if opening_criterion == OpeningCriterion::RelativeAcceleration {
+        accelerations.extend(
+            bodies
+                .iter()
+                .zip(new_accelerations)
+                .map(|(body, acceleration)| (body.entity, acceleration.length())),
+        );
+    }
+}
+
+/// A node or body ordered by its squared distance from a query position
+struct ByDistance<T> {
+    distance_squared: Scalar,
+    item: T,
+}
+
+impl<T> PartialEq for ByDistance<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance_squared.total_cmp(&other.distance_squared) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<T> Eq for ByDistance<T> {}
+
+impl<T> PartialOrd for ByDistance<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for ByDistance<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.distance_squared.total_cmp(&other.distance_squared)
+    }
+}
+
+/// Traceless quadrupole moment m·(3·x·xᵀ - |x|²·I) of a point of `mass` at
+/// `offset` from the expansion center
+#[inline]
+pub(crate) fn quadrupole_moment(offset: Vector, mass: Scalar) -> Matrix {
+    (Matrix::from_cols(offset * offset.x, offset * offset.y, offset * offset.z) * 3.0
+        - Matrix::from_diagonal(Vector::splat(offset.length_squared())))
+        * mass
+}
+
+/// Force on a body of `mass` from the quadrupole moment of a node, on top of
+/// the node's monopole force
+///
+/// For r the offset of the body from the node's center of mass, the
+/// quadrupole potential Φ = -G·(rᵀ·Q·r)/(2r⁵) gives the force
+/// F = G·m·(Q·r/r⁵ - 5(rᵀ·Q·r)·r/(2r⁷)). Inside the softening core, or
+/// where the clamp caps the force, the expansion does not hold, and the node
+/// is left as a monopole.
+#[inline]
+pub(crate) fn quadrupole_force(
+    pair_force: &PairForce,
+    offset: Vector,
+    mass: Scalar,
+    total_mass: Scalar,
+    quadrupole: &Matrix,
+    g: Scalar,
+) -> Vector {
+    let distance_squared = offset.length_squared();
+
+    if !pair_force.is_outside_softening(distance_squared, g * mass * total_mass) {
+        return Vector::ZERO;
+    }
+
+    let distance = distance_squared.sqrt();
+    let quadrupole_offset = *quadrupole * offset;
+    let projection = offset.dot(quadrupole_offset);
+
+    (quadrupole_offset - offset * (2.5 * projection / distance_squared))
+        * (g * mass / (distance_squared * distance_squared * distance))
+}
+
+/// Force on a body from the quadrupole moment of a node, as in
+/// `quadrupole_force`, and the time derivative of that force
+///
+/// With v the velocity of the body relative to the node's center of mass
+/// and p = rᵀ·Q·r, the derivative is
+/// G·m·(Q·v/r⁵ - 5(r·v)·Q·r/r⁷ - 5(2(vᵀ·Q·r)·r + p·v - 7p(r·v)·r/r²)/(2r⁷)).
+/// Q is held fixed: its change as the node's bodies move about their center
+/// of mass is left out, as their individual motion is for the monopole.
+#[inline]
+pub(crate) fn quadrupole_force_and_jerk(
+    pair_force: &PairForce,
+    offset: Vector,
+    relative_velocity: Vector,
+    mass: Scalar,
+    total_mass: Scalar,
+    quadrupole: &Matrix,
+    g: Scalar,
+) -> (Vector, Vector) {
+    let distance_squared = offset.length_squared();
+
+    if !pair_force.is_outside_softening(distance_squared, g * mass * total_mass) {
+        return (Vector::ZERO, Vector::ZERO);
+    }
+
+    let distance = distance_squared.sqrt();
+    let scale = g * mass / (distance_squared * distance_squared * distance);
+    let quadrupole_offset = *quadrupole * offset;
+    let projection = offset.dot(quadrupole_offset);
+    let radial_velocity = offset.dot(relative_velocity);
+
+    let force = (quadrupole_offset - offset * (2.5 * projection / distance_squared)) * scale;
+    let jerk = (*quadrupole * relative_velocity
+        - quadrupole_offset * (5.0 * radial_velocity / distance_squared)
+        - (offset * (2.0 * relative_velocity.dot(quadrupole_offset))
+            + relative_velocity * projection
+            - offset * (7.0 * projection * radial_velocity / distance_squared))
+            * (2.5 / distance_squared))
+        * scale;
+
+    (force, jerk)
+}
+
+/// Potential per unit mass from the quadrupole moment of a node, on top of
+/// the node's monopole potential
+///
+/// For r the offset of the position from the node's center of mass, this is
+/// Φ = -G·(rᵀ·Q·r)/(2r⁵), the potential of `quadrupole_force`. Like
+/// `PairForce::potential` it ignores the force cap of the clamp, so it is
+/// left out only inside the softening core.
+#[inline]
+pub(crate) fn quadrupole_potential(
+    pair_force: &PairForce,
+    offset: Vector,
+    quadrupole: &Matrix,
+    g: Scalar,
+) -> Scalar {
+    let distance_squared = offset.length_squared();
+
+    // No strength reaches the force cap
+    if !pair_force.is_outside_softening(distance_squared, 0.0) {
+        return 0.0;
+    }
+
+    let distance = distance_squared.sqrt();
+    -g * offset.dot(*quadrupole * offset) / (2.0 * distance_squared * distance_squared * distance)
 }
 
 /// Represents a node in the octree, which can be either internal or external (leaf).
@@ -584,7 +1567,8 @@ impl Octree {
 /// # Node Types
 ///
 /// * `Internal` - A node that subdivides space into 8 octants
-///   - Contains aggregated mass and center of mass for Barnes-Hut approximation
+///   - Contains aggregated mass and center of mass for Barnes-Hut approximation,
+///     and the quadrupole moment when the tree is built with `MultipoleOrder::Quadrupole`
 ///   - Has up to 8 children (one per octant, may be None if octant is empty)
 ///
 /// * `External` - A leaf node containing actual bodies
@@ -596,7 +1580,9 @@ pub enum OctreeNode {
     Internal {
         bounds: Aabb3d,                         // Spatial bounds of this node
         center_of_mass: Vector,                 // Weighted average position of all contained bodies
+        center_of_mass_velocity: Vector,        // Weighted average velocity of all contained bodies
         total_mass: Scalar,                     // Sum of all contained body masses
+        quadrupole: Matrix,                     // Quadrupole moment about the center of mass
         children: [Option<Box<OctreeNode>>; 8], // Child nodes for each octant
     },
     /// Leaf node containing actual bodies
@@ -614,6 +1600,234 @@ impl OctreeNode {
         }
     }
 
+    /// Replaces each body in the subtree with its entry in `bodies`, moving
+    /// bodies that left their leaf to `escaped` and pruning emptied children
+    ///
+    /// Returns false if a body has no entry, in which case the subtree is
+    /// left half-updated and must be rebuilt.
+    fn update_bodies(
+        &mut self,
+        bodies: &[OctreeBody],
+        indices: &EntityHashMap<usize>,
+        escaped: &mut Vec<OctreeBody>,
+        pool: &mut OctreeNodePool,
+    ) -> bool {
+        match self {
+            OctreeNode::Internal { children, .. } => {
+                for slot in children.iter_mut() {
+                    let Some(child) = slot else {
+                        continue;
+                    };
+                    if !child.update_bodies(bodies, indices, escaped, pool) {
+                        return false;
+                    }
+                    if child.is_empty()
+                        && let Some(child) = slot.take()
+                    {
+                        pool.return_node(*child);
+                    }
+                }
+                true
+            }
+            OctreeNode::External {
+                bounds,
+                bodies: leaf_bodies,
+            } => {
+                let mut index = 0;
+                while index < leaf_bodies.len() {
+                    let Some(&body_index) = indices.get(&leaf_bodies[index].entity) else {
+                        return false;
+                    };
+                    let body = bodies[body_index];
+                    if bounds.contains(body.position) {
+                        leaf_bodies[index] = body;
+                        index += 1;
+                    } else {
+                        leaf_bodies.swap_remove(index);
+                        escaped.push(body);
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Whether the node holds no bodies; only internal nodes left without
+    /// children during a refit can be empty apart from leaves
+    fn is_empty(&self) -> bool {
+        match self {
+            OctreeNode::Internal { children, .. } => children.iter().all(Option::is_none),
+            OctreeNode::External { bodies, .. } => bodies.is_empty(),
+        }
+    }
+
+    /// Inserts `body` into the leaf whose cell contains it, creating the leaf
+    /// if the cell is empty and subdividing it if it overflows
+    fn insert(
+        &mut self,
+        body: OctreeBody,
+        leaf_threshold: usize,
+        multipole_order: MultipoleOrder,
+        pool: &mut OctreeNodePool,
+        depth: usize,
+    ) {
+        match self {
+            OctreeNode::Internal {
+                bounds, children, ..
+            } => {
+                let octant = Octant::from_position(body.position, bounds.center()).index();
+                match &mut children[octant] {
+                    Some(child) => {
+                        child.insert(body, leaf_threshold, multipole_order, pool, depth + 1)
+                    }
+                    None => {
+                        let mut bodies = pool.get_external_bodies(1);
+                        bodies.push(body);
+                        children[octant] = Some(Box::new(OctreeNode::External {
+                            bounds: bounds.octants()[octant],
+                            bodies,
+                        }));
+                    }
+                }
+            }
+            OctreeNode::External { bounds, bodies } => {
+                bodies.push(body);
+                if bodies.len() > leaf_threshold && depth < MAX_OCTREE_DEPTH {
+                    let bodies = std::mem::take(bodies);
+                    *self = Octree::build_node(
+                        *bounds,
+                        bodies,
+                        leaf_threshold,
+                        multipole_order,
+                        pool,
+                        depth,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Recomputes the aggregated moments of every internal node from its
+    /// children, returning the total mass, center of mass, center-of-mass
+    /// velocity, and quadrupole moment of the subtree
+    fn update_moments(
+        &mut self,
+        multipole_order: MultipoleOrder,
+    ) -> (Scalar, Vector, Vector, Matrix) {
+        match self {
+            OctreeNode::External { bounds, bodies } => {
+                let (total_mass, weighted_sum, momentum) = bodies.iter().fold(
+                    (0.0, Vector::ZERO, Vector::ZERO),
+                    |(mass_acc, pos_acc, momentum_acc), body| {
+                        (
+                            mass_acc + body.mass,
+                            pos_acc + body.position * body.mass,
+                            momentum_acc + body.velocity * body.mass,
+                        )
+                    },
+                );
+                let (center_of_mass, center_of_mass_velocity) = if total_mass > 0.0 {
+                    (weighted_sum / total_mass, momentum / total_mass)
+                } else {
+                    (bounds.center(), Vector::ZERO)
+                };
+                let quadrupole = match multipole_order {
+                    MultipoleOrder::Monopole => Matrix::ZERO,
+                    MultipoleOrder::Quadrupole => {
+                        bodies.iter().fold(Matrix::ZERO, |moment, body| {
+                            moment + quadrupole_moment(body.position - center_of_mass, body.mass)
+                        })
+                    }
+                };
+                (
+                    total_mass,
+                    center_of_mass,
+                    center_of_mass_velocity,
+                    quadrupole,
+                )
+            }
+            OctreeNode::Internal {
+                bounds,
+                center_of_mass,
+                center_of_mass_velocity,
+                total_mass,
+                quadrupole,
+                children,
+            } => {
+                let mut child_moments = [(0.0, Vector::ZERO, Vector::ZERO, Matrix::ZERO); 8];
+                for (moments, child) in child_moments.iter_mut().zip(children.iter_mut()) {
+                    if let Some(child) = child {
+                        *moments = child.update_moments(multipole_order);
+                    }
+                }
+
+                let (mass, weighted_sum, momentum) = child_moments.iter().fold(
+                    (0.0, Vector::ZERO, Vector::ZERO),
+                    |(mass_acc, pos_acc, momentum_acc), &(mass, position, velocity, _)| {
+                        (
+                            mass_acc + mass,
+                            pos_acc + position * mass,
+                            momentum_acc + velocity * mass,
+                        )
+                    },
+                );
+                *total_mass = mass;
+                (*center_of_mass, *center_of_mass_velocity) = if mass > 0.0 {
+                    (weighted_sum / mass, momentum / mass)
+                } else {
+                    (bounds.center(), Vector::ZERO)
+                };
+
+                // Shift each child's quadrupole from its own center of mass
+                // to the node's
+                *quadrupole = match multipole_order {
+                    MultipoleOrder::Monopole => Matrix::ZERO,
+                    MultipoleOrder::Quadrupole => child_moments.iter().fold(
+                        Matrix::ZERO,
+                        |moment, &(mass, position, _, child_quadrupole)| {
+                            moment
+                                + child_quadrupole
+                                + quadrupole_moment(position - *center_of_mass, mass)
+                        },
+                    ),
+                };
+
+                (
+                    *total_mass,
+                    *center_of_mass,
+                    *center_of_mass_velocity,
+                    *quadrupole,
+                )
+            }
+        }
+    }
+
+    fn visit_bodies_within(
+        &self,
+        center: Vector,
+        radius_squared: Scalar,
+        visit: &mut impl FnMut(&OctreeBody),
+    ) {
+        if self.bounds().distance_squared_to(center) > radius_squared {
+            return;
+        }
+
+        match self {
+            OctreeNode::Internal { children, .. } => {
+                for child in children.iter().flatten() {
+                    child.visit_bodies_within(center, radius_squared, visit);
+                }
+            }
+            OctreeNode::External { bodies, .. } => {
+                for body in bodies {
+                    if body.position.distance_squared(center) <= radius_squared {
+                        visit(body);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn collect_bounds(&self, bounds: &mut Vec<Aabb3d>) {
         bounds.push(self.bounds());
 
@@ -1,8 +1,10 @@
 //! Physics resources for simulation
 
-use super::integrators::Integrator;
+use super::gravity::{ForceErrorEstimate, GravitySolver};
+use super::integrators::{Integrator, IntegratorReport};
 use crate::physics::math::Scalar;
 use bevy::prelude::*;
+use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
 
 /// Resource holding the currently active integrator
 #[derive(Resource)]
@@ -14,11 +16,53 @@ impl Default for CurrentIntegrator {
     }
 }
 
+/// Resource holding the gravity solver selected in `PhysicsConfig`
+#[derive(Resource)]
+pub struct CurrentGravitySolver(pub Box<dyn GravitySolver>);
+
+/// Resource holding the latest sampled force error of the gravity solver
+///
+/// Updated every `ForceErrorConfig::interval` ticks while sampling is
+/// enabled.
+#[derive(Resource, Debug, Clone)]
+pub struct ForceErrorMonitor {
+    /// Errors of the latest sample, if one had any force to compare
+    pub estimate: Option<ForceErrorEstimate>,
+    /// Opening angle of the solver after the latest sample, if it has one
+    pub theta: Option<Scalar>,
+    /// Fixed ticks since the latest sample
+    pub ticks_since_sample: usize,
+    /// Chooses the sampled bodies, separate from `SharedRng` so that sampling
+    /// does not change what is spawned
+    pub rng: ChaCha8Rng,
+}
+
+impl Default for ForceErrorMonitor {
+    fn default() -> Self {
+        Self {
+            estimate: None,
+            theta: None,
+            ticks_since_sample: 0,
+            rng: ChaCha8Rng::seed_from_u64(0),
+        }
+    }
+}
+
 /// Resource for physics timestep control
 #[derive(Resource, Debug, Clone)]
 pub struct PhysicsTime {
-    /// Timestep for physics simulation
+    /// Simulated time covered by each fixed tick
     pub dt: Scalar,
+    /// Length of the shortest step taken during the last tick
+    pub current_dt: Scalar,
+    /// Number of steps taken during the last tick
+    pub substeps: usize,
+    /// What the integrator chose during the last tick
+    pub integrator_report: IntegratorReport,
+    /// Evaluations of every body's acceleration during the last tick, from
+    /// the integrator's cost per step; a lower bound for integrators whose
+    /// cost varies
+    pub force_evaluations: Scalar,
     /// Whether physics is paused
     pub paused: bool,
 }
@@ -26,7 +70,11 @@ pub struct PhysicsTime {
 impl Default for PhysicsTime {
     fn default() -> Self {
         Self {
-            dt: 1.0 / 60.0, // 60 Hz default // TODO: base on FixedUpdate schedule?
+            dt: 1.0 / 60.0, // 60 Hz default, matched by the FixedUpdate timestep
+            current_dt: 1.0 / 60.0,
+            substeps: 1,
+            integrator_report: IntegratorReport::default(),
+            force_evaluations: 0.0,
             paused: false,
         }
     }
@@ -0,0 +1,201 @@
+//! Timestep selection criteria for adaptive integration
+//!
+//! Each criterion returns the largest step a single body can take at the
+//! requested accuracy. The step for the whole system is the minimum over all
+//! bodies. A body with no acceleration imposes no limit and reports
+//! `Scalar::INFINITY`, leaving the caller's bounds to decide.
+//!
+//! `StepSizer` applies the configured criterion and bounds to one body, and
+//! `step_adaptively` splits a tick into sub-steps of the size it asks for.
+
+use crate::physics::integrators::{Integrator, SystemAccelerationField};
+use crate::physics::math::{Scalar, Vector};
+use bevy::ecs::entity::Entity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Criterion used to size adaptive sub-steps
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestepCriterion {
+    /// dt = sqrt(2·η·ε / |a|), with ε `TimestepConfig::softening`
+    #[default]
+    Acceleration,
+    /// dt = η·|a| / |ȧ|, with the jerk ȧ estimated from successive accelerations
+    Aarseth,
+}
+
+/// Acceleration-based timestep criterion
+///
+/// dt = sqrt(2·η·ε / |a|)
+///
+/// This is the criterion used by GADGET: a body may move at most a fraction
+/// of the softening length ε under its current acceleration, so steps shrink
+/// as bodies approach each other and the acceleration grows.
+///
+/// # Arguments
+/// * `acceleration` - Current acceleration of the body
+/// * `accuracy` - Dimensionless accuracy parameter η
+/// * `softening` - Force softening length ε
+pub fn acceleration_criterion(acceleration: Vector, accuracy: Scalar, softening: Scalar) -> Scalar {
+    let magnitude = acceleration.length();
+    if magnitude > 0.0 {
+        (2.0 * accuracy * softening / magnitude).sqrt()
+    } else {
+        Scalar::INFINITY
+    }
+}
+
+/// Aarseth-style timestep criterion
+///
+/// dt = η·|a| / |ȧ|
+///
+/// A lower-order form of Aarseth's criterion that uses only the acceleration
+/// and its first time derivative (the jerk). The step is the time over which
+/// the acceleration would change by a fraction η of itself, which tracks
+/// close encounters well without any length scale.
+///
+/// # Arguments
+/// * `acceleration` - Current acceleration of the body
+/// * `jerk` - Time derivative of the acceleration
+/// * `accuracy` - Dimensionless accuracy parameter η
+pub fn aarseth_criterion(acceleration: Vector, jerk: Vector, accuracy: Scalar) -> Scalar {
+    let jerk_magnitude = jerk.length();
+    if jerk_magnitude > 0.0 {
+        accuracy * acceleration.length() / jerk_magnitude
+    } else {
+        Scalar::INFINITY
+    }
+}
+
+/// Sizes a body's step with a timestep criterion, within bounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepSizer {
+    pub criterion: TimestepCriterion,
+    /// Dimensionless accuracy parameter η
+    pub accuracy: Scalar,
+    /// Length ε of the acceleration criterion
+    pub softening: Scalar,
+    pub min_dt: Scalar,
+    pub max_dt: Scalar,
+}
+
+impl StepSizer {
+    /// Step a body asks for, clamped to `[min_dt, max_dt]`
+    ///
+    /// The Aarseth criterion needs a jerk estimate, so a body without one yet
+    /// falls back to the acceleration criterion.
+    pub fn step(&self, acceleration: Vector, jerk: Option<Vector>) -> Scalar {
+        match (self.criterion, jerk) {
+            (TimestepCriterion::Aarseth, Some(jerk)) => {
+                aarseth_criterion(acceleration, jerk, self.accuracy)
+            }
+            _ => acceleration_criterion(acceleration, self.accuracy, self.softening),
+        }
+        .max(self.min_dt)
+        .min(self.max_dt)
+    }
+}
+
+/// Accelerations from the start of the previous sub-step
+///
+/// The Aarseth criterion estimates each body's jerk from the change in its
+/// acceleration since then.
+#[derive(Debug, Default)]
+pub struct PreviousAccelerations {
+    accelerations: HashMap<Entity, Vector>,
+    dt: Scalar,
+}
+
+/// Summary of one tick of adaptive sub-steps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveStepReport {
+    /// Shortest sub-step taken
+    pub shortest_step: Scalar,
+    /// Number of sub-steps taken
+    pub substeps: usize,
+}
+
+/// Advance a system through one tick in adaptive sub-steps.
+///
+/// Each sub-step is as long as the shortest step any body asks for through
+/// `step_size`, given its acceleration at the start of the sub-step and, from
+/// the second sub-step on, a jerk estimated from the change in acceleration
+/// across the previous one. Once `max_substeps` would be exceeded, the
+/// remaining sub-steps are lengthened so that the tick still covers its full
+/// duration and the simulation keeps real-time pacing.
+///
+/// The field is evaluated at the start of every sub-step before the
+/// integrator steps, so a field that keeps its last accelerations can serve
+/// the integrator's first evaluation without summing the forces again.
+///
+/// # Arguments
+///
+/// * `integrator` - Integrator used to advance the system through each sub-step
+/// * `positions` - Position of each body, advanced in place
+/// * `velocities` - Velocity of each body, advanced in place
+/// * `field` - System-wide field
+/// * `entities` - Entity of each body, identifying it across ticks for jerk estimates
+/// * `tick` - Length of the tick
+/// * `max_substeps` - Upper bound on sub-steps in the tick
+/// * `previous` - Accelerations from the previous sub-step, updated in place
+/// * `step_size` - Desired step for a body given its acceleration and estimated jerk
+#[allow(clippy::too_many_arguments)]
+pub fn step_adaptively(
+    integrator: &dyn Integrator,
+    positions: &mut [Vector],
+    velocities: &mut [Vector],
+    field: &mut dyn SystemAccelerationField,
+    entities: &[Entity],
+    tick: Scalar,
+    max_substeps: usize,
+    previous: &mut PreviousAccelerations,
+    step_size: impl Fn(Vector, Option<Vector>) -> Scalar,
+) -> AdaptiveStepReport {
+    debug_assert_eq!(positions.len(), velocities.len());
+    debug_assert_eq!(positions.len(), entities.len());
+
+    let max_substeps = max_substeps.max(1);
+    let mut accelerations = vec![Vector::ZERO; positions.len()];
+    let mut remaining = tick;
+    let mut report = AdaptiveStepReport {
+        shortest_step: tick,
+        substeps: 0,
+    };
+
+    while remaining > tick * Scalar::EPSILON && report.substeps < max_substeps {
+        field.evaluate(positions, &mut accelerations);
+
+        let criterion_dt = accelerations
+            .iter()
+            .zip(entities)
+            .map(|(&acceleration, entity)| {
+                let jerk = previous
+                    .accelerations
+                    .get(entity)
+                    .filter(|_| previous.dt > 0.0)
+                    .map(|&previous_acceleration| {
+                        (acceleration - previous_acceleration) / previous.dt
+                    });
+                step_size(acceleration, jerk)
+            })
+            .fold(Scalar::INFINITY, Scalar::min);
+
+        let substeps_left = (max_substeps - report.substeps) as Scalar;
+        let step = criterion_dt.max(remaining / substeps_left).min(remaining);
+
+        previous.accelerations.clear();
+        previous
+            .accelerations
+            .extend(entities.iter().copied().zip(accelerations.iter().copied()));
+        previous.dt = step;
+
+        integrator.step_system(positions, velocities, field, step);
+
+        remaining -= step;
+        report.substeps += 1;
+        report.shortest_step = report.shortest_step.min(step);
+    }
+
+    report
+}
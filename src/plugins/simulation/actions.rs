@@ -5,7 +5,7 @@
 
 use super::physics::spawn_bodies;
 use crate::physics::components::PhysicsBody;
-use crate::physics::resources::PhysicsTime;
+use crate::physics::resources::{CurrentGravitySolver, CurrentIntegrator, PhysicsTime};
 use crate::prelude::*;
 use bevy_panorbit_camera::PanOrbitCamera;
 
@@ -21,7 +21,8 @@ pub fn handle_restart_simulation_event(
     mut rendering_rng: ResMut<RenderingRng>,
     body_count: Res<BodyCount>,
     mut barycenter: ResMut<Barycenter>,
-    mut octree: ResMut<GravitationalOctree>,
+    mut gravity_solver: ResMut<CurrentGravitySolver>,
+    integrator: Res<CurrentIntegrator>,
     mut pan_orbit_camera: Single<&mut PanOrbitCamera>,
     config: Res<SimulationConfig>,
 ) {
@@ -41,7 +42,8 @@ pub fn handle_restart_simulation_event(
 
         **barycenter = None;
 
-        octree.build(vec![]);
+        gravity_solver.0.build(&[]);
+        integrator.0.reset();
 
         pan_orbit_camera.target_focus = Vec3::ZERO;
         pan_orbit_camera.force_update = true;
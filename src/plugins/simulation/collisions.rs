@@ -0,0 +1,188 @@
+//! Collision handling for simulation bodies
+//!
+//! Runs after integration so that contacts are detected at the freshly
+//! integrated positions, and before transforms are synchronized so that
+//! merged or separated bodies are rendered where they now are. Which response
+//! runs is chosen by `physics.collisions.mode`.
+
+use super::components::factory;
+use crate::config::CollisionMode;
+use crate::physics::collisions::{
+    CollisionBody, bounce, find_overlapping_pairs, group_overlapping, merge_bodies,
+};
+use crate::physics::components::{Mass, PhysicsBody, Position, Radius, Velocity};
+use crate::prelude::*;
+
+type CollidingBodyData<'a> = (
+    Entity,
+    &'a mut Position,
+    &'a mut Velocity,
+    &'a mut Mass,
+    &'a mut Radius,
+    &'a mut Mesh3d,
+    &'a MeshMaterial3d<StandardMaterial>,
+);
+
+/// Merge bodies whose spheres overlap
+///
+/// Every cluster of touching bodies collapses into its most massive member.
+/// The survivor receives the combined mass, momentum, and volume, a mesh
+/// matching its new radius, and a mass-weighted blend of the members' colors.
+/// All other members are despawned and announced with a `BodyMerged` message.
+pub fn merge_colliding_bodies(
+    config: Res<SimulationConfig>,
+    mut commands: Commands,
+    mut bodies: Query<CollidingBodyData, With<PhysicsBody>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut merges: MessageWriter<BodyMerged>,
+) {
+    if config.physics.collisions.mode != CollisionMode::Merge {
+        return;
+    }
+
+    let collision_bodies: Vec<CollisionBody> = bodies
+        .iter()
+        .map(
+            |(entity, position, velocity, mass, radius, _, _)| CollisionBody {
+                entity,
+                position: position.value(),
+                velocity: velocity.value(),
+                mass: mass.value(),
+                radius: radius.value(),
+            },
+        )
+        .collect();
+
+    let pairs = find_overlapping_pairs(&collision_bodies);
+    if pairs.is_empty() {
+        return;
+    }
+
+    for cluster in group_overlapping(collision_bodies.len(), &pairs) {
+        let members: Vec<&CollisionBody> = cluster
+            .iter()
+            .map(|&index| &collision_bodies[index])
+            .collect();
+        let merged = merge_bodies(members.iter().copied());
+
+        let Some(survivor) = members
+            .iter()
+            .max_by(|a, b| a.mass.total_cmp(&b.mass))
+            .map(|body| body.entity)
+        else {
+            continue;
+        };
+
+        let (base_color, emissive) = blend_member_colors(&members, &bodies, &materials);
+
+        let Ok((_, mut position, mut velocity, mut mass, mut radius, mut mesh, material)) =
+            bodies.get_mut(survivor)
+        else {
+            continue;
+        };
+
+        *position = Position::new(merged.position);
+        *velocity = Velocity::new(merged.velocity);
+        *mass = Mass::new(merged.mass);
+        *radius = Radius::new(merged.radius);
+        *mesh = Mesh3d(factory::create_detailed_mesh(
+            &mut meshes,
+            merged.radius as f32,
+        ));
+
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = base_color.into();
+            material.emissive = emissive;
+        }
+
+        for absorbed in members
+            .iter()
+            .map(|body| body.entity)
+            .filter(|&entity| entity != survivor)
+        {
+            commands.entity(absorbed).despawn();
+            merges.write(BodyMerged { survivor, absorbed });
+        }
+    }
+}
+
+/// Bounce overlapping bodies off each other
+///
+/// Each overlapping pair exchanges an impulse along its line of centres,
+/// scaled by the configured coefficient of restitution, and is pushed apart
+/// until the spheres no longer intersect. Pairs are resolved in turn, so a
+/// body touching several others sees the effect of earlier contacts.
+pub fn bounce_colliding_bodies(
+    config: Res<SimulationConfig>,
+    mut bodies: Query<(Entity, &mut Position, &mut Velocity, &Mass, &Radius), With<PhysicsBody>>,
+) {
+    if config.physics.collisions.mode != CollisionMode::Bounce {
+        return;
+    }
+
+    let mut collision_bodies: Vec<CollisionBody> = bodies
+        .iter()
+        .map(|(entity, position, velocity, mass, radius)| CollisionBody {
+            entity,
+            position: position.value(),
+            velocity: velocity.value(),
+            mass: mass.value(),
+            radius: radius.value(),
+        })
+        .collect();
+
+    let pairs = find_overlapping_pairs(&collision_bodies);
+    if pairs.is_empty() {
+        return;
+    }
+
+    let restitution = config.physics.collisions.restitution.clamp(0.0, 1.0);
+    let mut touched = vec![false; collision_bodies.len()];
+
+    for (i, j) in pairs {
+        let (head, tail) = collision_bodies.split_at_mut(j);
+        bounce(&mut head[i], &mut tail[0], restitution);
+        touched[i] = true;
+        touched[j] = true;
+    }
+
+    for body in collision_bodies
+        .iter()
+        .zip(touched)
+        .filter_map(|(body, touched)| touched.then_some(body))
+    {
+        if let Ok((_, mut position, mut velocity, _, _)) = bodies.get_mut(body.entity) {
+            *position = Position::new(body.position);
+            *velocity = Velocity::new(body.velocity);
+        }
+    }
+}
+
+/// Mass-weighted average of the base and emissive colors of a cluster
+fn blend_member_colors(
+    members: &[&CollisionBody],
+    bodies: &Query<CollidingBodyData, With<PhysicsBody>>,
+    materials: &Assets<StandardMaterial>,
+) -> (LinearRgba, LinearRgba) {
+    let total_mass: Scalar = members.iter().map(|body| body.mass).sum();
+
+    members.iter().fold(
+        (LinearRgba::NONE, LinearRgba::NONE),
+        |(base_color, emissive), body| {
+            let Some(material) = bodies
+                .get(body.entity)
+                .ok()
+                .and_then(|(_, _, _, _, _, _, material)| materials.get(&material.0))
+            else {
+                return (base_color, emissive);
+            };
+
+            let weight = (body.mass / total_mass) as f32;
+            (
+                base_color + material.base_color.to_linear() * weight,
+                emissive + material.emissive * weight,
+            )
+        },
+    )
+}
@@ -7,17 +7,20 @@
 use crate::prelude::*;
 
 pub mod actions;
+mod collisions;
 mod components;
 mod physics;
 
 use crate::physics::integrators::VelocityVerlet;
 use crate::physics::integrators::registry::IntegratorRegistry;
-use crate::physics::resources::CurrentIntegrator;
+use crate::physics::resources::{CurrentGravitySolver, CurrentIntegrator, ForceErrorMonitor};
 use actions::{handle_restart_simulation_event, handle_toggle_pause_simulation_event};
 use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings};
+use collisions::{bounce_colliding_bodies, merge_colliding_bodies};
+pub use physics::PhysicsSet;
 use physics::{
-    PhysicsSet, counteract_barycentric_drift, integrate_motions, rebuild_octree,
-    sync_transform_from_position,
+    counteract_barycentric_drift, create_barnes_hut_solver, create_gravity_solver,
+    integrate_motions, monitor_force_error, rebuild_gravity_solver, sync_transform_from_position,
 };
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
@@ -74,19 +77,27 @@ impl Plugin for SimulationPlugin {
         app.insert_resource(GravitationalConstant(config.physics.gravitational_constant));
         app.insert_resource(BodyCount(config.physics.body_count));
         app.init_resource::<Barycenter>();
-        app.insert_resource(GravitationalOctree::new(
-            Octree::new(
-                config.physics.octree_theta,
-                config.physics.force_calculation_min_distance,
-                config.physics.force_calculation_max_force,
-            )
-            .with_leaf_threshold(config.physics.octree_leaf_threshold),
-        ));
+
+        let gravity_solver = match create_gravity_solver(&config.physics) {
+            Ok(solver) => solver,
+            Err(e) => {
+                warn!(
+                    "Failed to create gravity solver: {}. Falling back to barnes_hut",
+                    e
+                );
+                create_barnes_hut_solver(&config.physics)
+            }
+        };
+        app.insert_resource(CurrentGravitySolver(gravity_solver));
+        app.init_resource::<ForceErrorMonitor>();
 
         // Create integrator using flexible configuration system
         let registry = IntegratorRegistry::new().with_standard_integrators();
         let integrator: Box<dyn crate::physics::integrators::Integrator + Send + Sync> =
-            match registry.create(&config.physics.integrator.integrator_type) {
+            match registry.create(
+                &config.physics.integrator.integrator_type,
+                &config.physics.integrator.parameters,
+            ) {
                 Ok(integrator) => integrator,
                 Err(e) => {
                     warn!(
@@ -99,10 +110,15 @@ impl Plugin for SimulationPlugin {
         app.insert_resource(CurrentIntegrator(integrator));
         app.insert_resource(IntegratorRegistry::default());
 
-        app.init_resource::<crate::physics::resources::PhysicsTime>();
+        // Each fixed tick advances the simulation by `PhysicsTime::dt`, so the
+        // two must agree for the simulation to run in real time
+        let physics_time = crate::physics::resources::PhysicsTime::default();
+        app.insert_resource(Time::<Fixed>::from_seconds(physics_time.dt));
+        app.insert_resource(physics_time);
 
         // New unified command event
         app.add_message::<SimulationCommand>();
+        app.add_message::<BodyMerged>();
 
         app.edit_schedule(FixedUpdate, |schedule| {
             schedule.set_build_settings(ScheduleBuildSettings {
@@ -114,8 +130,9 @@ impl Plugin for SimulationPlugin {
         app.configure_sets(
             FixedUpdate,
             (
-                PhysicsSet::BuildOctree,
+                PhysicsSet::BuildGravitySolver,
                 PhysicsSet::IntegrateMotions,
+                PhysicsSet::ResolveCollisions,
                 PhysicsSet::SyncTransforms,
                 PhysicsSet::CorrectBarycentricDrift,
             )
@@ -137,10 +154,19 @@ impl Plugin for SimulationPlugin {
         app.add_systems(
             FixedUpdate,
             (
-                rebuild_octree.in_set(PhysicsSet::BuildOctree),
+                (
+                    rebuild_gravity_solver,
+                    monitor_force_error.run_if(in_state(AppState::Running)),
+                )
+                    .chain()
+                    .in_set(PhysicsSet::BuildGravitySolver),
                 integrate_motions
                     .in_set(PhysicsSet::IntegrateMotions)
                     .run_if(in_state(AppState::Running)),
+                (merge_colliding_bodies, bounce_colliding_bodies)
+                    .chain()
+                    .in_set(PhysicsSet::ResolveCollisions)
+                    .run_if(in_state(AppState::Running)),
                 sync_transform_from_position
                     .in_set(PhysicsSet::SyncTransforms)
                     .run_if(in_state(AppState::Running)),
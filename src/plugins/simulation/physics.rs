@@ -1,94 +1,465 @@
-use crate::config::SimulationConfig;
-use crate::physics::integrators::AccelerationField;
+use crate::config::{
+    GravitySolverKind, OctreeUpdate, PhysicsConfig, SimulationConfig, TimestepMode,
+};
+use crate::physics::integrators::SystemAccelerationField;
 use crate::physics::math::{Scalar, Vector};
 use crate::physics::{
+    block_timestep::step_blocks,
     components::{Mass, PhysicsBody, PhysicsBodyBundle, Position, Velocity},
-    octree::{Octree, OctreeBody},
-    resources::{CurrentIntegrator, PhysicsTime},
-};
-use crate::resources::{
-    Barycenter, GravitationalConstant, GravitationalOctree, RenderingRng, SharedRng,
+    gravity::{
+        DirectSummation, FastMultipole, GravitySolver, ParticleMesh, ThetaTuner,
+        estimate_force_error,
+    },
+    linear_octree::LinearOctree,
+    octree::{Octree, OctreeBody, OctreeLayout},
+    resources::{CurrentGravitySolver, CurrentIntegrator, ForceErrorMonitor, PhysicsTime},
+    timestep::{PreviousAccelerations, StepSizer, step_adaptively},
 };
+use crate::resources::{Barycenter, GravitationalConstant, RenderingRng, SharedRng};
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 use bevy::pbr::MeshMaterial3d;
 use bevy::prelude::Mesh3d;
 use bevy::prelude::*;
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PhysicsSet {
-    BuildOctree,
+    BuildGravitySolver,
     IntegrateMotions,
+    ResolveCollisions,
     SyncTransforms,
     CorrectBarycentricDrift,
 }
 
-/// Rebuild the octree structure from current body positions
-pub fn rebuild_octree(
-    bodies: Query<(Entity, &Position, &Mass)>,
-    mut octree: ResMut<GravitationalOctree>,
+/// Create the gravity solver selected in the physics configuration
+///
+/// # Errors
+///
+/// Returns an error if the settings of the selected solver are invalid.
+pub fn create_gravity_solver(physics: &PhysicsConfig) -> Result<Box<dyn GravitySolver>, String> {
+    let softening = physics.force_calculation_softening;
+
+    match physics.gravity_solver {
+        GravitySolverKind::BarnesHut => Ok(create_barnes_hut_solver(physics)),
+        GravitySolverKind::Direct => Ok(Box::new(
+            DirectSummation::new(
+                physics.force_calculation_min_distance,
+                physics.force_calculation_max_force,
+            )
+            .with_softening(softening),
+        )),
+        GravitySolverKind::Fmm => Ok(Box::new(
+            FastMultipole::new(
+                physics.fmm.order,
+                physics.fmm.theta,
+                physics.fmm.leaf_threshold,
+                physics.force_calculation_min_distance,
+                physics.force_calculation_max_force,
+            )?
+            .with_softening(softening),
+        )),
+        GravitySolverKind::ParticleMesh => Ok(Box::new(
+            ParticleMesh::new(
+                physics.particle_mesh.grid_size,
+                physics.particle_mesh.split_scale,
+                physics
+                    .particle_mesh
+                    .p3m
+                    .then_some(physics.particle_mesh.p3m_cutoff),
+                physics.force_calculation_min_distance,
+                physics.force_calculation_max_force,
+            )?
+            .with_softening(softening),
+        )),
+    }
+}
+
+/// Create the Barnes-Hut octree solver from the physics configuration
+///
+/// Only the boxed layout can refit; the linear layout rebuilds every time.
+pub fn create_barnes_hut_solver(physics: &PhysicsConfig) -> Box<dyn GravitySolver> {
+    match physics.octree_layout {
+        OctreeLayout::Boxed => {
+            let octree = Octree::new(
+                physics.octree_theta,
+                physics.force_calculation_min_distance,
+                physics.force_calculation_max_force,
+            )
+            .with_softening(physics.force_calculation_softening)
+            .with_leaf_threshold(physics.octree_leaf_threshold)
+            .with_multipole_order(physics.octree_multipole_order)
+            .with_opening_criterion(physics.octree_opening_criterion)
+            .with_relative_accuracy(physics.octree_relative_accuracy);
+
+            match physics.octree_update {
+                OctreeUpdate::Rebuild => Box::new(octree),
+                OctreeUpdate::Refit => {
+                    Box::new(octree.with_refit(physics.octree_refit_max_reinserted))
+                }
+            }
+        }
+        OctreeLayout::Linear => Box::new(
+            LinearOctree::new(
+                physics.octree_theta,
+                physics.force_calculation_min_distance,
+                physics.force_calculation_max_force,
+            )
+            .with_softening(physics.force_calculation_softening)
+            .with_leaf_threshold(physics.octree_leaf_threshold)
+            .with_multipole_order(physics.octree_multipole_order)
+            .with_opening_criterion(physics.octree_opening_criterion)
+            .with_relative_accuracy(physics.octree_relative_accuracy),
+        ),
+    }
+}
+
+/// Rebuild the gravity solver from current body positions and velocities
+pub fn rebuild_gravity_solver(
+    bodies: Query<(Entity, &Position, &Velocity, &Mass)>,
+    mut solver: ResMut<CurrentGravitySolver>,
 ) {
     if bodies.is_empty() {
         return;
     }
 
-    octree.build(bodies.iter().map(|(entity, position, mass)| OctreeBody {
-        position: position.value(),
-        mass: mass.value(),
-        entity,
-    }));
+    let bodies: Vec<OctreeBody> = bodies
+        .iter()
+        .map(|(entity, position, velocity, mass)| OctreeBody {
+            position: position.value(),
+            velocity: velocity.value(),
+            mass: mass.value(),
+            entity,
+        })
+        .collect();
+    solver.0.build(&bodies);
 }
 
-/// Acceleration field that wraps the octree for a specific body
+/// Sample the force error of the gravity solver and tune its opening angle
 ///
-/// This struct implements the AccelerationField trait to allow integrators
-/// to calculate accelerations at arbitrary positions during multi-stage integration.
-struct BodyAccelerationField<'a> {
-    octree: &'a Octree,
-    body_entity: Entity,
-    body_mass: Scalar,
+/// Every `interval` ticks, the forces of a random sample of bodies are
+/// compared against direct summation and the result is stored in
+/// `ForceErrorMonitor`. With auto-tuning enabled, the solver's theta is then
+/// stepped toward the target force error or frame time. Runs right after the
+/// solver is built, so the sample sees the same bodies as the solver.
+pub fn monitor_force_error(
+    bodies: Query<(Entity, &Position, &Velocity, &Mass)>,
+    mut solver: ResMut<CurrentGravitySolver>,
+    mut monitor: ResMut<ForceErrorMonitor>,
+    g: Res<GravitationalConstant>,
+    config: Res<SimulationConfig>,
+    diagnostics: Option<Res<DiagnosticsStore>>,
+) {
+    let settings = &config.physics.force_error;
+    if !settings.enabled || bodies.is_empty() {
+        return;
+    }
+
+    monitor.ticks_since_sample += 1;
+    if monitor.ticks_since_sample < settings.interval {
+        return;
+    }
+    monitor.ticks_since_sample = 0;
+
+    let bodies: Vec<OctreeBody> = bodies
+        .iter()
+        .map(|(entity, position, velocity, mass)| OctreeBody {
+            position: position.value(),
+            velocity: velocity.value(),
+            mass: mass.value(),
+            entity,
+        })
+        .collect();
+
+    let mut reference = DirectSummation::new(
+        config.physics.force_calculation_min_distance,
+        config.physics.force_calculation_max_force,
+    )
+    .with_softening(config.physics.force_calculation_softening);
+    reference.build(&bodies);
+
+    let sample_size = settings.sample_size.min(bodies.len());
+    let samples = rand::seq::index::sample(&mut monitor.rng, bodies.len(), sample_size).into_vec();
+    monitor.estimate = estimate_force_error(&*solver.0, &reference, &bodies, &samples, **g);
+
+    // The smoothed frame time, published in milliseconds
+    let frame_time = diagnostics
+        .as_ref()
+        .and_then(|store| store.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME))
+        .and_then(|frame_time| frame_time.smoothed())
+        .map(|milliseconds| milliseconds / 1000.0);
+
+    if let Some(theta) = solver.0.theta() {
+        let tuner = ThetaTuner {
+            target: settings.auto_tune,
+            target_error: settings.target_error,
+            target_frame_time: settings.target_frame_time,
+            min_theta: settings.min_theta,
+            max_theta: settings.max_theta,
+        };
+        solver
+            .0
+            .set_theta(tuner.next_theta(theta, monitor.estimate.as_ref(), frame_time));
+    }
+    monitor.theta = solver.0.theta();
+}
+
+/// System-wide acceleration field backed by the gravity solver
+///
+/// Each update rebuilds the solver from the given positions, so every stage
+/// of a multi-stage integrator sees all bodies where that stage put them. An
+/// update with the positions the solver was last built from is skipped,
+/// which lets the first stage reuse the solver built in
+/// `PhysicsSet::BuildGravitySolver`. Updates without velocities keep the
+/// velocities the solver last had, which only jerk evaluations read.
+///
+/// Whole-system evaluations go through `GravitySolver::forces`, so solvers
+/// that share work between bodies can do so, and their accelerations are
+/// recorded with the solver for opening criteria that depend on them.
+struct GravitySystemField<'a> {
+    solver: &'a mut dyn GravitySolver,
+    masses: &'a [Scalar],
+    gravitational_parameters: Vec<Scalar>,
     g: Scalar,
+    /// The bodies the solver was last built from
+    bodies: Vec<OctreeBody>,
+    /// Accelerations of the latest `evaluate`, kept until the solver is
+    /// rebuilt, so that the evaluation sizing an adaptive sub-step also
+    /// serves as the first stage of the step itself
+    accelerations: Option<Vec<Vector>>,
 }
 
-impl<'a> AccelerationField for BodyAccelerationField<'a> {
-    fn at(&self, position: Vector) -> Vector {
-        let force = self.octree.calculate_force_at_position(
-            position,
-            self.body_mass,
-            self.body_entity,
-            self.g,
-        );
-        force / self.body_mass
+impl GravitySystemField<'_> {
+    fn is_built_from(&self, positions: &[Vector]) -> bool {
+        positions
+            .iter()
+            .eq(self.bodies.iter().map(|body| &body.position))
+    }
+
+    fn entity(&self, index: usize) -> Entity {
+        self.bodies[index].entity
+    }
+}
+
+impl<'a> SystemAccelerationField for GravitySystemField<'a> {
+    fn update(&mut self, positions: &[Vector]) {
+        if self.is_built_from(positions) {
+            return;
+        }
+
+        for (body, &position) in self.bodies.iter_mut().zip(positions) {
+            body.position = position;
+        }
+        self.solver.build(&self.bodies);
+        self.accelerations = None;
+    }
+
+    fn at(&self, index: usize, position: Vector) -> Vector {
+        let mass = self.masses[index];
+        let force = self
+            .solver
+            .force_at(position, mass, self.entity(index), self.g);
+        force / mass
+    }
+
+    fn update_with_velocities(&mut self, positions: &[Vector], velocities: &[Vector]) {
+        if self.is_built_from(positions)
+            && velocities
+                .iter()
+                .eq(self.bodies.iter().map(|body| &body.velocity))
+        {
+            return;
+        }
+
+        for ((body, &position), &velocity) in self.bodies.iter_mut().zip(positions).zip(velocities)
+        {
+            body.position = position;
+            body.velocity = velocity;
+        }
+        self.solver.build(&self.bodies);
+        self.accelerations = None;
+    }
+
+    fn at_with_jerk(&self, index: usize, position: Vector, velocity: Vector) -> (Vector, Vector) {
+        let mass = self.masses[index];
+        let (force, force_derivative) =
+            self.solver
+                .force_and_jerk_at(position, velocity, mass, self.entity(index), self.g);
+        (force / mass, force_derivative / mass)
+    }
+
+    fn evaluate(&mut self, positions: &[Vector], accelerations: &mut [Vector]) {
+        debug_assert_eq!(positions.len(), accelerations.len());
+
+        self.update(positions);
+        if let Some(cached) = &self.accelerations {
+            accelerations.copy_from_slice(cached);
+            return;
+        }
+
+        self.solver.forces(&self.bodies, self.g, accelerations);
+        for (acceleration, &mass) in accelerations.iter_mut().zip(self.masses) {
+            *acceleration /= mass;
+        }
+        self.solver
+            .record_accelerations(&self.bodies, accelerations);
+        self.accelerations = Some(accelerations.to_vec());
+    }
+
+    fn evaluate_with_jerk(
+        &mut self,
+        positions: &[Vector],
+        velocities: &[Vector],
+        derivatives: &mut [(Vector, Vector)],
+    ) {
+        debug_assert_eq!(positions.len(), derivatives.len());
+
+        self.update_with_velocities(positions, velocities);
+        self.solver
+            .forces_and_jerks(&self.bodies, self.g, derivatives);
+        for ((acceleration, jerk), &mass) in derivatives.iter_mut().zip(self.masses) {
+            *acceleration /= mass;
+            *jerk /= mass;
+        }
+        let accelerations: Vec<Vector> = derivatives
+            .iter()
+            .map(|&(acceleration, _)| acceleration)
+            .collect();
+        self.solver
+            .record_accelerations(&self.bodies, &accelerations);
+    }
+
+    fn gravitational_parameters(&self) -> Option<&[Scalar]> {
+        Some(&self.gravitational_parameters)
     }
 }
 
 /// Integrate positions and velocities for all bodies
+///
+/// Bodies are gathered into flat arrays and advanced together through
+/// `Integrator::step_system`, then written back. In adaptive timestep mode
+/// the tick is split into as many sub-steps as the configured criterion
+/// requires; in block mode each body takes its own power-of-two fraction of
+/// the tick.
 pub fn integrate_motions(
     mut query: Query<(Entity, &mut Position, &mut Velocity, &Mass)>,
     integrator: Res<CurrentIntegrator>,
-    physics_time: Res<PhysicsTime>,
-    octree: Res<GravitationalOctree>,
+    mut physics_time: ResMut<PhysicsTime>,
+    mut solver: ResMut<CurrentGravitySolver>,
     g: Res<GravitationalConstant>,
+    config: Res<SimulationConfig>,
+    mut previous: Local<PreviousAccelerations>,
 ) {
-    if physics_time.is_paused() {
+    if physics_time.is_paused() || query.is_empty() {
         return;
     }
 
     let dt = physics_time.dt;
-    let octree: &Octree = &octree;
-
-    query
-        .par_iter_mut()
-        .for_each(|(entity, mut position, mut velocity, mass)| {
-            let field = BodyAccelerationField {
-                octree,
-                body_entity: entity,
-                body_mass: mass.value(),
-                g: **g,
-            };
+    let step_sizer = step_sizer(&config.physics);
+
+    let (entities, masses): (Vec<Entity>, Vec<Scalar>) = query
+        .iter()
+        .map(|(entity, _, _, mass)| (entity, mass.value()))
+        .unzip();
+    let (mut positions, mut velocities): (Vec<Vector>, Vec<Vector>) = query
+        .iter()
+        .map(|(_, position, velocity, _)| (position.value(), velocity.value()))
+        .unzip();
 
+    let mut field = GravitySystemField {
+        solver: &mut *solver.0,
+        masses: &masses,
+        gravitational_parameters: masses.iter().map(|&mass| mass * **g).collect(),
+        g: **g,
+        // The solver was built from these bodies in
+        // `PhysicsSet::BuildGravitySolver`
+        bodies: entities
+            .iter()
+            .zip(&masses)
+            .zip(positions.iter().zip(&velocities))
+            .map(|((&entity, &mass), (&position, &velocity))| OctreeBody {
+                position,
+                velocity,
+                mass,
+                entity,
+            })
+            .collect(),
+        accelerations: None,
+    };
+
+    // Steps of the whole system, counting each body's step in block mode as
+    // a fraction of one
+    let (current_dt, substeps, system_steps) = match config.physics.timestep.mode {
+        TimestepMode::Fixed => {
             integrator
                 .0
-                .step(position.value_mut(), velocity.value_mut(), &field, dt);
-        });
+                .step_system(&mut positions, &mut velocities, &mut field, dt);
+            (dt, 1, 1.0)
+        }
+        TimestepMode::Adaptive => {
+            let report = step_adaptively(
+                &*integrator.0,
+                &mut positions,
+                &mut velocities,
+                &mut field,
+                &entities,
+                dt,
+                config.physics.timestep.max_substeps,
+                &mut previous,
+                |acceleration, jerk| step_sizer.step(acceleration, jerk),
+            );
+            (
+                report.shortest_step,
+                report.substeps,
+                report.substeps as Scalar,
+            )
+        }
+        TimestepMode::Block => {
+            let report = step_blocks(
+                &*integrator.0,
+                &mut positions,
+                &mut velocities,
+                &mut field,
+                dt,
+                config.physics.timestep.max_block_level,
+                |acceleration, jerk| step_sizer.step(acceleration, jerk),
+            );
+            (
+                report.shortest_step,
+                report.block_times,
+                report.body_steps as Scalar / entities.len() as Scalar,
+            )
+        }
+    };
+
+    physics_time.current_dt = current_dt;
+    physics_time.substeps = substeps;
+    physics_time.force_evaluations =
+        integrator.0.force_evaluations().minimum() as Scalar * system_steps;
+    physics_time.integrator_report = integrator.0.report();
+
+    for ((_, mut position, mut velocity, _), (new_position, new_velocity)) in
+        query.iter_mut().zip(positions.into_iter().zip(velocities))
+    {
+        *position.value_mut() = new_position;
+        *velocity.value_mut() = new_velocity;
+    }
+}
+
+/// Step sizer for the configured timestep criterion and bounds
+///
+/// The acceleration criterion uses the configured `softening`, or else
+/// `force_calculation_min_distance`.
+fn step_sizer(physics: &PhysicsConfig) -> StepSizer {
+    let settings = &physics.timestep;
+
+    StepSizer {
+        criterion: settings.criterion,
+        accuracy: settings.accuracy,
+        softening: settings
+            .softening
+            .unwrap_or(physics.force_calculation_min_distance),
+        min_dt: settings.min_dt,
+        max_dt: settings.max_dt,
+    }
 }
 
 /// Synchronize Transform components from high-precision Position components
@@ -5,8 +5,12 @@
 //! diagnostics implementation such as octree build time, force calculation
 //! performance, and physics step timing.
 
-use crate::physics::components::{Mass, PhysicsBody, Velocity};
+use crate::physics::components::{Mass, Position, Velocity};
 use crate::physics::math::Scalar;
+use crate::physics::octree::OctreeBody;
+use crate::physics::resources::{CurrentGravitySolver, ForceErrorMonitor, PhysicsTime};
+use crate::plugins::simulation::PhysicsSet;
+use crate::resources::GravitationalConstant;
 use crate::states::AppState;
 use bevy::diagnostic::DEFAULT_MAX_HISTORY_LENGTH;
 use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
@@ -18,6 +22,30 @@ use core::time::Duration;
 pub struct SimulationMetrics {
     /// Last calculated kinetic energy
     pub kinetic_energy: Scalar,
+    /// Last calculated potential energy, if the gravity solver has one
+    pub potential_energy: Option<Scalar>,
+    /// Sum of the last kinetic and potential energies
+    pub total_energy: Option<Scalar>,
+    /// Shortest physics step taken during the last tick
+    pub timestep: Scalar,
+    /// Number of physics steps taken during the last tick
+    pub substeps: usize,
+    /// Longest internal step of an integrator with its own step control
+    pub integrator_step: Option<Scalar>,
+    /// Largest local position error estimated by the integrator
+    pub integrator_error: Option<Scalar>,
+    /// Order of the last step of an integrator that chooses its order
+    pub integrator_order: Option<usize>,
+    /// Steps of an implicit integrator that reached its iteration cap
+    pub integrator_unconverged: Option<usize>,
+    /// Force evaluations of the whole system during the last tick
+    pub force_evaluations: Scalar,
+    /// RMS relative force error of the latest sample of bodies
+    pub force_error_rms: Option<Scalar>,
+    /// Largest relative force error of the latest sample of bodies
+    pub force_error_max: Option<Scalar>,
+    /// Opening angle of the gravity solver after the latest sample
+    pub theta: Option<Scalar>,
 }
 
 #[derive(Resource)]
@@ -46,6 +74,53 @@ impl SimulationDiagnosticsPlugin {
     pub const KINETIC_ENERGY: DiagnosticPath =
         DiagnosticPath::const_new("simulation/energy/kinetic");
 
+    /// Diagnostic path for gravitational potential energy
+    pub const POTENTIAL_ENERGY: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/energy/potential");
+
+    /// Diagnostic path for total energy, the Hamiltonian of the system
+    pub const TOTAL_ENERGY: DiagnosticPath = DiagnosticPath::const_new("simulation/energy/total");
+
+    /// Diagnostic path for the current physics timestep
+    pub const TIMESTEP: DiagnosticPath = DiagnosticPath::const_new("simulation/time/timestep");
+
+    /// Diagnostic path for the number of physics sub-steps per tick
+    pub const SUBSTEPS: DiagnosticPath = DiagnosticPath::const_new("simulation/time/substeps");
+
+    /// Diagnostic path for the internal step chosen by an adaptive integrator
+    pub const INTEGRATOR_STEP: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/integrator/step_size");
+
+    /// Diagnostic path for the local error estimated by an embedded integrator
+    pub const INTEGRATOR_ERROR: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/integrator/error_estimate");
+
+    /// Diagnostic path for the order chosen by an extrapolation integrator
+    pub const INTEGRATOR_ORDER: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/integrator/order");
+
+    /// Diagnostic path for the steps an implicit integrator failed to converge
+    pub const INTEGRATOR_UNCONVERGED: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/integrator/unconverged_steps");
+
+    /// Diagnostic path for the force evaluations made during each tick, for
+    /// comparing integrators at equal cost
+    pub const FORCE_EVALUATIONS: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/integrator/force_evaluations");
+
+    /// Diagnostic path for the RMS relative force error of sampled bodies
+    /// against direct summation
+    pub const FORCE_ERROR_RMS: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/gravity/force_error_rms");
+
+    /// Diagnostic path for the largest relative force error of sampled bodies
+    pub const FORCE_ERROR_MAX: DiagnosticPath =
+        DiagnosticPath::const_new("simulation/gravity/force_error_max");
+
+    /// Diagnostic path for the opening angle of the gravity solver, which
+    /// changes under auto-tuning
+    pub const THETA: DiagnosticPath = DiagnosticPath::const_new("simulation/gravity/theta");
+
     fn register_diagnostics(&self, app: &mut App) {
         // Register kinetic energy diagnostic with units
         app.register_diagnostic(
@@ -54,17 +129,90 @@ impl SimulationDiagnosticsPlugin {
                 .with_smoothing_factor(self.smoothing_factor)
                 .with_suffix("J"), // Add units for clarity
         );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::POTENTIAL_ENERGY)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor)
+                .with_suffix("J"),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::TOTAL_ENERGY)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor)
+                .with_suffix("J"),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::TIMESTEP)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor)
+                .with_suffix("s"),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::SUBSTEPS)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::INTEGRATOR_STEP)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor)
+                .with_suffix("s"),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::INTEGRATOR_ERROR)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::INTEGRATOR_ORDER)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::INTEGRATOR_UNCONVERGED)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor),
+        );
+
+        app.register_diagnostic(
+            Diagnostic::new(Self::FORCE_EVALUATIONS)
+                .with_max_history_length(self.max_history_length)
+                .with_smoothing_factor(self.smoothing_factor),
+        );
+
+        for path in [Self::FORCE_ERROR_RMS, Self::FORCE_ERROR_MAX, Self::THETA] {
+            app.register_diagnostic(
+                Diagnostic::new(path)
+                    .with_max_history_length(self.max_history_length)
+                    .with_smoothing_factor(self.smoothing_factor),
+            );
+        }
     }
 
     fn update_timer_ticks(mut state: ResMut<SimulationDiagnosticsState>, time: Res<Time>) {
         state.update_timer.tick(time.delta());
     }
 
-    /// Calculate total kinetic energy of the system
+    /// Calculate the kinetic, potential, and total energy of the system
     ///
-    /// Kinetic energy = ½ Σ(m·v²) for all bodies
-    fn calculate_kinetic_energy(
-        bodies: Query<(&Velocity, &Mass), With<PhysicsBody>>,
+    /// Kinetic energy = ½ Σ(m·v²) for all bodies, and potential energy comes
+    /// from the gravity solver. This runs between
+    /// `PhysicsSet::BuildGravitySolver` and `PhysicsSet::IntegrateMotions`,
+    /// while the solver still holds the bodies' current positions, so both
+    /// energies are taken at the same instant. Solvers without a potential
+    /// report only kinetic energy.
+    fn calculate_energy(
+        bodies: Query<(Entity, &Position, &Velocity, &Mass)>,
+        solver: Option<Res<CurrentGravitySolver>>,
+        g: Option<Res<GravitationalConstant>>,
         mut metrics: ResMut<SimulationMetrics>,
         mut diagnostics: Diagnostics,
         state: Res<SimulationDiagnosticsState>,
@@ -76,7 +224,7 @@ impl SimulationDiagnosticsPlugin {
 
         let kinetic_energy: Scalar = bodies
             .iter()
-            .map(|(velocity, mass)| {
+            .map(|(_, _, velocity, mass)| {
                 let v_squared = velocity.value().length_squared();
                 0.5 * mass.value() * v_squared
             })
@@ -84,6 +232,101 @@ impl SimulationDiagnosticsPlugin {
 
         metrics.kinetic_energy = kinetic_energy;
         diagnostics.add_measurement(&Self::KINETIC_ENERGY, || kinetic_energy as f64);
+
+        // The same bodies the solver was built from
+        let potential_energy = solver.zip(g).and_then(|(solver, g)| {
+            let bodies: Vec<OctreeBody> = bodies
+                .iter()
+                .map(|(entity, position, velocity, mass)| OctreeBody {
+                    position: position.value(),
+                    velocity: velocity.value(),
+                    mass: mass.value(),
+                    entity,
+                })
+                .collect();
+            solver.0.potential_energy(&bodies, **g)
+        });
+
+        metrics.potential_energy = potential_energy;
+        metrics.total_energy = potential_energy.map(|potential| kinetic_energy + potential);
+        if let Some(potential_energy) = potential_energy {
+            diagnostics.add_measurement(&Self::POTENTIAL_ENERGY, || potential_energy);
+            diagnostics.add_measurement(&Self::TOTAL_ENERGY, || kinetic_energy + potential_energy);
+        }
+    }
+
+    /// Record the timestep chosen by the physics systems
+    ///
+    /// In adaptive timestep mode this is the shortest sub-step of the last
+    /// tick; in fixed mode it is the tick length.
+    fn record_timestep(
+        physics_time: Option<Res<PhysicsTime>>,
+        mut metrics: ResMut<SimulationMetrics>,
+        mut diagnostics: Diagnostics,
+        state: Res<SimulationDiagnosticsState>,
+    ) {
+        if !state.update_timer.is_finished() {
+            return;
+        }
+
+        let Some(physics_time) = physics_time else {
+            return;
+        };
+
+        metrics.timestep = physics_time.current_dt;
+        metrics.substeps = physics_time.substeps;
+        diagnostics.add_measurement(&Self::TIMESTEP, || physics_time.current_dt);
+        diagnostics.add_measurement(&Self::SUBSTEPS, || physics_time.substeps as f64);
+
+        metrics.force_evaluations = physics_time.force_evaluations;
+        diagnostics.add_measurement(&Self::FORCE_EVALUATIONS, || physics_time.force_evaluations);
+
+        let report = physics_time.integrator_report;
+        metrics.integrator_step = report.step_size;
+        if let Some(step_size) = report.step_size {
+            diagnostics.add_measurement(&Self::INTEGRATOR_STEP, || step_size);
+        }
+
+        metrics.integrator_error = report.error_estimate;
+        if let Some(error_estimate) = report.error_estimate {
+            diagnostics.add_measurement(&Self::INTEGRATOR_ERROR, || error_estimate);
+        }
+
+        metrics.integrator_order = report.order;
+        if let Some(order) = report.order {
+            diagnostics.add_measurement(&Self::INTEGRATOR_ORDER, || order as f64);
+        }
+
+        metrics.integrator_unconverged = report.unconverged_steps;
+        if let Some(unconverged) = report.unconverged_steps {
+            diagnostics.add_measurement(&Self::INTEGRATOR_UNCONVERGED, || unconverged as f64);
+        }
+    }
+
+    /// Record the force error sample taken by the physics systems, in the
+    /// ticks when a new one was taken
+    fn record_force_error(
+        monitor: Option<Res<ForceErrorMonitor>>,
+        mut metrics: ResMut<SimulationMetrics>,
+        mut diagnostics: Diagnostics,
+    ) {
+        let Some(monitor) = monitor else {
+            return;
+        };
+        if !monitor.is_changed() || monitor.ticks_since_sample != 0 {
+            return;
+        }
+
+        metrics.force_error_rms = monitor.estimate.map(|estimate| estimate.rms_relative_error);
+        metrics.force_error_max = monitor.estimate.map(|estimate| estimate.max_relative_error);
+        metrics.theta = monitor.theta;
+        if let Some(estimate) = monitor.estimate {
+            diagnostics.add_measurement(&Self::FORCE_ERROR_RMS, || estimate.rms_relative_error);
+            diagnostics.add_measurement(&Self::FORCE_ERROR_MAX, || estimate.max_relative_error);
+        }
+        if let Some(theta) = monitor.theta {
+            diagnostics.add_measurement(&Self::THETA, || theta);
+        }
     }
 }
 
@@ -96,9 +339,21 @@ impl Plugin for SimulationDiagnosticsPlugin {
 
         self.register_diagnostics(app);
 
+        app.add_systems(
+            FixedUpdate,
+            Self::calculate_energy
+                .after(PhysicsSet::BuildGravitySolver)
+                .before(PhysicsSet::IntegrateMotions)
+                .run_if(in_state(AppState::Running)),
+        );
+
         app.add_systems(
             FixedPostUpdate,
-            (Self::update_timer_ticks, Self::calculate_kinetic_energy)
+            (
+                Self::update_timer_ticks,
+                Self::record_timestep,
+                Self::record_force_error,
+            )
                 .run_if(in_state(AppState::Running)),
         );
     }
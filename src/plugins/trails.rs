@@ -298,7 +298,9 @@ impl Plugin for TrailsPlugin {
             Update,
             (
                 Self::initialize_trails.in_set(TrailSet::Initialize),
-                Self::update_trails.in_set(TrailSet::Update),
+                (Self::handle_body_merges, Self::update_trails)
+                    .chain()
+                    .in_set(TrailSet::Update),
                 Self::render_trails.in_set(TrailSet::Render),
             )
                 .run_if(in_state(AppState::Running).or(in_state(AppState::Paused))),
@@ -312,7 +314,8 @@ impl Plugin for TrailsPlugin {
 
 impl TrailsPlugin {
     fn update_trails(
-        mut trail_query: Query<(&mut Trail, &TrackedBody), With<TrailRenderer>>,
+        mut commands: Commands,
+        mut trail_query: Query<(Entity, &mut Trail, &TrackedBody), With<TrailRenderer>>,
         body_query: Query<&Transform, With<PhysicsBody>>,
         time: Res<Time>,
         config: Res<SimulationConfig>,
@@ -321,7 +324,7 @@ impl TrailsPlugin {
         let current_time = time.elapsed_secs();
         let is_paused = matches!(app_state.get(), AppState::Paused);
 
-        for (mut trail, tracked_body) in trail_query.iter_mut() {
+        for (trail_entity, mut trail, tracked_body) in trail_query.iter_mut() {
             if is_paused {
                 trail.pause(current_time);
                 continue;
@@ -331,8 +334,10 @@ impl TrailsPlugin {
                 trail.unpause(current_time);
             }
 
+            let tracked_transform = body_query.get(tracked_body.0).ok();
+
             // Only add new points if we're tracking an active body
-            if let Ok(transform) = body_query.get(tracked_body.0)
+            if let Some(transform) = tracked_transform
                 && trail.should_update(current_time, config.trails.update_interval_seconds)
             {
                 trail.add_point(transform.translation, current_time);
@@ -344,6 +349,52 @@ impl TrailsPlugin {
                 config.trails.trail_length_seconds,
                 config.trails.max_points_per_trail,
             );
+
+            // Retire orphaned trails (e.g. of bodies absorbed in a merge) once they have faded out
+            if tracked_transform.is_none() && trail.points.is_empty() {
+                commands.entity(trail_entity).despawn();
+            }
+        }
+    }
+
+    /// Keeps the trail of a body that absorbed another in step with its new size and color
+    ///
+    /// The trail of the absorbed body is left to fade out and is retired by
+    /// `update_trails` once its last point has expired.
+    fn handle_body_merges(
+        mut merges: MessageReader<BodyMerged>,
+        mut trail_query: Query<
+            (&mut Trail, &TrackedBody, &MeshMaterial3d<StandardMaterial>),
+            With<TrailRenderer>,
+        >,
+        body_query: Query<(&Radius, &MeshMaterial3d<StandardMaterial>), With<PhysicsBody>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+    ) {
+        for merge in merges.read() {
+            let Ok((radius, body_material)) = body_query.get(merge.survivor) else {
+                continue;
+            };
+
+            let Some(color) = materials
+                .get(&body_material.0)
+                .map(|material| material.base_color)
+            else {
+                continue;
+            };
+
+            for (mut trail, tracked_body, trail_material) in trail_query.iter_mut() {
+                if tracked_body.0 != merge.survivor {
+                    continue;
+                }
+
+                trail.color = color;
+                trail.body_radius = radius.value() as f32;
+
+                if let Some(material) = materials.get_mut(&trail_material.0) {
+                    material.base_color = color;
+                    material.emissive = color.into();
+                }
+            }
         }
     }
 
@@ -5,6 +5,7 @@
 //! events to toggle visualization states.
 
 use crate::physics::aabb3d::Aabb3d;
+use crate::physics::resources::CurrentGravitySolver;
 use crate::plugins::diagnostics_hud::DiagnosticsHudSettings;
 use crate::prelude::*;
 use bevy::color::palettes::css;
@@ -107,10 +108,10 @@ fn handle_visualization_commands(
 }
 
 /// Visualizes the octree structure using debug gizmos
-/// Only runs when settings or octree changes to avoid unnecessary work
+/// Only runs when settings or the gravity solver changes to avoid unnecessary work
 fn visualize_octree(
     mut gizmos: Gizmos,
-    octree: Res<GravitationalOctree>,
+    gravity_solver: Res<CurrentGravitySolver>,
     settings: Res<OctreeVisualizationSettings>,
 ) {
     // Early exit if visualization is disabled
@@ -119,11 +120,11 @@ fn visualize_octree(
     }
 
     // Only process if settings or octree has changed
-    if !settings.is_changed() && !octree.is_changed() {
+    if !settings.is_changed() && !gravity_solver.is_changed() {
         return;
     }
 
-    for aabb in octree.bounds() {
+    for aabb in gravity_solver.0.bounds() {
         draw_bounding_box_wireframe_gizmo(&mut gizmos, &aabb, settings.line_color);
     }
 }
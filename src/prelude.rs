@@ -17,9 +17,7 @@ pub use crate::config::SimulationConfig;
 pub use crate::states::AppState;
 
 // Internal re-exports - Resources (most commonly used)
-pub use crate::resources::{
-    Barycenter, BodyCount, GravitationalConstant, GravitationalOctree, RenderingRng, SharedRng,
-};
+pub use crate::resources::{Barycenter, BodyCount, GravitationalConstant, RenderingRng, SharedRng};
 
 // Internal re-exports - Visualization
 pub use crate::plugins::visualization::{
@@ -30,6 +28,6 @@ pub use crate::plugins::visualization::{
 pub use crate::physics::octree::Octree;
 
 // Internal re-exports - Events
-pub use crate::messages::SimulationCommand;
+pub use crate::messages::{BodyMerged, SimulationCommand};
 
 // Note: Utility functions are kept crate-private and not re-exported in prelude
@@ -77,12 +77,3 @@ impl From<usize> for BodyCount {
 
 #[derive(Resource, Deref, DerefMut, Copy, Clone, Default, PartialEq, Debug)]
 pub struct Barycenter(pub Option<Vector>);
-
-#[derive(Resource, Deref, DerefMut)]
-pub struct GravitationalOctree(pub Octree);
-
-impl GravitationalOctree {
-    pub fn new(octree: Octree) -> Self {
-        Self(octree)
-    }
-}
@@ -0,0 +1,136 @@
+//! Block timesteps agree with a global timestep on a clustered system
+
+mod common;
+
+use common::{G, body, plummer_sphere, point_in_unit_ball};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::block_timestep::step_blocks;
+use stardrift::physics::integrators::{Integrator, SystemAccelerationField, VelocityVerlet};
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::Octree;
+use stardrift::physics::timestep::acceleration_criterion;
+
+const BODY_COUNT: usize = 64;
+const SOFTENING: Scalar = 0.05;
+const TICK: Scalar = 1.0 / 16.0;
+const TICKS: usize = 8;
+const MAX_LEVEL: u32 = 6;
+const ACCURACY: Scalar = 0.001;
+
+/// Octree-backed field, rebuilt from every position update
+struct OctreeField {
+    octree: Octree,
+    masses: Vec<Scalar>,
+}
+
+impl OctreeField {
+    fn new(masses: Vec<Scalar>) -> Self {
+        Self {
+            octree: Octree::new(0.5, SOFTENING, 1e6),
+            masses,
+        }
+    }
+}
+
+impl SystemAccelerationField for OctreeField {
+    fn update(&mut self, positions: &[Vector]) {
+        self.octree.build(
+            positions
+                .iter()
+                .zip(&self.masses)
+                .enumerate()
+                .map(|(i, (&position, &mass))| body(i, position, Vector::ZERO, mass)),
+        );
+    }
+
+    fn at(&self, index: usize, position: Vector) -> Vector {
+        let probe = body(index, position, Vector::ZERO, self.masses[index]);
+        self.octree
+            .calculate_force_at_position(probe.position, probe.mass, probe.entity, G)
+            / probe.mass
+    }
+}
+
+/// Plummer sphere of unit total mass with G = 1, each body moving in a
+/// random direction at up to half its local escape speed, which keeps the
+/// cluster bound without sampling the exact distribution function
+fn plummer_cluster(seed: u64) -> (Vec<Scalar>, Vec<Vector>, Vec<Vector>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+
+    let bodies = plummer_sphere(BODY_COUNT, seed);
+    let velocities = bodies
+        .iter()
+        .map(|body| {
+            let radius = body.position.length();
+            let escape_speed = (2.0 / (1.0 + radius * radius).sqrt()).sqrt();
+            point_in_unit_ball(&mut rng) * (0.5 * escape_speed)
+        })
+        .collect();
+
+    (
+        bodies.iter().map(|body| body.mass).collect(),
+        bodies.iter().map(|body| body.position).collect(),
+        velocities,
+    )
+}
+
+#[test]
+fn block_timesteps_match_global_timestep() {
+    let (masses, initial_positions, initial_velocities) = plummer_cluster(42);
+    let integrator = VelocityVerlet;
+
+    // Reference: every body takes the shortest block step
+    let mut global_positions = initial_positions.clone();
+    let mut global_velocities = initial_velocities.clone();
+    let mut field = OctreeField::new(masses.clone());
+    let finest = TICK / (1u64 << MAX_LEVEL) as Scalar;
+    for _ in 0..TICKS << MAX_LEVEL {
+        integrator.step_system(
+            &mut global_positions,
+            &mut global_velocities,
+            &mut field,
+            finest,
+        );
+    }
+
+    let mut block_positions = initial_positions;
+    let mut block_velocities = initial_velocities;
+    let mut field = OctreeField::new(masses);
+    let mut body_steps = 0;
+    let mut shortest_step = TICK;
+    for _ in 0..TICKS {
+        let report = step_blocks(
+            &integrator,
+            &mut block_positions,
+            &mut block_velocities,
+            &mut field,
+            TICK,
+            MAX_LEVEL,
+            |acceleration, _| acceleration_criterion(acceleration, ACCURACY, SOFTENING),
+        );
+        body_steps += report.body_steps;
+        shortest_step = shortest_step.min(report.shortest_step);
+    }
+
+    let max_error = block_positions
+        .iter()
+        .zip(&global_positions)
+        .map(|(block, global)| (*block - *global).length())
+        .fold(0.0, Scalar::max);
+    let global_steps = BODY_COUNT * (TICKS << MAX_LEVEL);
+
+    assert!(
+        body_steps < global_steps / 4,
+        "block timesteps should save most body steps ({body_steps} of {global_steps})"
+    );
+    assert!(
+        shortest_step < TICK,
+        "some bodies should need shorter blocks than the tick, shortest {shortest_step:.3e}"
+    );
+    assert!(
+        max_error < 1e-4,
+        "block positions drifted {max_error:.3e} from the global-step result"
+    );
+}
@@ -0,0 +1,82 @@
+//! `--list-integrators --format json` lists every registered integrator with
+//! its self-description
+
+use serde_json::Value;
+use stardrift::physics::integrators::IntegratorParameters;
+use stardrift::physics::integrators::registry::IntegratorRegistry;
+use std::process::Command;
+
+/// Integrators as listed by the binary, in the order listed
+fn listed_integrators() -> Vec<Value> {
+    let output = Command::new(env!("CARGO_BIN_EXE_stardrift"))
+        .args(["--list-integrators", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    match serde_json::from_slice(&output.stdout).unwrap() {
+        Value::Array(integrators) => integrators,
+        other => panic!("expected a JSON array, got {other}"),
+    }
+}
+
+#[test]
+fn json_listing_matches_the_registry() {
+    let registry = IntegratorRegistry::default();
+    let listed = listed_integrators();
+
+    let names: Vec<&str> = listed
+        .iter()
+        .map(|integrator| integrator["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, registry.list_available());
+
+    let expected = serde_json::to_value(registry.describe_available()).unwrap();
+    assert_eq!(Value::Array(listed), expected);
+}
+
+#[test]
+fn json_listing_describes_each_integrator() {
+    let registry = IntegratorRegistry::default();
+    let aliases = registry.list_aliases();
+
+    for listed in listed_integrators() {
+        let name = listed["name"].as_str().unwrap();
+        let integrator = registry.create(name, &IntegratorParameters::new()).unwrap();
+
+        assert_eq!(
+            listed["order"].as_u64(),
+            Some(integrator.convergence_order() as u64),
+            "{name}"
+        );
+        assert_eq!(
+            listed["symplectic"].as_bool(),
+            Some(integrator.is_symplectic()),
+            "{name}"
+        );
+        assert_eq!(
+            listed["time_reversible"].as_bool(),
+            Some(integrator.is_time_reversible()),
+            "{name}"
+        );
+        assert_eq!(
+            listed["force_evaluations"],
+            serde_json::to_value(integrator.force_evaluations()).unwrap(),
+            "{name}"
+        );
+        assert_eq!(
+            listed["description"].as_str(),
+            Some(integrator.description()),
+            "{name}"
+        );
+        assert!(!integrator.description().is_empty(), "{name}");
+
+        for alias in listed["aliases"].as_array().unwrap() {
+            let alias = alias.as_str().unwrap();
+            assert!(
+                aliases.contains(&(alias.to_string(), name.to_string())),
+                "{alias} should resolve to {name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Collision detection finds exactly the overlapping pairs and clusters,
+//! merging conserves mass, momentum, and the centre of mass, and bouncing
+//! conserves momentum while pushing overlapping bodies apart
+
+mod common;
+
+use bevy::ecs::entity::Entity;
+use common::random_vector;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::collisions::{
+    CollisionBody, bounce, find_overlapping_pairs, group_overlapping, merge_bodies,
+};
+use stardrift::physics::math::{Scalar, Vector};
+
+/// Bodies of varied size scattered through a cube of half-width `extent`
+fn random_bodies(count: usize, extent: Scalar, seed: u64) -> Vec<CollisionBody> {
+    // Velocities and radii come from a separate stream of the same seed
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+
+    common::random_bodies(count, seed)
+        .into_iter()
+        .map(|body| CollisionBody {
+            entity: body.entity,
+            position: body.position * extent,
+            velocity: random_vector(&mut rng, 1.0),
+            mass: body.mass,
+            radius: rng.random_range(0.05..0.6),
+        })
+        .collect()
+}
+
+fn brute_force_pairs(bodies: &[CollisionBody]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            if bodies[i].overlaps(&bodies[j]) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+#[test]
+fn sort_and_sweep_finds_the_brute_force_pairs() {
+    for (count, extent, seed) in [(500, 10.0, 1), (500, 4.0, 2), (50, 1.0, 3), (1, 1.0, 4)] {
+        let bodies = random_bodies(count, extent, seed);
+
+        let mut pairs = find_overlapping_pairs(&bodies);
+        pairs.sort_unstable();
+        let expected = brute_force_pairs(&bodies);
+
+        assert!(
+            count < 50 || !expected.is_empty(),
+            "{count} bodies in ±{extent} should overlap somewhere"
+        );
+        assert_eq!(pairs, expected, "{count} bodies in ±{extent}, seed {seed}");
+    }
+
+    assert!(find_overlapping_pairs(&[]).is_empty());
+}
+
+#[test]
+fn chains_of_contacts_form_one_cluster() {
+    // A-B and C-D touch first, then B-C joins the two chains; E-F is a
+    // separate contact, and G touches nothing
+    let pairs = [(0, 1), (2, 3), (1, 2), (4, 5)];
+
+    let mut clusters = group_overlapping(7, &pairs);
+    for cluster in &mut clusters {
+        cluster.sort_unstable();
+    }
+    clusters.sort_unstable();
+
+    assert_eq!(clusters, vec![vec![0, 1, 2, 3], vec![4, 5]]);
+    assert!(group_overlapping(3, &[]).is_empty());
+}
+
+#[test]
+fn merging_conserves_mass_momentum_and_centre_of_mass() {
+    let bodies = random_bodies(5, 1.0, 5);
+
+    let merged = merge_bodies(&bodies);
+
+    let mass: Scalar = bodies.iter().map(|body| body.mass).sum();
+    let momentum: Vector = bodies.iter().map(|body| body.velocity * body.mass).sum();
+    let centre_of_mass = bodies
+        .iter()
+        .map(|body| body.position * body.mass)
+        .sum::<Vector>()
+        / mass;
+    let volume: Scalar = bodies.iter().map(|body| body.radius.powi(3)).sum();
+
+    assert!((merged.mass - mass).abs() < 1e-12 * mass);
+    assert!((merged.velocity * merged.mass - momentum).length() < 1e-12 * mass);
+    assert!((merged.position - centre_of_mass).length() < 1e-12);
+    assert!((merged.radius.powi(3) - volume).abs() < 1e-12 * volume);
+
+    // A lone body merges into itself
+    let alone = merge_bodies(&bodies[..1]);
+    assert!((alone.position - bodies[0].position).length() < 1e-12);
+    assert!((alone.velocity - bodies[0].velocity).length() < 1e-12);
+    assert!((alone.radius - bodies[0].radius).abs() < 1e-12);
+}
+
+/// Overlapping pairs of unequal bodies on random oblique approaches
+fn approaching_pairs(count: usize, seed: u64) -> Vec<(CollisionBody, CollisionBody)> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..count)
+        .map(|i| {
+            let a = CollisionBody {
+                entity: Entity::from_raw_u32(2 * i as u32).unwrap(),
+                position: random_vector(&mut rng, 1.0),
+                velocity: random_vector(&mut rng, 1.0),
+                mass: rng.random_range(0.1..10.0),
+                radius: rng.random_range(0.1..1.0),
+            };
+            let normal = random_vector(&mut rng, 1.0).normalize();
+            let depth = rng.random_range(0.1..0.9);
+            let b = CollisionBody {
+                entity: Entity::from_raw_u32(2 * i as u32 + 1).unwrap(),
+                position: a.position + normal * (a.radius * (1.0 + depth)),
+                velocity: a.velocity - normal * rng.random_range(0.1..2.0)
+                    + random_vector(&mut rng, 1.0).reject_from(normal),
+                mass: rng.random_range(0.1..10.0),
+                radius: a.radius,
+            };
+            (a, b)
+        })
+        .collect()
+}
+
+fn momentum(a: &CollisionBody, b: &CollisionBody) -> Vector {
+    a.velocity * a.mass + b.velocity * b.mass
+}
+
+fn kinetic_energy(a: &CollisionBody, b: &CollisionBody) -> Scalar {
+    0.5 * (a.mass * a.velocity.length_squared() + b.mass * b.velocity.length_squared())
+}
+
+#[test]
+fn bouncing_conserves_momentum() {
+    for restitution in [0.0, 0.5, 1.0] {
+        for (mut a, mut b) in approaching_pairs(20, 6) {
+            let before = momentum(&a, &b);
+
+            bounce(&mut a, &mut b, restitution);
+
+            let scale = before.length().max(a.mass + b.mass);
+            assert!(
+                (momentum(&a, &b) - before).length() < 1e-12 * scale,
+                "momentum changed with restitution {restitution}"
+            );
+        }
+    }
+}
+
+#[test]
+fn elastic_bounces_conserve_kinetic_energy() {
+    for (mut a, mut b) in approaching_pairs(20, 7) {
+        let before = kinetic_energy(&a, &b);
+
+        bounce(&mut a, &mut b, 1.0);
+
+        let after = kinetic_energy(&a, &b);
+        assert!(
+            (after - before).abs() < 1e-12 * before,
+            "kinetic energy {before} became {after}"
+        );
+    }
+}
+
+#[test]
+fn inelastic_bounces_leave_no_approach_velocity() {
+    for (mut a, mut b) in approaching_pairs(20, 8) {
+        let normal = (b.position - a.position).normalize();
+        let before = kinetic_energy(&a, &b);
+
+        bounce(&mut a, &mut b, 0.0);
+
+        let approach_speed = (b.velocity - a.velocity).dot(normal);
+        assert!(
+            approach_speed.abs() < 1e-12,
+            "bodies still approach at {approach_speed} along the normal"
+        );
+        assert!(kinetic_energy(&a, &b) < before);
+    }
+}
+
+#[test]
+fn bouncing_separates_overlapping_bodies() {
+    for (mut a, mut b) in approaching_pairs(20, 9) {
+        let mass = a.mass + b.mass;
+        let centre_of_mass = (a.position * a.mass + b.position * b.mass) / mass;
+
+        bounce(&mut a, &mut b, 0.5);
+
+        let distance = a.position.distance(b.position);
+        assert!(
+            (distance - (a.radius + b.radius)).abs() < 1e-12,
+            "bodies are {distance} apart, should just touch at {}",
+            a.radius + b.radius
+        );
+        let moved = (a.position * a.mass + b.position * b.mass) / mass;
+        assert!((moved - centre_of_mass).length() < 1e-12);
+    }
+}
+
+#[test]
+fn separating_bodies_keep_their_velocities() {
+    for (mut a, mut b) in approaching_pairs(20, 10) {
+        // Reverse the relative velocity so the overlapping bodies already
+        // move apart along the normal
+        let relative = b.velocity - a.velocity;
+        b.velocity = a.velocity - relative;
+        let (velocity_a, velocity_b) = (a.velocity, b.velocity);
+
+        bounce(&mut a, &mut b, 1.0);
+
+        assert_eq!(a.velocity, velocity_a);
+        assert_eq!(b.velocity, velocity_b);
+    }
+}
+
+#[test]
+fn bodies_apart_are_left_alone() {
+    let (mut a, mut b) = approaching_pairs(1, 11).remove(0);
+    b.position = a.position + Vector::X * (a.radius + b.radius + 0.01);
+    let (before_a, before_b) = (a, b);
+
+    bounce(&mut a, &mut b, 1.0);
+
+    assert_eq!(
+        (a.position, a.velocity),
+        (before_a.position, before_a.velocity)
+    );
+    assert_eq!(
+        (b.position, b.velocity),
+        (before_b.position, before_b.velocity)
+    );
+}
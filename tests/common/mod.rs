@@ -0,0 +1,119 @@
+//! Fixtures shared by the gravity solver integration tests
+
+#![allow(dead_code)]
+
+use bevy::ecs::entity::Entity;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::gravity::{DirectSummation, GravitySolver};
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::OctreeBody;
+
+pub const G: Scalar = 1.0;
+pub const MIN_DISTANCE: Scalar = 1e-3;
+pub const MAX_FORCE: Scalar = 1e12;
+
+/// Body number `index`, whose entity is built from the index
+pub fn body(index: usize, position: Vector, velocity: Vector, mass: Scalar) -> OctreeBody {
+    OctreeBody {
+        position,
+        velocity,
+        mass,
+        entity: Entity::from_raw_u32(index as u32).unwrap(),
+    }
+}
+
+/// Vector with each component uniform in [-scale, scale)
+pub fn random_vector(rng: &mut ChaCha8Rng, scale: Scalar) -> Vector {
+    Vector::new(
+        rng.random_range(-scale..scale),
+        rng.random_range(-scale..scale),
+        rng.random_range(-scale..scale),
+    )
+}
+
+/// Point uniformly distributed in the unit ball
+pub fn point_in_unit_ball(rng: &mut ChaCha8Rng) -> Vector {
+    loop {
+        let candidate = Vector::new(
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+        );
+        if candidate.length_squared() <= 1.0 {
+            break candidate;
+        }
+    }
+}
+
+/// Bodies uniformly filling a unit sphere, with masses spread over two decades
+pub fn uniform_sphere(count: usize, seed: u64) -> Vec<OctreeBody> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..count)
+        .map(|i| {
+            let position = point_in_unit_ball(&mut rng);
+            body(i, position, Vector::ZERO, rng.random_range(0.01..1.0))
+        })
+        .collect()
+}
+
+/// Bodies uniformly filling a cube of side two, with masses spread over a decade
+pub fn random_bodies(count: usize, seed: u64) -> Vec<OctreeBody> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..count)
+        .map(|i| {
+            let position = random_vector(&mut rng, 1.0);
+            body(i, position, Vector::ZERO, rng.random_range(0.1..1.0))
+        })
+        .collect()
+}
+
+/// Equal-mass Plummer sphere of unit scale radius and total mass, cut off at
+/// ten scale radii
+pub fn plummer_sphere(count: usize, seed: u64) -> Vec<OctreeBody> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..count)
+        .map(|i| {
+            let radius = loop {
+                let mass_fraction: Scalar = rng.random_range(0.0..1.0);
+                let radius = (mass_fraction.powf(-2.0 / 3.0) - 1.0).powf(-0.5);
+                if radius < 10.0 {
+                    break radius;
+                }
+            };
+            let cos_polar: Scalar = rng.random_range(-1.0..1.0);
+            let sin_polar = (1.0 - cos_polar * cos_polar).sqrt();
+            let azimuth: Scalar = rng.random_range(0.0..std::f64::consts::TAU);
+            let direction = Vector::new(
+                sin_polar * azimuth.cos(),
+                sin_polar * azimuth.sin(),
+                cos_polar,
+            );
+
+            body(i, direction * radius, Vector::ZERO, 1.0 / count as Scalar)
+        })
+        .collect()
+}
+
+/// Forces on `bodies` by direct summation
+pub fn exact_forces(bodies: &[OctreeBody]) -> Vec<Vector> {
+    let mut direct = DirectSummation::new(MIN_DISTANCE, MAX_FORCE);
+    direct.build(bodies);
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    direct.forces(bodies, G, &mut forces);
+    forces
+}
+
+/// Root-mean-square relative error of `forces` against `exact`
+pub fn rms_relative_error(forces: &[Vector], exact: &[Vector]) -> Scalar {
+    let mean_square = forces
+        .iter()
+        .zip(exact)
+        .map(|(force, exact)| (*force - *exact).length_squared() / exact.length_squared())
+        .sum::<Scalar>()
+        / forces.len() as Scalar;
+    mean_square.sqrt()
+}
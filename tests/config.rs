@@ -0,0 +1,94 @@
+//! Settings that deserialize but cannot be simulated are rejected on load
+
+use stardrift::config::{ForceErrorConfig, SimulationConfig, TimestepConfig};
+use stardrift::physics::block_timestep::MAX_BLOCK_LEVEL;
+
+/// Load a configuration from TOML written to a temporary file
+fn load(name: &str, content: &str) -> SimulationConfig {
+    let path = std::env::temp_dir().join(format!("stardrift-{}-{name}.toml", std::process::id()));
+    std::fs::write(&path, content).unwrap();
+    let config = SimulationConfig::load_or_default(path.to_str().unwrap());
+    std::fs::remove_file(path).unwrap();
+    config
+}
+
+#[test]
+fn default_config_is_valid() {
+    assert!(SimulationConfig::default().validate().is_ok());
+}
+
+#[test]
+fn max_block_level_is_bounded() {
+    let timestep = TimestepConfig {
+        max_block_level: MAX_BLOCK_LEVEL,
+        ..Default::default()
+    };
+    assert!(timestep.validate().is_ok());
+
+    let timestep = TimestepConfig {
+        max_block_level: MAX_BLOCK_LEVEL + 1,
+        ..Default::default()
+    };
+    assert!(timestep.validate().is_err());
+}
+
+#[test]
+fn force_error_settings_are_checked() {
+    let valid = ForceErrorConfig::default();
+    assert!(valid.validate().is_ok());
+
+    let invalid = [
+        ForceErrorConfig {
+            min_theta: 1.0,
+            max_theta: 0.5,
+            ..valid.clone()
+        },
+        ForceErrorConfig {
+            min_theta: 0.0,
+            ..valid.clone()
+        },
+        ForceErrorConfig {
+            target_error: 0.0,
+            ..valid.clone()
+        },
+        ForceErrorConfig {
+            target_frame_time: -1.0,
+            ..valid.clone()
+        },
+        ForceErrorConfig {
+            interval: 0,
+            ..valid.clone()
+        },
+        ForceErrorConfig {
+            sample_size: 0,
+            ..valid.clone()
+        },
+    ];
+    for config in invalid {
+        assert!(config.validate().is_err(), "{config:?} should be rejected");
+    }
+}
+
+#[test]
+fn invalid_config_falls_back_to_defaults() {
+    let config = load(
+        "max-block-level",
+        "[physics.timestep]\nmode = \"block\"\nmax_block_level = 64\n",
+    );
+    assert_eq!(
+        config.physics.timestep.max_block_level,
+        TimestepConfig::default().max_block_level
+    );
+
+    let config = load(
+        "theta-bounds",
+        "[physics.force_error]\nmin_theta = 1.0\nmax_theta = 0.5\n",
+    );
+    assert_eq!(
+        config.physics.force_error.min_theta,
+        ForceErrorConfig::default().min_theta
+    );
+
+    let config = load("block-level", "[physics.timestep]\nmax_block_level = 12\n");
+    assert_eq!(config.physics.timestep.max_block_level, 12);
+}
@@ -0,0 +1,90 @@
+//! The fast multipole method converges on direct summation as the order grows
+
+mod common;
+
+use common::{G, MAX_FORCE, MIN_DISTANCE, exact_forces, rms_relative_error, uniform_sphere};
+use stardrift::physics::gravity::{FastMultipole, GravitySolver};
+use stardrift::physics::math::{Scalar, Vector};
+
+const BODY_COUNT: usize = 2_000;
+
+#[test]
+fn error_falls_with_expansion_order() {
+    let bodies = uniform_sphere(BODY_COUNT, 3);
+    let exact = exact_forces(&bodies);
+
+    let mut previous = Scalar::INFINITY;
+    for order in 2..=4 {
+        let mut fmm = FastMultipole::new(order, 0.5, 8, MIN_DISTANCE, MAX_FORCE).unwrap();
+        fmm.build(&bodies);
+        let mut forces = vec![Vector::ZERO; bodies.len()];
+        fmm.forces(&bodies, G, &mut forces);
+
+        let error = rms_relative_error(&forces, &exact);
+        assert!(error < 1e-2, "order {order} error {error:.3e} is too large");
+        assert!(
+            error < previous,
+            "order {order} error {error:.3e} should be below order {} error {previous:.3e}",
+            order - 1
+        );
+        previous = error;
+    }
+}
+
+#[test]
+fn single_body_queries_match_direct_summation() {
+    let bodies = uniform_sphere(BODY_COUNT, 5);
+    let exact = exact_forces(&bodies);
+
+    let mut fmm = FastMultipole::new(4, 0.5, 8, MIN_DISTANCE, MAX_FORCE).unwrap();
+    fmm.build(&bodies);
+
+    let forces: Vec<Vector> = bodies
+        .iter()
+        .map(|body| fmm.force_at(body.position, body.mass, body.entity, G))
+        .collect();
+    let error = rms_relative_error(&forces, &exact);
+    assert!(error < 1e-2, "force_at error {error:.3e} is too large");
+}
+
+#[test]
+fn small_opening_angle_approaches_direct_summation() {
+    let bodies = uniform_sphere(BODY_COUNT, 7);
+    let exact = exact_forces(&bodies);
+
+    let mut fmm = FastMultipole::new(4, 0.2, 8, MIN_DISTANCE, MAX_FORCE).unwrap();
+    fmm.build(&bodies);
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    fmm.forces(&bodies, G, &mut forces);
+
+    let error = rms_relative_error(&forces, &exact);
+    assert!(error < 1e-5, "error {error:.3e} at theta 0.2 is too large");
+}
+
+#[test]
+fn whole_system_forces_conserve_momentum() {
+    let bodies = uniform_sphere(BODY_COUNT, 9);
+
+    let mut fmm = FastMultipole::new(3, 0.5, 8, MIN_DISTANCE, MAX_FORCE).unwrap();
+    fmm.build(&bodies);
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    fmm.forces(&bodies, G, &mut forces);
+
+    // Mutual interactions apply equal and opposite forces to both sides
+    let net: Vector = forces.iter().copied().sum();
+    let scale: Scalar = forces.iter().map(|force| force.length()).sum();
+    assert!(
+        net.length() < 1e-10 * scale,
+        "net force {:.3e} should vanish relative to {scale:.3e}",
+        net.length()
+    );
+}
+
+#[test]
+fn rejects_invalid_settings() {
+    assert!(FastMultipole::new(1, 0.5, 8, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(FastMultipole::new(5, 0.5, 8, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(FastMultipole::new(3, 0.0, 8, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(FastMultipole::new(3, 1.0, 8, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(FastMultipole::new(3, 0.5, 0, MIN_DISTANCE, MAX_FORCE).is_err());
+}
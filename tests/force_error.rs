@@ -0,0 +1,160 @@
+//! Sampled force errors match the full comparison against direct summation,
+//! and theta tuning settles on the target error within its bounds
+
+mod common;
+
+use common::{G, MAX_FORCE, MIN_DISTANCE, exact_forces, plummer_sphere, rms_relative_error};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::gravity::{
+    DirectSummation, ForceErrorEstimate, GravitySolver, ThetaAutoTune, ThetaTuner,
+    estimate_force_error,
+};
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{Octree, OctreeBody};
+
+const BODY_COUNT: usize = 2_000;
+
+fn direct(bodies: &[OctreeBody]) -> DirectSummation {
+    let mut direct = DirectSummation::new(MIN_DISTANCE, MAX_FORCE);
+    direct.build(bodies);
+    direct
+}
+
+fn octree(bodies: &[OctreeBody], theta: Scalar) -> Octree {
+    let mut octree = Octree::new(theta, MIN_DISTANCE, MAX_FORCE);
+    octree.build(bodies.iter().copied());
+    octree
+}
+
+fn tuner(target: ThetaAutoTune) -> ThetaTuner {
+    ThetaTuner {
+        target,
+        target_error: 1e-3,
+        target_frame_time: 1.0 / 60.0,
+        min_theta: 0.2,
+        max_theta: 1.2,
+    }
+}
+
+#[test]
+fn sampling_every_body_matches_the_full_comparison() {
+    let bodies = plummer_sphere(BODY_COUNT, 1);
+    let direct = direct(&bodies);
+    let octree = octree(&bodies, 0.7);
+
+    let exact = exact_forces(&bodies);
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    octree.forces(&bodies, G, &mut forces);
+    let rms = rms_relative_error(&forces, &exact);
+    let max = forces
+        .iter()
+        .zip(&exact)
+        .map(|(force, exact)| (*force - *exact).length() / exact.length())
+        .fold(0.0, Scalar::max);
+
+    let all: Vec<usize> = (0..bodies.len()).collect();
+    let estimate = estimate_force_error(&octree, &direct, &bodies, &all, G).unwrap();
+
+    assert_eq!(estimate.sample_count, bodies.len());
+    assert!((estimate.rms_relative_error - rms).abs() < 1e-6 * rms);
+    assert!((estimate.max_relative_error - max).abs() < 1e-6 * max);
+
+    // A small random sample lands near the full RMS error
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let sample = rand::seq::index::sample(&mut rng, bodies.len(), 64).into_vec();
+    let sampled = estimate_force_error(&octree, &direct, &bodies, &sample, G).unwrap();
+    assert!(
+        sampled.rms_relative_error > 0.5 * rms && sampled.rms_relative_error < 2.0 * rms,
+        "RMS error {:.3e} over 64 bodies should be near {rms:.3e} over every body",
+        sampled.rms_relative_error
+    );
+    assert!(sampled.max_relative_error <= max);
+}
+
+#[test]
+fn exact_solvers_have_no_error_and_lone_bodies_are_skipped() {
+    let bodies = plummer_sphere(BODY_COUNT, 3);
+    let direct = direct(&bodies);
+    let exact = octree(&bodies, 0.0);
+
+    let estimate = estimate_force_error(&exact, &direct, &bodies, &[0, 10, 100], G).unwrap();
+    assert!(estimate.max_relative_error < 1e-10);
+
+    let lone = &bodies[..1];
+    let lone_direct = self::direct(lone);
+    assert_eq!(
+        estimate_force_error(&octree(lone, 0.5), &lone_direct, lone, &[0], G),
+        None
+    );
+}
+
+#[test]
+fn tuning_settles_on_the_target_error() {
+    let bodies = plummer_sphere(BODY_COUNT, 4);
+    let direct = direct(&bodies);
+    let sample: Vec<usize> = (0..bodies.len()).step_by(10).collect();
+    let tuner = tuner(ThetaAutoTune::ForceError);
+
+    for initial_theta in [0.2, 1.2] {
+        let mut octree = octree(&bodies, initial_theta);
+        let mut estimate = None;
+        for _ in 0..20 {
+            estimate = estimate_force_error(&octree, &direct, &bodies, &sample, G);
+            let theta = tuner.next_theta(octree.theta, estimate.as_ref(), None);
+            GravitySolver::set_theta(&mut octree, theta);
+        }
+
+        let error = estimate.unwrap().rms_relative_error;
+        assert!(
+            error > 0.5 * tuner.target_error && error < 2.0 * tuner.target_error,
+            "from θ = {initial_theta}: RMS error {error:.3e} at θ = {:.3} should settle near \
+             {:.1e}",
+            octree.theta,
+            tuner.target_error
+        );
+    }
+}
+
+#[test]
+fn tuning_stays_within_bounds_and_limits_each_step() {
+    let tuner = tuner(ThetaAutoTune::ForceError);
+    let estimate = |error| ForceErrorEstimate {
+        rms_relative_error: error,
+        max_relative_error: error,
+        sample_count: 1,
+    };
+
+    // Far too accurate: theta grows by at most a quarter, up to its maximum
+    assert_eq!(tuner.next_theta(0.5, Some(&estimate(1e-9)), None), 0.625);
+    assert_eq!(tuner.next_theta(1.1, Some(&estimate(0.0)), None), 1.2);
+    // Far too inaccurate: theta shrinks, down to its minimum
+    assert_eq!(tuner.next_theta(0.5, Some(&estimate(1.0)), None), 0.4);
+    assert_eq!(tuner.next_theta(0.21, Some(&estimate(1.0)), None), 0.2);
+    // Without a sample, theta is left alone
+    assert_eq!(tuner.next_theta(0.5, None, Some(1.0)), 0.5);
+
+    // Frames slower than the target raise theta, faster ones lower it
+    let tuner = self::tuner(ThetaAutoTune::FrameTime);
+    assert!(tuner.next_theta(0.5, None, Some(1.2 / 60.0)) > 0.5);
+    assert!(tuner.next_theta(0.5, None, Some(0.8 / 60.0)) < 0.5);
+    assert_eq!(tuner.next_theta(0.5, None, Some(1.0 / 60.0)), 0.5);
+    assert_eq!(tuner.next_theta(0.5, Some(&estimate(1.0)), None), 0.5);
+
+    // A zero error against a zero target gives no direction
+    let tuner = ThetaTuner {
+        target_error: 0.0,
+        ..self::tuner(ThetaAutoTune::ForceError)
+    };
+    assert_eq!(tuner.next_theta(0.5, Some(&estimate(0.0)), None), 0.5);
+    // Crossed bounds resolve to the minimum rather than panicking
+    let tuner = ThetaTuner {
+        min_theta: 1.0,
+        max_theta: 0.5,
+        ..self::tuner(ThetaAutoTune::ForceError)
+    };
+    assert_eq!(tuner.next_theta(0.7, Some(&estimate(1e-3)), None), 1.0);
+
+    let tuner = self::tuner(ThetaAutoTune::Off);
+    assert_eq!(tuner.next_theta(0.5, Some(&estimate(1.0)), Some(1.0)), 0.5);
+}
@@ -0,0 +1,676 @@
+//! Integrators converge at their stated order on a Kepler orbit, the
+//! symplectic ones keep the energy error bounded over many orbits, Hermite
+//! keeps its energy drift small, adaptive ones become more accurate as their
+//! tolerance is tightened and start afresh after a reset, as does the
+//! unconverged step count of Gauss-Legendre, and IAS15 follows the orbit to
+//! machine precision. Coefficient-driven compositions are checked for
+//! consistency, order, and symmetry. Wisdom-Holman, which solves the Kepler
+//! orbit exactly, is checked on planets perturbing each other instead.
+
+use stardrift::physics::integrators::{
+    BulirschStoer, Composition, DormandPrince, GaussLegendre4, GaussLegendre6, Hermite4, Ias15,
+    Integrator, IntegratorParameters, SystemAccelerationField, VelocityVerlet, WisdomHolman,
+    WisdomHolmanCoordinates, Yoshida4, Yoshida6, Yoshida8,
+};
+use stardrift::physics::math::{Scalar, Vector};
+use std::f64::consts::TAU;
+
+/// Eccentricity of the test orbit
+const ECCENTRICITY: Scalar = 0.5;
+
+/// Masses of the two bodies, summing to one so that with G = 1 and a unit
+/// semi-major axis the period is 2π
+const MASSES: [Scalar; 2] = [0.8, 0.2];
+
+/// Period of the binary orbit
+const PERIOD: Scalar = TAU;
+
+/// Time over which convergence is measured, deliberately not a whole period,
+/// where errors of some methods cancel and they appear to converge faster
+const DURATION: Scalar = 2.0;
+
+/// Orbits over which the energy error is followed
+const ORBITS: usize = 100;
+
+/// Point masses attracting each other with G = 1 and no softening
+struct PointMasses {
+    gravitational_parameters: Vec<Scalar>,
+    positions: Vec<Vector>,
+    velocities: Vec<Vector>,
+}
+
+impl PointMasses {
+    fn new(gravitational_parameters: &[Scalar]) -> Self {
+        Self {
+            gravitational_parameters: gravitational_parameters.to_vec(),
+            positions: vec![Vector::ZERO; gravitational_parameters.len()],
+            velocities: vec![Vector::ZERO; gravitational_parameters.len()],
+        }
+    }
+}
+
+impl SystemAccelerationField for PointMasses {
+    fn update(&mut self, positions: &[Vector]) {
+        self.positions.copy_from_slice(positions);
+    }
+
+    fn update_with_velocities(&mut self, positions: &[Vector], velocities: &[Vector]) {
+        self.positions.copy_from_slice(positions);
+        self.velocities.copy_from_slice(velocities);
+    }
+
+    fn at_with_jerk(&self, index: usize, position: Vector, velocity: Vector) -> (Vector, Vector) {
+        self.positions
+            .iter()
+            .zip(&self.velocities)
+            .zip(&self.gravitational_parameters)
+            .enumerate()
+            .filter(|&(j, _)| j != index)
+            .map(|(_, ((&source, &source_velocity), &mu))| {
+                let offset = source - position;
+                let relative_velocity = source_velocity - velocity;
+                let r = offset.length();
+                let r3 = r * r * r;
+                let acceleration = offset * (mu / r3);
+                let jerk = relative_velocity * (mu / r3)
+                    - offset * (3.0 * mu * offset.dot(relative_velocity) / (r3 * r * r));
+                (acceleration, jerk)
+            })
+            .fold((Vector::ZERO, Vector::ZERO), |(a, j), (da, dj)| {
+                (a + da, j + dj)
+            })
+    }
+
+    fn at(&self, index: usize, position: Vector) -> Vector {
+        self.positions
+            .iter()
+            .zip(&self.gravitational_parameters)
+            .enumerate()
+            .filter(|&(j, _)| j != index)
+            .map(|(_, (&source, &mu))| {
+                let offset = source - position;
+                let r = offset.length();
+                offset * (mu / (r * r * r))
+            })
+            .sum()
+    }
+
+    fn gravitational_parameters(&self) -> Option<&[Scalar]> {
+        Some(&self.gravitational_parameters)
+    }
+}
+
+/// Binary of unit semi-major axis starting at pericentre, in the frame of
+/// its centre of mass
+fn binary() -> (Vec<Vector>, Vec<Vector>) {
+    let separation = Vector::new(1.0 - ECCENTRICITY, 0.0, 0.0);
+    let relative_velocity = Vector::new(
+        0.0,
+        ((1.0 + ECCENTRICITY) / (1.0 - ECCENTRICITY)).sqrt(),
+        0.0,
+    );
+    split_relative(separation, relative_velocity)
+}
+
+/// Positions and velocities of both bodies from their separation and
+/// relative velocity, with the centre of mass at rest at the origin
+fn split_relative(separation: Vector, relative_velocity: Vector) -> (Vec<Vector>, Vec<Vector>) {
+    let [first, second] = MASSES;
+    (
+        vec![-separation * second, separation * first],
+        vec![-relative_velocity * second, relative_velocity * first],
+    )
+}
+
+/// Exact state of the binary after `duration`, from Kepler's equation
+fn exact_binary(duration: Scalar) -> (Vec<Vector>, Vec<Vector>) {
+    // With a unit semi-major axis and total mass the mean motion is one, so
+    // the mean anomaly is the time since pericentre
+    let mut eccentric_anomaly = duration;
+    for _ in 0..50 {
+        eccentric_anomaly -=
+            (eccentric_anomaly - ECCENTRICITY * eccentric_anomaly.sin() - duration)
+                / (1.0 - ECCENTRICITY * eccentric_anomaly.cos());
+    }
+
+    let (sin, cos) = eccentric_anomaly.sin_cos();
+    let minor = (1.0 - ECCENTRICITY * ECCENTRICITY).sqrt();
+    let rate = 1.0 / (1.0 - ECCENTRICITY * cos);
+    split_relative(
+        Vector::new(cos - ECCENTRICITY, minor * sin, 0.0),
+        Vector::new(-sin * rate, minor * cos * rate, 0.0),
+    )
+}
+
+fn energy(masses: &[Scalar], positions: &[Vector], velocities: &[Vector]) -> Scalar {
+    let kinetic: Scalar = masses
+        .iter()
+        .zip(velocities)
+        .map(|(&mass, velocity)| 0.5 * mass * velocity.length_squared())
+        .sum();
+    let potential: Scalar = (0..masses.len())
+        .flat_map(|i| (i + 1..masses.len()).map(move |j| (i, j)))
+        .map(|(i, j)| -masses[i] * masses[j] / positions[i].distance(positions[j]))
+        .sum();
+    kinetic + potential
+}
+
+/// Integrate the binary through `duration` in `steps` equal steps
+fn integrate_binary(
+    integrator: &dyn Integrator,
+    duration: Scalar,
+    steps: usize,
+) -> (Vec<Vector>, Vec<Vector>) {
+    let (mut positions, mut velocities) = binary();
+    let mut field = PointMasses::new(&MASSES);
+    let dt = duration / steps as Scalar;
+    for _ in 0..steps {
+        integrator.step_system(&mut positions, &mut velocities, &mut field, dt);
+    }
+    (positions, velocities)
+}
+
+/// Largest position error of either body after `duration` in `steps` steps
+fn binary_error(integrator: &dyn Integrator, duration: Scalar, steps: usize) -> Scalar {
+    let (positions, _) = integrate_binary(integrator, duration, steps);
+    let (exact, _) = exact_binary(duration);
+    positions
+        .iter()
+        .zip(&exact)
+        .map(|(position, exact)| position.distance(*exact))
+        .fold(0.0, Scalar::max)
+}
+
+/// Relative energy error of the binary sampled after every step
+fn energy_errors(integrator: &dyn Integrator, dt: Scalar, steps: usize) -> Vec<Scalar> {
+    let (mut positions, mut velocities) = binary();
+    let mut field = PointMasses::new(&MASSES);
+    let initial = energy(&MASSES, &positions, &velocities);
+
+    (0..steps)
+        .map(|_| {
+            integrator.step_system(&mut positions, &mut velocities, &mut field, dt);
+            ((energy(&MASSES, &positions, &velocities) - initial) / initial).abs()
+        })
+        .collect()
+}
+
+/// Star of unit mass with two planets of a thousandth of its mass on
+/// slightly eccentric, slightly inclined orbits, in the frame of the centre
+/// of mass
+fn planetary_system() -> (Vec<Scalar>, Vec<Vector>, Vec<Vector>) {
+    let masses = vec![1.0, 1e-3, 1e-3];
+    // Planet at `radius` with `speed_factor` times the circular speed
+    let planet = |radius: Scalar, speed_factor: Scalar, inclination: Scalar| {
+        let speed = speed_factor / radius.sqrt();
+        (
+            Vector::new(radius, 0.0, 0.0),
+            Vector::new(0.0, inclination.cos(), inclination.sin()) * speed,
+        )
+    };
+    let (inner_position, inner_velocity) = planet(1.0, 1.05, 0.02);
+    let (outer_position, outer_velocity) = planet(1.6, 0.97, -0.03);
+    let mut positions = vec![Vector::ZERO, inner_position, -outer_position];
+    let mut velocities = vec![Vector::ZERO, inner_velocity, -outer_velocity];
+
+    let total: Scalar = masses.iter().sum();
+    let weighted_mean = |values: &[Vector]| {
+        values
+            .iter()
+            .zip(&masses)
+            .map(|(&value, &mass)| value * mass)
+            .sum::<Vector>()
+            / total
+    };
+    let (centre, drift) = (weighted_mean(&positions), weighted_mean(&velocities));
+    positions
+        .iter_mut()
+        .for_each(|position| *position -= centre);
+    velocities
+        .iter_mut()
+        .for_each(|velocity| *velocity -= drift);
+
+    (masses, positions, velocities)
+}
+
+/// Integrate the planetary system in `steps` steps of `dt`, returning the
+/// final positions and the relative energy error after every step
+fn integrate_planets(
+    integrator: &dyn Integrator,
+    dt: Scalar,
+    steps: usize,
+) -> (Vec<Vector>, Vec<Scalar>) {
+    let (masses, mut positions, mut velocities) = planetary_system();
+    let mut field = PointMasses::new(&masses);
+    let initial = energy(&masses, &positions, &velocities);
+
+    let errors = (0..steps)
+        .map(|_| {
+            integrator.step_system(&mut positions, &mut velocities, &mut field, dt);
+            ((energy(&masses, &positions, &velocities) - initial) / initial).abs()
+        })
+        .collect();
+    (positions, errors)
+}
+
+/// Convergence order measured by halving the step from `steps` steps
+fn measured_order(integrator: &dyn Integrator, steps: usize) -> Scalar {
+    let coarse = binary_error(integrator, DURATION, steps);
+    let fine = binary_error(integrator, DURATION, 2 * steps);
+    (coarse / fine).log2()
+}
+
+fn assert_order(integrator: &dyn Integrator, steps: usize) {
+    let expected = integrator.convergence_order() as Scalar;
+    let order = measured_order(integrator, steps);
+    assert!(
+        (order - expected).abs() < 0.25,
+        "{} converges at order {order:.2} from {steps} steps, expected {expected}",
+        integrator.name()
+    );
+}
+
+/// Largest relative energy error over the first and over the last tenth of
+/// `ORBITS` orbits
+fn early_and_late_energy_errors(
+    integrator: &dyn Integrator,
+    steps_per_orbit: usize,
+) -> (Scalar, Scalar) {
+    let errors = energy_errors(
+        integrator,
+        PERIOD / steps_per_orbit as Scalar,
+        ORBITS * steps_per_orbit,
+    );
+    let tenth = errors.len() / 10;
+    let largest = |errors: &[Scalar]| errors.iter().copied().fold(0.0, Scalar::max);
+    (
+        largest(&errors[..tenth]),
+        largest(&errors[errors.len() - tenth..]),
+    )
+}
+
+fn assert_bounded_energy(integrator: &dyn Integrator, steps_per_orbit: usize) {
+    let (early, late) = early_and_late_energy_errors(integrator, steps_per_orbit);
+    assert!(
+        late <= 1.1 * early,
+        "{} energy error grew from {early:.3e} to {late:.3e} over {ORBITS} orbits",
+        integrator.name()
+    );
+}
+
+/// Check that each hundredfold tighter tolerance makes an adaptive
+/// integrator at least ten times more accurate over ten orbits
+fn assert_error_follows_tolerance(adaptive: impl Fn(Scalar) -> Box<dyn Integrator>) {
+    let errors: Vec<Scalar> = [1e-6, 1e-8, 1e-10]
+        .into_iter()
+        .map(|tolerance| binary_error(adaptive(tolerance).as_ref(), 10.0 * PERIOD + DURATION, 80))
+        .collect();
+
+    assert!(
+        errors.windows(2).all(|pair| pair[1] < 0.1 * pair[0]),
+        "errors {errors:?} do not shrink with the tolerance"
+    );
+}
+
+#[test]
+fn yoshida_compositions_converge_at_their_order() {
+    // Steps short enough to be in the asymptotic regime, but long enough
+    // that the finer error stays well above round-off
+    assert_order(&Yoshida4, 128);
+    assert_order(&Yoshida6, 64);
+    assert_order(&Yoshida8, 64);
+}
+
+#[test]
+fn yoshida_compositions_keep_energy_bounded() {
+    assert_bounded_energy(&Yoshida4, 64);
+    assert_bounded_energy(&Yoshida6, 64);
+    assert_bounded_energy(&Yoshida8, 64);
+}
+
+/// Fourth-order Yoshida triple jump written as drift and kick coefficients
+fn yoshida4_coefficients() -> (Vec<Scalar>, Vec<Scalar>) {
+    let cube_root = 2.0_f64.cbrt();
+    let outer = 1.0 / (2.0 - cube_root);
+    let inner = -cube_root / (2.0 - cube_root);
+    (
+        vec![
+            0.5 * outer,
+            0.5 * (outer + inner),
+            0.5 * (outer + inner),
+            0.5 * outer,
+        ],
+        vec![outer, inner, outer],
+    )
+}
+
+#[test]
+fn composition_rejects_inconsistent_coefficients() {
+    let invalid = [
+        (vec![1.0], vec![], None),
+        (vec![0.5, 0.25, 0.25], vec![1.0], None),
+        (vec![0.5, 0.5], vec![0.5, 0.5, 0.0], None),
+        (vec![0.5, 0.6], vec![1.0], None),
+        (vec![0.5, 0.5], vec![0.9], None),
+        (vec![Scalar::NAN, 1.0], vec![1.0], None),
+        (vec![0.5, 0.5], vec![1.0], Some(0)),
+    ];
+
+    for (drift, kick, order) in invalid {
+        assert!(
+            Composition::new(drift.clone(), kick.clone(), order).is_err(),
+            "drift {drift:?}, kick {kick:?}, order {order:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn composition_estimates_its_order() {
+    let verlet = Composition::new(vec![0.5, 0.5], vec![1.0], None).unwrap();
+    assert_eq!(verlet.convergence_order(), 2);
+
+    let (drift, kick) = yoshida4_coefficients();
+    let yoshida = Composition::new(drift, kick, None).unwrap();
+    assert_eq!(yoshida.convergence_order(), 4);
+    assert_order(&yoshida, 128);
+
+    let euler = Composition::new(vec![1.0], vec![1.0], None).unwrap();
+    assert_eq!(euler.convergence_order(), 1);
+    assert_eq!(
+        Composition::new(vec![1.0], vec![1.0], Some(3))
+            .unwrap()
+            .convergence_order(),
+        3,
+        "a declared order is taken as given"
+    );
+}
+
+#[test]
+fn composition_detects_symmetric_sequences() {
+    let symmetric = [
+        (vec![0.5, 0.5], vec![1.0]),
+        // Kick-drift-kick, starting with a zero drift
+        (vec![0.0, 1.0], vec![0.5, 0.5]),
+        yoshida4_coefficients(),
+    ];
+    for (drift, kick) in symmetric {
+        let composition = Composition::new(drift, kick, Some(2)).unwrap();
+        assert!(
+            composition.is_time_reversible(),
+            "{composition:?} should be time-reversible"
+        );
+    }
+
+    let asymmetric = [
+        (vec![1.0], vec![1.0]),
+        (vec![0.25, 0.75], vec![1.0]),
+        (vec![0.5, 0.5], vec![0.25, 0.75]),
+    ];
+    for (drift, kick) in asymmetric {
+        let composition = Composition::new(drift, kick, Some(1)).unwrap();
+        assert!(
+            !composition.is_time_reversible(),
+            "{composition:?} should not be time-reversible"
+        );
+    }
+}
+
+#[test]
+fn composition_configures_from_partial_parameters() {
+    let parameters = |toml: &str| toml.parse::<IntegratorParameters>().unwrap();
+    let composition = Composition::default();
+
+    // Without parameters the composition keeps its coefficients and order
+    let configured = composition.configure(&parameters("")).unwrap();
+    assert_eq!(configured.convergence_order(), 2);
+    assert_eq!(
+        configured.force_evaluations(),
+        composition.force_evaluations()
+    );
+
+    let configured = composition.configure(&parameters("order = 3")).unwrap();
+    assert_eq!(configured.convergence_order(), 3);
+
+    // New coefficients have their order estimated unless it is declared
+    let configured = composition
+        .configure(&parameters("drift = [1.0]\nkick = [1.0]"))
+        .unwrap();
+    assert_eq!(configured.convergence_order(), 1);
+    assert!(!configured.is_time_reversible());
+
+    // Coefficients left out are taken from the composition being configured,
+    // here giving two symplectic Euler half steps
+    let configured = composition
+        .configure(&parameters("kick = [0.5, 0.5]"))
+        .unwrap();
+    assert_eq!(configured.convergence_order(), 1);
+    assert!(composition.configure(&parameters("kick = [0.5]")).is_err());
+
+    assert!(
+        composition
+            .configure(&parameters("tolerance = 1e-6"))
+            .is_err()
+    );
+}
+
+#[test]
+fn hermite_converges_at_fourth_order() {
+    assert_order(&Hermite4, 128);
+}
+
+#[test]
+fn hermite_energy_drift_stays_small() {
+    // Hermite is not symplectic, so its energy error drifts rather than
+    // oscillating, but the drift shrinks at least as fast as the error
+    let late_error = |steps_per_orbit| early_and_late_energy_errors(&Hermite4, steps_per_orbit).1;
+    let (coarse, fine) = (late_error(128), late_error(256));
+
+    assert!(
+        fine < 1e-5,
+        "relative energy error reached {fine:.3e} over {ORBITS} orbits"
+    );
+    assert!(
+        coarse > 16.0 * fine,
+        "halving the step only reduced the energy drift from {coarse:.3e} to {fine:.3e}"
+    );
+}
+
+#[test]
+fn ias15_follows_a_binary_to_machine_precision() {
+    // Eight steps an orbit, each split internally as the error control needs
+    let error = binary_error(&Ias15::default(), 10.0 * PERIOD + DURATION, 80);
+    assert!(error < 1e-12, "position error {error:.3e} after ten orbits");
+
+    let errors = energy_errors(&Ias15::default(), PERIOD / 8.0, ORBITS * 8);
+    let largest = errors.iter().copied().fold(0.0, Scalar::max);
+    assert!(
+        largest < 1e-13,
+        "relative energy error reached {largest:.3e} over {ORBITS} orbits"
+    );
+}
+
+#[test]
+fn dormand_prince_converges_at_fifth_order() {
+    assert_order(&DormandPrince::fixed(), 128);
+}
+
+#[test]
+fn adaptive_dormand_prince_follows_its_tolerance() {
+    assert_error_follows_tolerance(|tolerance| {
+        Box::new(DormandPrince::adaptive(tolerance).unwrap())
+    });
+}
+
+#[test]
+fn bulirsch_stoer_follows_its_tolerance() {
+    assert_error_follows_tolerance(|tolerance| Box::new(BulirschStoer::new(tolerance).unwrap()));
+
+    // The extrapolation reaches an even order of at least four
+    let integrator = BulirschStoer::default();
+    integrate_binary(&integrator, DURATION, 4);
+    let order = integrator.report().order.unwrap();
+    assert!(
+        order >= 4 && order % 2 == 0,
+        "last step was of order {order}"
+    );
+}
+
+#[test]
+fn adaptive_integrators_start_afresh_after_reset() {
+    let integrators: [Box<dyn Integrator>; 3] = [
+        Box::new(Ias15::default()),
+        Box::new(DormandPrince::adaptive(1e-8).unwrap()),
+        Box::new(BulirschStoer::default()),
+    ];
+
+    for integrator in &integrators {
+        let fresh = integrate_binary(integrator.clone_box().as_ref(), DURATION, 8);
+
+        integrate_binary(integrator.as_ref(), PERIOD, 3);
+        integrator.reset();
+        assert_eq!(integrator.report().step_size, None);
+        assert_eq!(
+            integrate_binary(integrator.as_ref(), DURATION, 8),
+            fresh,
+            "{} should not carry its state across a reset",
+            integrator.name()
+        );
+
+        // A step far shorter than the last is taken as if from the start
+        let (mut positions, mut velocities) = binary();
+        let (mut fresh_positions, mut fresh_velocities) = binary();
+        let mut field = PointMasses::new(&MASSES);
+        let short_step = DURATION / 8.0 / 100.0;
+        integrator.step_system(&mut positions, &mut velocities, &mut field, short_step);
+        integrator.clone_box().step_system(
+            &mut fresh_positions,
+            &mut fresh_velocities,
+            &mut field,
+            short_step,
+        );
+        assert_eq!(
+            (positions, velocities),
+            (fresh_positions, fresh_velocities),
+            "{} should start afresh after an abrupt change of step",
+            integrator.name()
+        );
+    }
+}
+
+#[test]
+fn gauss_legendre_converges_at_its_order() {
+    assert_order(&GaussLegendre4::default(), 64);
+    assert_order(&GaussLegendre6::default(), 32);
+}
+
+#[test]
+fn gauss_legendre_keeps_energy_bounded() {
+    assert_bounded_energy(&GaussLegendre4::default(), 64);
+    assert_bounded_energy(&GaussLegendre6::default(), 64);
+}
+
+#[test]
+fn gauss_legendre_forgets_unconverged_steps_on_reset() {
+    let parameters = "tolerance = 1e-15\nmax_iterations = 1"
+        .parse::<IntegratorParameters>()
+        .unwrap();
+    let integrators = [
+        GaussLegendre4::default().configure(&parameters).unwrap(),
+        GaussLegendre6::default().configure(&parameters).unwrap(),
+    ];
+
+    for integrator in &integrators {
+        integrate_binary(integrator.as_ref(), DURATION, 4);
+        let unconverged = integrator.report().unconverged_steps.unwrap();
+        assert!(unconverged > 0, "{} should not converge", integrator.name());
+
+        integrator.reset();
+        assert_eq!(
+            integrator.report().unconverged_steps,
+            Some(0),
+            "{} should forget its unconverged steps",
+            integrator.name()
+        );
+    }
+}
+
+fn wisdom_holman_variants() -> [WisdomHolman; 2] {
+    [
+        WisdomHolman::new(WisdomHolmanCoordinates::Jacobi),
+        WisdomHolman::new(WisdomHolmanCoordinates::DemocraticHeliocentric),
+    ]
+}
+
+#[test]
+fn jacobi_wisdom_holman_is_exact_on_a_binary() {
+    // In Jacobi coordinates a binary is a single Kepler orbit with no
+    // interaction left, so steps longer than an orbit are still exact.
+    // Democratic heliocentric coordinates keep the jump of the central body
+    // and are not.
+    let integrator = WisdomHolman::new(WisdomHolmanCoordinates::Jacobi);
+    let error = binary_error(&integrator, 10.0 * PERIOD + DURATION, 8);
+    assert!(error < 1e-12, "position error {error:.3e} after ten orbits");
+}
+
+#[test]
+fn wisdom_holman_converges_at_second_order() {
+    // Without an exact solution the order follows from how fast the
+    // differences between runs at successively halved steps shrink
+    let duration = 20.0;
+    for integrator in &wisdom_holman_variants() {
+        let positions: Vec<Vec<Vector>> = [64, 128, 256]
+            .into_iter()
+            .map(|steps| integrate_planets(integrator, duration / steps as Scalar, steps).0)
+            .collect();
+        let differences: Vec<Scalar> = positions
+            .windows(2)
+            .map(|pair| {
+                pair[0]
+                    .iter()
+                    .zip(&pair[1])
+                    .map(|(coarse, fine)| coarse.distance(*fine))
+                    .fold(0.0, Scalar::max)
+            })
+            .collect();
+
+        let order = (differences[0] / differences[1]).log2();
+        assert!(
+            (order - 2.0).abs() < 0.25,
+            "{:?} converges at order {order:.2}",
+            integrator.coordinates()
+        );
+    }
+}
+
+#[test]
+fn wisdom_holman_keeps_energy_bounded() {
+    // Forty steps an orbit of the outer planet
+    let outer_period = TAU * 1.6_f64.powf(1.5);
+    let steps = ORBITS * 40;
+    let dt = outer_period / 40.0;
+    let largest = |errors: &[Scalar]| errors.iter().copied().fold(0.0, Scalar::max);
+    let (_, leapfrog) = integrate_planets(&VelocityVerlet, dt, steps);
+
+    for integrator in &wisdom_holman_variants() {
+        let (_, errors) = integrate_planets(integrator, dt, steps);
+        let tenth = steps / 10;
+        let (early, late) = (largest(&errors[..tenth]), largest(&errors[steps - tenth..]));
+        // The planets slowly exchange energy, so the oscillation of the error
+        // varies a little from one orbit to the next
+        assert!(
+            late <= 1.5 * early,
+            "{:?} energy error grew from {early:.3e} to {late:.3e}",
+            integrator.coordinates()
+        );
+
+        // Only the interaction between the planets limits the accuracy, not
+        // the attraction of the star
+        assert!(
+            largest(&errors) < 0.01 * largest(&leapfrog),
+            "{:?} energy error {:.3e} against {:.3e} for leapfrog",
+            integrator.coordinates(),
+            largest(&errors),
+            largest(&leapfrog)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! The linear octree reproduces the forces, jerks, and subdivision of the
+//! boxed octree
+
+mod common;
+
+use bevy::ecs::entity::Entity;
+use common::{G, MAX_FORCE, MIN_DISTANCE, body, random_vector};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::gravity::GravitySolver;
+use stardrift::physics::linear_octree::LinearOctree;
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{MultipoleOrder, Octree, OctreeBody};
+
+const TOLERANCE: Scalar = 1e-10;
+
+/// Bodies in a few dense clusters inside a sparse background, so that the
+/// tree is deep in places and shallow in others
+fn clustered(count: usize, seed: u64) -> Vec<OctreeBody> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let centers: Vec<Vector> = (0..4).map(|_| random_vector(&mut rng, 10.0)).collect();
+
+    (0..count)
+        .map(|i| {
+            let position = if i % 5 == 0 {
+                random_vector(&mut rng, 12.0)
+            } else {
+                centers[i % centers.len()] + random_vector(&mut rng, 0.2)
+            };
+            let velocity = random_vector(&mut rng, 1.0);
+            body(i, position, velocity, rng.random_range(0.01..1.0))
+        })
+        .collect()
+}
+
+/// Largest relative difference between matching vectors
+fn max_relative_difference(actual: &[Vector], expected: &[Vector]) -> Scalar {
+    actual
+        .iter()
+        .zip(expected)
+        .map(|(actual, expected)| (*actual - *expected).length() / expected.length())
+        .fold(0.0, Scalar::max)
+}
+
+fn assert_matches_boxed(bodies: &[OctreeBody], theta: Scalar, leaf_threshold: usize) {
+    for multipole_order in [MultipoleOrder::Monopole, MultipoleOrder::Quadrupole] {
+        let mut boxed = Octree::new(theta, MIN_DISTANCE, MAX_FORCE)
+            .with_leaf_threshold(leaf_threshold)
+            .with_multipole_order(multipole_order);
+        let mut linear = LinearOctree::new(theta, MIN_DISTANCE, MAX_FORCE)
+            .with_leaf_threshold(leaf_threshold)
+            .with_multipole_order(multipole_order);
+        GravitySolver::build(&mut boxed, bodies);
+        GravitySolver::build(&mut linear, bodies);
+
+        assert_eq!(
+            GravitySolver::bounds(&boxed).len(),
+            GravitySolver::bounds(&linear).len(),
+            "both trees should have the same nodes"
+        );
+
+        let mut expected = vec![Vector::ZERO; bodies.len()];
+        let mut actual = vec![Vector::ZERO; bodies.len()];
+        boxed.forces(bodies, G, &mut expected);
+        linear.forces(bodies, G, &mut actual);
+        let force_difference = max_relative_difference(&actual, &expected);
+
+        let mut expected = vec![(Vector::ZERO, Vector::ZERO); bodies.len()];
+        let mut actual = vec![(Vector::ZERO, Vector::ZERO); bodies.len()];
+        boxed.forces_and_jerks(bodies, G, &mut expected);
+        linear.forces_and_jerks(bodies, G, &mut actual);
+        let (actual_jerks, expected_jerks): (Vec<Vector>, Vec<Vector>) = actual
+            .iter()
+            .zip(&expected)
+            .map(|(actual, expected)| (actual.1, expected.1))
+            .unzip();
+        let jerk_difference = max_relative_difference(&actual_jerks, &expected_jerks);
+
+        assert!(
+            force_difference < TOLERANCE && jerk_difference < TOLERANCE,
+            "theta {theta}, leaf threshold {leaf_threshold}, {multipole_order:?}: forces differ \
+             by {force_difference:.3e} and jerks by {jerk_difference:.3e}"
+        );
+    }
+}
+
+#[test]
+fn matches_boxed_octree() {
+    let bodies = clustered(2_000, 7);
+
+    for theta in [0.0, 0.5, 1.0] {
+        for leaf_threshold in [1, 4, 16] {
+            assert_matches_boxed(&bodies, theta, leaf_threshold);
+        }
+    }
+}
+
+#[test]
+fn matches_boxed_octree_for_flat_and_coincident_bodies() {
+    // A disk with no extent along z, plus bodies stacked on one point that
+    // subdivide down to the maximum depth
+    let mut bodies = clustered(1_000, 11);
+    for body in &mut bodies {
+        body.position.z = 0.0;
+    }
+    for body in bodies.iter_mut().step_by(100) {
+        body.position = Vector::new(1.0, 2.0, 0.0);
+    }
+
+    assert_matches_boxed(&bodies, 0.5, 1);
+}
+
+#[test]
+fn rebuilds_reuse_buffers_without_stale_nodes() {
+    let mut linear = LinearOctree::new(0.5, MIN_DISTANCE, MAX_FORCE);
+
+    let large = clustered(2_000, 3);
+    GravitySolver::build(&mut linear, &large);
+    let small = clustered(10, 5);
+    GravitySolver::build(&mut linear, &small);
+
+    let mut boxed = Octree::new(0.5, MIN_DISTANCE, MAX_FORCE);
+    GravitySolver::build(&mut boxed, &small);
+    assert_eq!(
+        GravitySolver::bounds(&boxed).len(),
+        GravitySolver::bounds(&linear).len()
+    );
+
+    GravitySolver::build(&mut linear, &[]);
+    assert!(GravitySolver::bounds(&linear).is_empty());
+    assert_eq!(
+        linear.calculate_force_at_position(Vector::ZERO, 1.0, Entity::PLACEHOLDER, G),
+        Vector::ZERO
+    );
+}
@@ -0,0 +1,129 @@
+//! Quadrupole moments cut the Barnes-Hut force error against direct summation,
+//! and enter the jerk as the time derivative of their force
+
+mod common;
+
+use bevy::ecs::entity::Entity;
+use common::{G, MAX_FORCE, MIN_DISTANCE, exact_forces, rms_relative_error, uniform_sphere};
+use stardrift::physics::gravity::GravitySolver;
+use stardrift::physics::linear_octree::LinearOctree;
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{MultipoleOrder, Octree, OctreeBody};
+
+const BODY_COUNT: usize = 2_000;
+
+/// Root-mean-square relative force error of the octree against `exact`
+fn octree_error(
+    bodies: &[OctreeBody],
+    exact: &[Vector],
+    theta: Scalar,
+    multipole_order: MultipoleOrder,
+) -> Scalar {
+    let mut octree = Octree::new(theta, MIN_DISTANCE, MAX_FORCE)
+        .with_leaf_threshold(4)
+        .with_multipole_order(multipole_order);
+    octree.build(bodies.iter().copied());
+
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    octree.forces(bodies, G, &mut forces);
+    rms_relative_error(&forces, exact)
+}
+
+#[test]
+fn quadrupole_reduces_force_error_at_same_theta() {
+    let bodies = uniform_sphere(BODY_COUNT, 7);
+    let exact = exact_forces(&bodies);
+
+    for theta in [0.3, 0.5, 0.8] {
+        let monopole = octree_error(&bodies, &exact, theta, MultipoleOrder::Monopole);
+        let quadrupole = octree_error(&bodies, &exact, theta, MultipoleOrder::Quadrupole);
+
+        assert!(
+            quadrupole < 0.5 * monopole,
+            "quadrupole error {quadrupole:.3e} should be well below monopole error \
+             {monopole:.3e} at theta {theta}"
+        );
+    }
+}
+
+#[test]
+fn quadrupole_matches_direct_summation_when_exact() {
+    let bodies = uniform_sphere(BODY_COUNT, 11);
+    let exact = exact_forces(&bodies);
+
+    // With theta = 0 no node is approximated, so the multipole order must
+    // not change anything
+    let error = octree_error(&bodies, &exact, 0.0, MultipoleOrder::Quadrupole);
+    assert!(
+        error < 1e-12,
+        "theta = 0 should reproduce direct summation, got {error:.3e}"
+    );
+}
+
+#[test]
+fn quadrupole_jerk_is_the_derivative_of_the_quadrupole_force() {
+    // An elongated cluster drifting as a whole, so its quadrupole moment is
+    // constant, seen from far enough that its root is approximated
+    let cluster_velocity = Vector::new(0.3, -0.2, 0.1);
+    let cluster = |time: Scalar| -> Vec<OctreeBody> {
+        uniform_sphere(BODY_COUNT, 13)
+            .into_iter()
+            .map(|body| OctreeBody {
+                position: body.position * Vector::new(2.0, 0.5, 0.5) + cluster_velocity * time,
+                velocity: cluster_velocity,
+                ..body
+            })
+            .collect()
+    };
+    let exclude = Entity::from_raw_u32(u32::MAX - 1).unwrap();
+    let (position, velocity) = (Vector::new(14.0, 9.0, -11.0), Vector::new(-0.5, 0.4, 0.2));
+    let step = 1e-4;
+
+    let boxed = |time, multipole_order| {
+        let mut octree = Octree::new(0.7, MIN_DISTANCE, MAX_FORCE)
+            .with_leaf_threshold(4)
+            .with_multipole_order(multipole_order);
+        octree.build(cluster(time));
+        octree
+    };
+    let linear = |time, multipole_order| {
+        let mut octree = LinearOctree::new(0.7, MIN_DISTANCE, MAX_FORCE)
+            .with_leaf_threshold(4)
+            .with_multipole_order(multipole_order);
+        octree.build(&cluster(time));
+        octree
+    };
+
+    let force_at = |time: Scalar| {
+        boxed(time, MultipoleOrder::Quadrupole).calculate_force_at_position(
+            position + velocity * time,
+            1.0,
+            exclude,
+            G,
+        )
+    };
+    let derivative = (force_at(step) - force_at(-step)) / (2.0 * step);
+
+    let (monopole_force, monopole_jerk) = boxed(0.0, MultipoleOrder::Monopole)
+        .calculate_force_and_jerk_at_position(position, velocity, 1.0, exclude, G);
+    let results = [
+        boxed(0.0, MultipoleOrder::Quadrupole)
+            .calculate_force_and_jerk_at_position(position, velocity, 1.0, exclude, G),
+        linear(0.0, MultipoleOrder::Quadrupole)
+            .calculate_force_and_jerk_at_position(position, velocity, 1.0, exclude, G),
+    ];
+
+    for (force, jerk) in results {
+        assert!((force - force_at(0.0)).length() < 1e-12 * force.length());
+        assert!(
+            (force - monopole_force).length() > 1e-4 * force.length(),
+            "the quadrupole term should be significant, force {force} against {monopole_force}"
+        );
+
+        let quadrupole_jerk = jerk - monopole_jerk;
+        assert!(
+            (jerk - derivative).length() < 1e-4 * quadrupole_jerk.length(),
+            "jerk {jerk} against force derivative {derivative}, quadrupole term {quadrupole_jerk}"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Radius and k-nearest-neighbour queries on the octree match brute force
+
+mod common;
+
+use bevy::ecs::entity::Entity;
+use common::random_vector;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{Octree, OctreeBody};
+
+/// Bodies uniformly filling a cube of side two, and a tight cluster within
+/// it that forces deep subdivision
+fn random_bodies(count: usize, seed: u64) -> Vec<OctreeBody> {
+    common::random_bodies(count, seed)
+        .into_iter()
+        .enumerate()
+        .map(|(i, body)| OctreeBody {
+            position: if i % 4 == 0 {
+                body.position * 0.01
+            } else {
+                body.position
+            },
+            ..body
+        })
+        .collect()
+}
+
+fn octree(bodies: &[OctreeBody], leaf_threshold: usize) -> Octree {
+    let mut octree = Octree::new(0.5, 0.01, 1e12).with_leaf_threshold(leaf_threshold);
+    octree.build(bodies.iter().copied());
+    octree
+}
+
+/// Every body and its distance from `position`, nearest first
+fn brute_force(bodies: &[OctreeBody], position: Vector) -> Vec<(Entity, Scalar)> {
+    let mut all: Vec<(Entity, Scalar)> = bodies
+        .iter()
+        .map(|body| (body.entity, body.position.distance(position)))
+        .collect();
+    all.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    all
+}
+
+fn query_positions(rng: &mut ChaCha8Rng) -> Vec<Vector> {
+    (0..50)
+        .map(|_| random_vector(rng, 1.5))
+        .chain([Vector::ZERO, Vector::splat(5.0)])
+        .collect()
+}
+
+#[test]
+fn radius_queries_match_brute_force() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let bodies = random_bodies(2_000, 2);
+
+    for leaf_threshold in [1, 8] {
+        let octree = octree(&bodies, leaf_threshold);
+
+        for position in query_positions(&mut rng) {
+            for radius in [0.0, 0.005, 0.1, 0.4, 10.0] {
+                let expected: Vec<(Entity, Scalar)> = brute_force(&bodies, position)
+                    .into_iter()
+                    .filter(|&(_, distance)| distance <= radius)
+                    .collect();
+                let found = octree.bodies_within(position, radius);
+
+                assert_eq!(
+                    found, expected,
+                    "radius {radius} around {position}, leaf threshold {leaf_threshold}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn nearest_neighbour_queries_match_brute_force() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let bodies = random_bodies(2_000, 4);
+
+    for leaf_threshold in [1, 8] {
+        let octree = octree(&bodies, leaf_threshold);
+
+        for position in query_positions(&mut rng) {
+            let all = brute_force(&bodies, position);
+            for k in [1, 2, 7, 32, 500] {
+                assert_eq!(
+                    octree.nearest_neighbors(position, k),
+                    all[..k],
+                    "{k} nearest to {position}, leaf threshold {leaf_threshold}"
+                );
+            }
+        }
+
+        // The neighbours of a body in the tree come after the body itself
+        let body = &bodies[10];
+        let neighbours = octree.nearest_neighbors(body.position, 4);
+        assert_eq!(neighbours[0], (body.entity, 0.0));
+        assert_eq!(neighbours, brute_force(&bodies, body.position)[..4]);
+    }
+}
+
+#[test]
+fn queries_handle_empty_and_small_trees() {
+    let bodies = random_bodies(5, 5);
+    let octree = octree(&bodies, 1);
+
+    assert_eq!(octree.nearest_neighbors(Vector::ZERO, 0), Vec::new());
+    assert_eq!(
+        octree.nearest_neighbors(Vector::ZERO, 10),
+        brute_force(&bodies, Vector::ZERO)
+    );
+
+    let empty = self::octree(&[], 1);
+    assert_eq!(empty.nearest_neighbors(Vector::ZERO, 3), Vec::new());
+    assert_eq!(empty.bodies_within(Vector::ZERO, 1.0), Vec::new());
+}
@@ -0,0 +1,172 @@
+//! Refitting the octree to moved bodies keeps its forces in line with a
+//! fresh build, and falls back to one when the tree no longer fits
+
+mod common;
+
+use common::{G, MAX_FORCE, MIN_DISTANCE, body, exact_forces, random_vector, rms_relative_error};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::gravity::GravitySolver;
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{MultipoleOrder, Octree, OctreeBody, RefitOutcome};
+
+const BODY_COUNT: usize = 2_000;
+
+/// Bodies uniformly filling a cube of half-width 1, each with a velocity
+fn uniform_cube(seed: u64) -> Vec<OctreeBody> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..BODY_COUNT)
+        .map(|i| {
+            let position = random_vector(&mut rng, 1.0);
+            let velocity = random_vector(&mut rng, 1.0);
+            body(i, position, velocity, rng.random_range(0.01..1.0))
+        })
+        .collect()
+}
+
+/// Bodies advanced along their velocities for `dt`
+fn drifted(bodies: &[OctreeBody], dt: Scalar) -> Vec<OctreeBody> {
+    bodies
+        .iter()
+        .map(|body| OctreeBody {
+            position: body.position + body.velocity * dt,
+            ..*body
+        })
+        .collect()
+}
+
+fn forces(solver: &dyn GravitySolver, bodies: &[OctreeBody]) -> Vec<Vector> {
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    solver.forces(bodies, G, &mut forces);
+    forces
+}
+
+fn octree(theta: Scalar, multipole_order: MultipoleOrder) -> Octree {
+    Octree::new(theta, MIN_DISTANCE, MAX_FORCE)
+        .with_leaf_threshold(4)
+        .with_multipole_order(multipole_order)
+        .with_refit(0.25)
+}
+
+#[test]
+fn refit_in_place_reproduces_the_built_tree() {
+    let bodies = uniform_cube(7);
+
+    // Refitting to unmoved bodies recomputes every moment bottom-up, which
+    // must agree with the moments summed over bodies during the build
+    for multipole_order in [MultipoleOrder::Monopole, MultipoleOrder::Quadrupole] {
+        let mut built = octree(0.5, multipole_order);
+        built.build(bodies.iter().copied());
+        let expected = forces(&built, &bodies);
+
+        let mut refitted = octree(0.5, multipole_order);
+        refitted.build(bodies.iter().copied());
+        assert_eq!(
+            refitted.refit(&bodies),
+            RefitOutcome::Refitted { reinserted: 0 }
+        );
+        let actual = forces(&refitted, &bodies);
+
+        let difference = rms_relative_error(&actual, &expected);
+        assert!(
+            difference < 1e-12,
+            "{multipole_order:?}: refit in place changed forces by {difference:.3e}"
+        );
+    }
+}
+
+#[test]
+fn refit_after_small_steps_matches_rebuilt_accuracy() {
+    let start = uniform_cube(11);
+
+    for multipole_order in [MultipoleOrder::Monopole, MultipoleOrder::Quadrupole] {
+        let mut refitted = octree(0.5, multipole_order);
+        refitted.build(start.iter().copied());
+
+        // A few steps, each moving bodies by a small fraction of a leaf
+        let mut reinserted = 0;
+        let mut bodies = start.clone();
+        for _ in 0..5 {
+            bodies = drifted(&bodies, 2e-3);
+            match refitted.refit(&bodies) {
+                RefitOutcome::Refitted { reinserted: count } => reinserted += count,
+                RefitOutcome::Rebuilt => panic!("small steps should not force a rebuild"),
+            }
+        }
+        assert!(reinserted > 0, "some bodies should have changed leaf");
+
+        let exact = exact_forces(&bodies);
+
+        let mut rebuilt = octree(0.5, multipole_order);
+        rebuilt.build(bodies.iter().copied());
+
+        let refit_error = rms_relative_error(&forces(&refitted, &bodies), &exact);
+        let rebuilt_error = rms_relative_error(&forces(&rebuilt, &bodies), &exact);
+        assert!(
+            refit_error < 1.5 * rebuilt_error,
+            "{multipole_order:?}: refit error {refit_error:.3e} against rebuilt \
+             {rebuilt_error:.3e}, {reinserted} reinserted"
+        );
+    }
+}
+
+#[test]
+fn refit_with_exact_forces_matches_direct_summation() {
+    let start = uniform_cube(13);
+    let mut refitted = octree(0.0, MultipoleOrder::Monopole);
+    refitted.build(start.iter().copied());
+
+    let bodies = drifted(&start, 1e-2);
+    assert!(matches!(
+        refitted.refit(&bodies),
+        RefitOutcome::Refitted { .. }
+    ));
+
+    let error = rms_relative_error(&forces(&refitted, &bodies), &exact_forces(&bodies));
+    assert!(
+        error < 1e-12,
+        "every body should be in the tree exactly once, got error {error:.3e}"
+    );
+}
+
+#[test]
+fn refit_falls_back_to_rebuild_when_tree_no_longer_fits() {
+    let bodies = uniform_cube(17);
+    let mut refitted = octree(0.5, MultipoleOrder::Monopole);
+
+    // Nothing to refit yet
+    assert_eq!(refitted.refit(&bodies), RefitOutcome::Rebuilt);
+
+    // A body leaves the root bounds
+    let mut escaped = bodies.clone();
+    escaped[0].position = Vector::splat(10.0);
+    assert_eq!(refitted.refit(&escaped), RefitOutcome::Rebuilt);
+    assert_eq!(refitted.refit(&bodies), RefitOutcome::Rebuilt);
+
+    // A body is removed
+    assert_eq!(refitted.refit(&bodies[1..]), RefitOutcome::Rebuilt);
+    assert_eq!(refitted.refit(&bodies), RefitOutcome::Rebuilt);
+
+    // The system contracts to a fraction of the root
+    let contracted: Vec<OctreeBody> = bodies
+        .iter()
+        .map(|body| OctreeBody {
+            position: body.position * 0.3,
+            ..*body
+        })
+        .collect();
+    assert_eq!(refitted.refit(&contracted), RefitOutcome::Rebuilt);
+    assert_eq!(refitted.refit(&bodies), RefitOutcome::Rebuilt);
+
+    // Too many bodies change leaf
+    let scrambled: Vec<OctreeBody> = bodies
+        .iter()
+        .zip(bodies.iter().rev())
+        .map(|(body, other)| OctreeBody {
+            position: other.position,
+            ..*body
+        })
+        .collect();
+    assert_eq!(refitted.refit(&scrambled), RefitOutcome::Rebuilt);
+}
@@ -0,0 +1,202 @@
+//! Force error against cost of the octree opening criteria on a Plummer
+//! sphere, and agreement between the boxed and linear octrees under each
+
+mod common;
+
+use common::{
+    G, MAX_FORCE, MIN_DISTANCE, exact_forces, plummer_sphere, random_vector, rms_relative_error,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::gravity::GravitySolver;
+use stardrift::physics::linear_octree::LinearOctree;
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{MultipoleOrder, Octree, OctreeBody, OpeningCriterion};
+
+const BODY_COUNT: usize = 4_000;
+
+fn accelerations(bodies: &[OctreeBody], forces: &[Vector]) -> Vec<Vector> {
+    forces
+        .iter()
+        .zip(bodies)
+        .map(|(force, body)| *force / body.mass)
+        .collect()
+}
+
+/// Octree with the criterion and its accuracy parameter, with accelerations
+/// recorded from the exact forces as they would be from the previous step
+fn octree(
+    bodies: &[OctreeBody],
+    exact: &[Vector],
+    criterion: OpeningCriterion,
+    parameter: Scalar,
+) -> Octree {
+    let mut octree = Octree::new(parameter, MIN_DISTANCE, MAX_FORCE)
+        .with_opening_criterion(criterion)
+        .with_relative_accuracy(parameter);
+    octree.build(bodies.iter().copied());
+    octree.record_accelerations(bodies, &accelerations(bodies, exact));
+    octree
+}
+
+/// RMS relative force error and interactions per body
+fn error_and_cost(octree: &Octree, bodies: &[OctreeBody], exact: &[Vector]) -> (Scalar, Scalar) {
+    let before = octree.force_calculation_count();
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    octree.forces(bodies, G, &mut forces);
+    let interactions = octree.force_calculation_count() - before;
+
+    (
+        rms_relative_error(&forces, exact),
+        interactions as Scalar / bodies.len() as Scalar,
+    )
+}
+
+#[test]
+fn error_falls_and_cost_rises_as_each_criterion_tightens() {
+    let bodies = plummer_sphere(BODY_COUNT, 42);
+    let exact = exact_forces(&bodies);
+
+    let sweeps = [
+        (OpeningCriterion::Geometric, [1.0, 0.7, 0.5, 0.3]),
+        (OpeningCriterion::Bmax, [1.0, 0.7, 0.5, 0.3]),
+        (
+            OpeningCriterion::RelativeAcceleration,
+            [0.02, 0.005, 0.0025, 0.0005],
+        ),
+    ];
+
+    for (criterion, parameters) in sweeps {
+        let mut previous = (Scalar::INFINITY, 0.0);
+        for parameter in parameters {
+            let (error, cost) = error_and_cost(
+                &octree(&bodies, &exact, criterion, parameter),
+                &bodies,
+                &exact,
+            );
+            assert!(
+                error < previous.0 && cost > previous.1,
+                "{criterion:?} {parameter}: error {error:.3e} and cost {cost:.0} should improve \
+                 on {:.3e} and {:.0}",
+                previous.0,
+                previous.1
+            );
+            previous = (error, cost);
+        }
+    }
+}
+
+/// α bounds the estimated error of each accepted node rather than the
+/// total, and as α falls more nodes are accepted and their errors add up.
+/// On these spheres the RMS error grows from about 0.35α at α = 0.02 to
+/// 0.8-1.0α at 5·10⁻⁴ and 0.9-1.2α at 2.5·10⁻⁴, so the bound is α down to
+/// 10⁻³ and 1.5α below.
+#[test]
+fn relative_criterion_error_tracks_its_accuracy() {
+    for seed in [7, 8] {
+        let bodies = plummer_sphere(BODY_COUNT, seed);
+        let exact = exact_forces(&bodies);
+
+        for (accuracy, bound) in [(0.01, 1.0), (0.001, 1.0), (0.0005, 1.5), (0.00025, 1.5)] {
+            let octree = octree(
+                &bodies,
+                &exact,
+                OpeningCriterion::RelativeAcceleration,
+                accuracy,
+            );
+            let (error, _) = error_and_cost(&octree, &bodies, &exact);
+            assert!(
+                error < bound * accuracy,
+                "seed {seed}, α = {accuracy}: RMS error {error:.3e} is {:.2}α, above {bound}α",
+                error / accuracy
+            );
+        }
+    }
+}
+
+#[test]
+fn relative_criterion_falls_back_to_geometric_without_accelerations() {
+    let bodies = plummer_sphere(1_000, 3);
+    let exact = exact_forces(&bodies);
+
+    let mut geometric = Octree::new(0.5, MIN_DISTANCE, MAX_FORCE);
+    geometric.build(bodies.iter().copied());
+    let mut relative = Octree::new(0.5, MIN_DISTANCE, MAX_FORCE)
+        .with_opening_criterion(OpeningCriterion::RelativeAcceleration);
+    relative.build(bodies.iter().copied());
+
+    let mut expected = vec![Vector::ZERO; bodies.len()];
+    let mut actual = vec![Vector::ZERO; bodies.len()];
+    geometric.forces(&bodies, G, &mut expected);
+    relative.forces(&bodies, G, &mut actual);
+    assert_eq!(actual, expected);
+
+    // The other criteria ignore recorded accelerations
+    geometric.record_accelerations(&bodies, &accelerations(&bodies, &exact));
+    geometric.forces(&bodies, G, &mut actual);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn linear_octree_matches_boxed_under_every_criterion() {
+    // Random velocities, so that every body has a jerk to compare
+    let mut rng = ChaCha8Rng::seed_from_u64(12);
+    let bodies: Vec<OctreeBody> = plummer_sphere(2_000, 11)
+        .into_iter()
+        .map(|body| OctreeBody {
+            velocity: random_vector(&mut rng, 0.5),
+            ..body
+        })
+        .collect();
+    let exact = exact_forces(&bodies);
+    let accelerations = accelerations(&bodies, &exact);
+
+    for (criterion, parameter) in [
+        (OpeningCriterion::Geometric, 0.7),
+        (OpeningCriterion::Bmax, 0.7),
+        (OpeningCriterion::RelativeAcceleration, 0.005),
+    ] {
+        for multipole_order in [MultipoleOrder::Monopole, MultipoleOrder::Quadrupole] {
+            let mut boxed = Octree::new(parameter, MIN_DISTANCE, MAX_FORCE)
+                .with_multipole_order(multipole_order)
+                .with_opening_criterion(criterion)
+                .with_relative_accuracy(parameter);
+            let mut linear = LinearOctree::new(parameter, MIN_DISTANCE, MAX_FORCE)
+                .with_multipole_order(multipole_order)
+                .with_opening_criterion(criterion)
+                .with_relative_accuracy(parameter);
+            GravitySolver::build(&mut boxed, &bodies);
+            GravitySolver::build(&mut linear, &bodies);
+            GravitySolver::record_accelerations(&mut boxed, &bodies, &accelerations);
+            GravitySolver::record_accelerations(&mut linear, &bodies, &accelerations);
+
+            let mut expected = vec![(Vector::ZERO, Vector::ZERO); bodies.len()];
+            let mut actual = vec![(Vector::ZERO, Vector::ZERO); bodies.len()];
+            boxed.forces_and_jerks(&bodies, G, &mut expected);
+            linear.forces_and_jerks(&bodies, G, &mut actual);
+            let mut expected_forces = vec![Vector::ZERO; bodies.len()];
+            let mut actual_forces = vec![Vector::ZERO; bodies.len()];
+            boxed.forces(&bodies, G, &mut expected_forces);
+            linear.forces(&bodies, G, &mut actual_forces);
+
+            let difference =
+                rms_relative_error(&actual_forces, &expected_forces).max(rms_relative_error(
+                    &actual.iter().map(|&(force, _)| force).collect::<Vec<_>>(),
+                    &expected.iter().map(|&(force, _)| force).collect::<Vec<_>>(),
+                ));
+            assert!(
+                difference < 1e-10,
+                "{criterion:?}, {multipole_order:?}: forces differ by {difference:.3e}"
+            );
+
+            let jerk_difference = rms_relative_error(
+                &actual.iter().map(|&(_, jerk)| jerk).collect::<Vec<_>>(),
+                &expected.iter().map(|&(_, jerk)| jerk).collect::<Vec<_>>(),
+            );
+            assert!(
+                jerk_difference < 1e-10,
+                "{criterion:?}, {multipole_order:?}: jerks differ by {jerk_difference:.3e}"
+            );
+        }
+    }
+}
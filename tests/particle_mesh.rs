@@ -0,0 +1,133 @@
+//! The particle mesh reproduces Newtonian forces at long range, and with the
+//! P3M correction at every range
+
+mod common;
+
+use common::{
+    G, MAX_FORCE, MIN_DISTANCE, body, exact_forces, point_in_unit_ball, rms_relative_error,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use stardrift::physics::gravity::fft::{Complex, Direction, Fft};
+use stardrift::physics::gravity::{GravitySolver, ParticleMesh};
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::OctreeBody;
+use std::f64::consts::TAU;
+
+const SPLIT_SCALE: Scalar = 1.25;
+const P3M_CUTOFF: Scalar = 4.5;
+
+/// Bodies uniformly filling spheres of `radius` around each of `centers`
+fn clusters(centers: &[Vector], radius: Scalar, per_cluster: usize, seed: u64) -> Vec<OctreeBody> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    (0..centers.len() * per_cluster)
+        .map(|i| {
+            let position = centers[i / per_cluster] + point_in_unit_ball(&mut rng) * radius;
+            body(i, position, Vector::ZERO, rng.random_range(0.01..1.0))
+        })
+        .collect()
+}
+
+fn solver_forces(solver: &mut dyn GravitySolver, bodies: &[OctreeBody]) -> Vec<Vector> {
+    solver.build(bodies);
+    let mut forces = vec![Vector::ZERO; bodies.len()];
+    solver.forces(bodies, G, &mut forces);
+    forces
+}
+
+#[test]
+fn fft_matches_discrete_fourier_transform() {
+    let size = 16;
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let signal: Vec<Complex> = (0..size)
+        .map(|_| Complex::new(rng.random_range(-1.0..1.0), rng.random_range(-1.0..1.0)))
+        .collect();
+
+    let fft = Fft::new(size).unwrap();
+    let mut transformed = signal.clone();
+    fft.transform(&mut transformed, Direction::Forward);
+
+    for (k, value) in transformed.iter().enumerate() {
+        let expected = signal
+            .iter()
+            .enumerate()
+            .fold(Complex::ZERO, |sum, (j, sample)| {
+                let angle = -TAU * (j * k) as Scalar / size as Scalar;
+                sum + *sample * Complex::new(angle.cos(), angle.sin())
+            });
+        assert!((value.re - expected.re).abs() < 1e-12);
+        assert!((value.im - expected.im).abs() < 1e-12);
+    }
+
+    fft.transform(&mut transformed, Direction::Inverse);
+    for (value, sample) in transformed.iter().zip(&signal) {
+        let restored = *value * (1.0 / size as Scalar);
+        assert!((restored.re - sample.re).abs() < 1e-12);
+        assert!((restored.im - sample.im).abs() < 1e-12);
+    }
+
+    assert!(Fft::new(12).is_err());
+}
+
+#[test]
+fn mesh_forces_match_newtonian_between_distant_clusters() {
+    let centers = [Vector::new(-1.0, 0.0, 0.0), Vector::new(1.0, 0.3, 0.0)];
+    let bodies = clusters(&centers, 0.1, 200, 3);
+    let exact = exact_forces(&bodies);
+
+    let mut mesh = ParticleMesh::new(32, SPLIT_SCALE, None, MIN_DISTANCE, MAX_FORCE).unwrap();
+    let forces = solver_forces(&mut mesh, &bodies);
+
+    // Forces within a cluster cancel, leaving the pull of the other cluster,
+    // tens of grid cells away
+    let pull: Vector = forces[..200].iter().copied().sum();
+    let expected: Vector = exact[..200].iter().copied().sum();
+    let error = (pull - expected).length() / expected.length();
+    assert!(
+        error < 1e-4,
+        "mesh pull between clusters off by {error:.3e}"
+    );
+}
+
+#[test]
+fn p3m_correction_matches_direct_summation() {
+    let bodies = clusters(&[Vector::ZERO], 1.0, 2_000, 7);
+    let exact = exact_forces(&bodies);
+
+    let mut mesh = ParticleMesh::new(16, SPLIT_SCALE, None, MIN_DISTANCE, MAX_FORCE).unwrap();
+    let mesh_error = rms_relative_error(&solver_forces(&mut mesh, &bodies), &exact);
+
+    let mut p3m =
+        ParticleMesh::new(16, SPLIT_SCALE, Some(P3M_CUTOFF), MIN_DISTANCE, MAX_FORCE).unwrap();
+    let forces = solver_forces(&mut p3m, &bodies);
+    let p3m_error = rms_relative_error(&forces, &exact);
+
+    assert!(p3m_error < 1e-2, "P3M error {p3m_error:.3e} is too large");
+    assert!(
+        p3m_error < 0.1 * mesh_error,
+        "P3M error {p3m_error:.3e} should be a tenth of the mesh-only error {mesh_error:.3e}"
+    );
+
+    // Mass assignment and interpolation share their weights, so the mesh
+    // exerts no net force
+    let net: Vector = forces.iter().copied().sum();
+    let scale: Scalar = forces.iter().map(|force| force.length()).sum();
+    assert!(net.length() < 1e-10 * scale);
+}
+
+#[test]
+fn rejects_invalid_settings() {
+    assert!(ParticleMesh::new(24, SPLIT_SCALE, None, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(ParticleMesh::new(4, SPLIT_SCALE, None, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(ParticleMesh::new(16, 0.0, None, MIN_DISTANCE, MAX_FORCE).is_err());
+    assert!(ParticleMesh::new(16, SPLIT_SCALE, Some(-1.0), MIN_DISTANCE, MAX_FORCE).is_err());
+}
+
+#[test]
+fn rejects_non_finite_scales() {
+    for value in [Scalar::NAN, Scalar::INFINITY] {
+        assert!(ParticleMesh::new(16, value, None, MIN_DISTANCE, MAX_FORCE).is_err());
+        assert!(ParticleMesh::new(16, SPLIT_SCALE, Some(value), MIN_DISTANCE, MAX_FORCE).is_err());
+    }
+}
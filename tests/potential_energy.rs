@@ -0,0 +1,175 @@
+//! Potential and potential energy through the octrees agree with direct
+//! summation, and the potential of an approximated node is consistent with
+//! its force
+
+mod common;
+
+use bevy::ecs::entity::Entity;
+use common::{G, MAX_FORCE, random_bodies};
+use stardrift::physics::gravity::{
+    DirectSummation, FastMultipole, GravitySolver, PairForce, Softening,
+};
+use stardrift::physics::linear_octree::LinearOctree;
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::octree::{MultipoleOrder, Octree, OctreeBody};
+
+const SOFTENING_LENGTH: Scalar = 0.05;
+
+/// Potential energy summed over every pair once
+fn pairwise_energy(bodies: &[OctreeBody], pair_force: &PairForce) -> Scalar {
+    let mut energy = 0.0;
+    for (i, body) in bodies.iter().enumerate() {
+        for other in &bodies[i + 1..] {
+            energy += pair_force.potential(
+                body.position.distance(other.position),
+                G * body.mass * other.mass,
+            );
+        }
+    }
+    energy
+}
+
+#[test]
+fn exact_octrees_match_the_pairwise_energy() {
+    let bodies = random_bodies(500, 1);
+
+    for softening in [Softening::Clamp, Softening::Plummer, Softening::CubicSpline] {
+        let pair_force = PairForce::new(SOFTENING_LENGTH, MAX_FORCE).with_softening(softening);
+        let expected = pairwise_energy(&bodies, &pair_force);
+
+        let mut direct =
+            DirectSummation::new(SOFTENING_LENGTH, MAX_FORCE).with_softening(softening);
+        let mut boxed = Octree::new(0.0, SOFTENING_LENGTH, MAX_FORCE).with_softening(softening);
+        let mut linear =
+            LinearOctree::new(0.0, SOFTENING_LENGTH, MAX_FORCE).with_softening(softening);
+        let solvers: [&mut dyn GravitySolver; 3] = [&mut direct, &mut boxed, &mut linear];
+
+        for solver in solvers {
+            solver.build(&bodies);
+            let energy = solver.potential_energy(&bodies, G).unwrap();
+            assert!(
+                ((energy - expected) / expected).abs() < 1e-12,
+                "{softening:?}: energy {energy} against pairwise {expected}"
+            );
+
+            let body = &bodies[17];
+            let potential = solver.potential_at(body.position, body.entity, G).unwrap();
+            let expected_potential: Scalar = bodies
+                .iter()
+                .filter(|other| other.entity != body.entity)
+                .map(|other| {
+                    pair_force.potential(body.position.distance(other.position), G * other.mass)
+                })
+                .sum();
+            assert!(((potential - expected_potential) / expected_potential).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn approximate_energy_improves_as_theta_falls() {
+    let bodies = random_bodies(3_000, 2);
+    let pair_force = PairForce::new(SOFTENING_LENGTH, MAX_FORCE).with_softening(Softening::Plummer);
+    let expected = pairwise_energy(&bodies, &pair_force);
+
+    for multipole_order in [MultipoleOrder::Monopole, MultipoleOrder::Quadrupole] {
+        let mut previous = Scalar::INFINITY;
+        for theta in [1.0, 0.7, 0.5, 0.3] {
+            let mut boxed = Octree::new(theta, SOFTENING_LENGTH, MAX_FORCE)
+                .with_softening(Softening::Plummer)
+                .with_multipole_order(multipole_order);
+            let mut linear = LinearOctree::new(theta, SOFTENING_LENGTH, MAX_FORCE)
+                .with_softening(Softening::Plummer)
+                .with_multipole_order(multipole_order);
+            boxed.build(bodies.iter().copied());
+            linear.build(&bodies);
+
+            let energy = boxed.potential_energy(&bodies, G);
+            let error = ((energy - expected) / expected).abs();
+
+            let linear_energy = linear.potential_energy(&bodies, G);
+            assert!(((linear_energy - energy) / energy).abs() < 1e-10);
+            assert!(
+                error < previous && error < 1e-3,
+                "{multipole_order:?} θ = {theta}: relative energy error {error:.3e} should fall \
+                 below {previous:.3e} and 1e-3"
+            );
+            previous = error;
+        }
+    }
+}
+
+#[test]
+fn node_potentials_are_consistent_with_node_forces() {
+    // A compact cluster seen from far enough that its root is approximated
+    let bodies = random_bodies(200, 3);
+    let exclude = Entity::from_raw_u32(u32::MAX - 1).unwrap();
+    let position = Vector::new(9.0, -4.0, 6.0);
+
+    for multipole_order in [MultipoleOrder::Monopole, MultipoleOrder::Quadrupole] {
+        let mut octree = Octree::new(0.7, SOFTENING_LENGTH, MAX_FORCE)
+            .with_softening(Softening::Plummer)
+            .with_multipole_order(multipole_order);
+        octree.build(bodies.iter().copied());
+
+        let step = 1e-5;
+        let gradient = Vector::new(
+            octree.calculate_potential_at_position(position + Vector::X * step, exclude, G)
+                - octree.calculate_potential_at_position(position - Vector::X * step, exclude, G),
+            octree.calculate_potential_at_position(position + Vector::Y * step, exclude, G)
+                - octree.calculate_potential_at_position(position - Vector::Y * step, exclude, G),
+            octree.calculate_potential_at_position(position + Vector::Z * step, exclude, G)
+                - octree.calculate_potential_at_position(position - Vector::Z * step, exclude, G),
+        ) / (2.0 * step);
+        let force = octree.calculate_force_at_position(position, 1.0, exclude, G);
+
+        assert!(
+            (force + gradient).length() < 1e-8 * force.length(),
+            "{multipole_order:?}: force {force} against potential gradient {gradient}"
+        );
+    }
+}
+
+#[test]
+fn quadrupole_potential_ignores_the_force_cap() {
+    // The clamp caps the pair force of the heavier body against the cluster,
+    // and leaves the lighter one alone, but not the potential of either
+    let bodies = random_bodies(200, 5);
+    let exclude = Entity::from_raw_u32(u32::MAX - 1).unwrap();
+    let position = Vector::new(9.0, -4.0, 6.0);
+    let max_force = 1.0;
+
+    let build = |multipole_order| {
+        let mut octree =
+            Octree::new(0.7, SOFTENING_LENGTH, max_force).with_multipole_order(multipole_order);
+        octree.build(bodies.iter().copied());
+        octree
+    };
+    let monopole = build(MultipoleOrder::Monopole);
+    let quadrupole = build(MultipoleOrder::Quadrupole);
+
+    let potential_term = quadrupole.calculate_potential_at_position(position, exclude, G)
+        - monopole.calculate_potential_at_position(position, exclude, G);
+    assert_ne!(potential_term, 0.0);
+
+    for (mass, capped) in [(0.1, false), (10.0, true)] {
+        let force_term = quadrupole.calculate_force_at_position(position, mass, exclude, G)
+            - monopole.calculate_force_at_position(position, mass, exclude, G);
+
+        assert_eq!(
+            force_term == Vector::ZERO,
+            capped,
+            "mass {mass}: {force_term}"
+        );
+    }
+}
+
+#[test]
+fn solvers_without_a_potential_report_none() {
+    let bodies = random_bodies(100, 4);
+    let mut fmm = FastMultipole::new(4, 0.5, 8, SOFTENING_LENGTH, MAX_FORCE).unwrap();
+    fmm.build(&bodies);
+
+    assert_eq!(fmm.potential_energy(&bodies, G), None);
+    assert_eq!(fmm.potential_at(Vector::ZERO, bodies[0].entity, G), None);
+}
@@ -0,0 +1,196 @@
+//! Adaptive sub-steps shrink through a close encounter within their bounds
+
+mod common;
+
+use bevy::ecs::entity::Entity;
+use common::body;
+use stardrift::physics::integrators::{SystemAccelerationField, VelocityVerlet};
+use stardrift::physics::math::{Scalar, Vector};
+use stardrift::physics::timestep::{
+    PreviousAccelerations, StepSizer, TimestepCriterion, aarseth_criterion, step_adaptively,
+};
+
+const SOFTENING: Scalar = 1e-3;
+const TICK: Scalar = 1.0 / 16.0;
+const MIN_DT: Scalar = 1e-4;
+const MAX_DT: Scalar = 1.0 / 64.0;
+
+/// Softened point masses with G = 1, summed directly
+struct PointMasses {
+    masses: Vec<Scalar>,
+    positions: Vec<Vector>,
+}
+
+impl SystemAccelerationField for PointMasses {
+    fn update(&mut self, positions: &[Vector]) {
+        self.positions.clear();
+        self.positions.extend_from_slice(positions);
+    }
+
+    fn at(&self, index: usize, position: Vector) -> Vector {
+        self.positions
+            .iter()
+            .zip(&self.masses)
+            .enumerate()
+            .filter(|&(other, _)| other != index)
+            .map(|(_, (&source, &mass))| {
+                let offset = source - position;
+                let distance_squared = offset.length_squared() + SOFTENING * SOFTENING;
+                offset * (mass / (distance_squared * distance_squared.sqrt()))
+            })
+            .sum()
+    }
+}
+
+fn step_sizer(criterion: TimestepCriterion) -> StepSizer {
+    StepSizer {
+        criterion,
+        accuracy: 0.01,
+        softening: 0.01,
+        min_dt: MIN_DT,
+        max_dt: MAX_DT,
+    }
+}
+
+/// Two half-unit masses released nearly head-on, separation 2, so that
+/// they pass within about 0.05 of each other after roughly π time units
+fn near_collision() -> (PointMasses, Vec<Vector>, Vec<Vector>, Vec<Entity>) {
+    let bodies = [
+        body(
+            0,
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(0.0, 0.01, 0.0),
+            0.5,
+        ),
+        body(
+            1,
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, -0.01, 0.0),
+            0.5,
+        ),
+    ];
+
+    (
+        PointMasses {
+            masses: bodies.iter().map(|body| body.mass).collect(),
+            positions: Vec::new(),
+        },
+        bodies.iter().map(|body| body.position).collect(),
+        bodies.iter().map(|body| body.velocity).collect(),
+        bodies.iter().map(|body| body.entity).collect(),
+    )
+}
+
+#[test]
+fn step_sizer_clamps_to_bounds() {
+    let sizer = step_sizer(TimestepCriterion::Acceleration);
+
+    assert_eq!(sizer.step(Vector::ZERO, None), MAX_DT);
+    assert_eq!(sizer.step(Vector::new(1e12, 0.0, 0.0), None), MIN_DT);
+
+    let acceleration = Vector::new(1.0, 0.0, 0.0);
+    let step = sizer.step(acceleration, None);
+    assert!(
+        MIN_DT < step && step < MAX_DT,
+        "moderate acceleration should size the step within bounds, got {step:.3e}"
+    );
+}
+
+#[test]
+fn aarseth_criterion_needs_a_jerk_estimate() {
+    let sizer = step_sizer(TimestepCriterion::Aarseth);
+    let acceleration = Vector::new(1.0, 0.0, 0.0);
+    let jerk = Vector::new(0.0, 20.0, 0.0);
+
+    assert_eq!(
+        sizer.step(acceleration, Some(jerk)),
+        aarseth_criterion(acceleration, jerk, 0.01)
+    );
+    assert_eq!(
+        sizer.step(acceleration, None),
+        step_sizer(TimestepCriterion::Acceleration).step(acceleration, None),
+        "without a jerk estimate the acceleration criterion applies"
+    );
+}
+
+#[test]
+fn quiet_system_takes_maximum_steps() {
+    let (mut field, mut positions, mut velocities, entities) = near_collision();
+    let sizer = step_sizer(TimestepCriterion::Acceleration);
+
+    let report = step_adaptively(
+        &VelocityVerlet,
+        &mut positions,
+        &mut velocities,
+        &mut field,
+        &entities,
+        TICK,
+        64,
+        &mut PreviousAccelerations::default(),
+        |acceleration, jerk| sizer.step(acceleration, jerk),
+    );
+
+    assert_eq!(report.substeps, 4, "a tick should split into max_dt steps");
+    assert_eq!(report.shortest_step, MAX_DT);
+}
+
+#[test]
+fn steps_shrink_through_close_encounter() {
+    let (mut field, mut positions, mut velocities, entities) = near_collision();
+    let sizer = step_sizer(TimestepCriterion::Acceleration);
+    let mut previous = PreviousAccelerations::default();
+
+    let mut shortest_step = TICK;
+    let mut closest_approach = Scalar::INFINITY;
+    for _ in 0..64 {
+        let report = step_adaptively(
+            &VelocityVerlet,
+            &mut positions,
+            &mut velocities,
+            &mut field,
+            &entities,
+            TICK,
+            1024,
+            &mut previous,
+            |acceleration, jerk| sizer.step(acceleration, jerk),
+        );
+        assert!(report.substeps <= 1024);
+        shortest_step = shortest_step.min(report.shortest_step);
+        closest_approach = closest_approach.min((positions[0] - positions[1]).length());
+    }
+
+    assert!(
+        closest_approach < 0.2,
+        "bodies should pass close to each other, closest {closest_approach:.3e}"
+    );
+    assert!(
+        shortest_step < MAX_DT / 10.0,
+        "steps should shrink near pericentre, shortest {shortest_step:.3e}"
+    );
+}
+
+#[test]
+fn substep_limit_covers_the_tick() {
+    let (mut field, mut positions, mut velocities, entities) = near_collision();
+    let step_sizer = step_sizer(TimestepCriterion::Acceleration);
+
+    // Every body asks for at most max_dt, a quarter of the tick
+    let report = step_adaptively(
+        &VelocityVerlet,
+        &mut positions,
+        &mut velocities,
+        &mut field,
+        &entities,
+        TICK,
+        2,
+        &mut PreviousAccelerations::default(),
+        |acceleration, jerk| step_sizer.step(acceleration, jerk),
+    );
+
+    assert_eq!(report.substeps, 2);
+    assert_eq!(
+        report.shortest_step,
+        TICK / 2.0,
+        "capped sub-steps should be lengthened to cover the tick"
+    );
+}